@@ -27,8 +27,9 @@ use std::time::Duration;
 use derive_more::{Display, Error, From};
 use ephyr_serde::seconds;
 use mime::Mime;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use tokio::time;
 use url::Url;
 
 /// [API] of [allatra.video][1] site.
@@ -45,22 +46,82 @@ impl Api {
     /// [1]: https://allatra.video
     pub const V1_URL: &'static str = "https://api.allatra.video/api/v1";
 
+    /// Maximum number of times a failed API request is retried, if the failure
+    /// is considered as transient (see [`Error::is_transient()`]).
+    pub const MAX_RETRIES: u32 = 3;
+
+    /// Delay before the first retry of a failed API request, which is doubled
+    /// on each next retry.
+    pub const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+    /// Timeout of a single API request.
+    pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
     /// Performs `GET /videos/yt/{youTubeHash}` API request, returning the
     /// parsed [`Video`], if any.
     ///
+    /// Transient failures (timeouts and `5xx` responses) are retried up to
+    /// [`Api::MAX_RETRIES`] times with an exponential backoff.
+    ///
     /// # Errors
     ///
     /// If API request cannot be performed, or fails. See [`Error`](enum@Error)
     /// for details.
+    #[inline]
     pub async fn get_videos_yt(id: &YoutubeId) -> Result<Video, Error> {
-        let resp = reqwest::get(&format!("{}/videos/yt/{}", Api::V1_URL, id))
+        Self::get_videos_yt_at(Self::V1_URL, id).await
+    }
+
+    /// Performs `GET /videos/yt/{youTubeHash}` API request to the API located
+    /// at the given `base` [URL], retrying transient failures.
+    ///
+    /// # Errors
+    ///
+    /// If API request cannot be performed, or fails.
+    ///
+    /// [URL]: https://en.wikipedia.org/wiki/URL
+    async fn get_videos_yt_at(
+        base: &str,
+        id: &YoutubeId,
+    ) -> Result<Video, Error> {
+        let url = format!("{}/videos/yt/{}", base, id);
+        let mut backoff = Self::INITIAL_BACKOFF;
+        let mut retries = 0;
+        loop {
+            match Self::get_json::<Video>(&url).await {
+                Err(e) if e.is_transient() && retries < Self::MAX_RETRIES => {
+                    retries += 1;
+                    time::delay_for(backoff).await;
+                    backoff *= 2;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Performs a single `GET` HTTP request to the given `url`, decoding its
+    /// JSON [`Response`].
+    ///
+    /// # Errors
+    ///
+    /// If HTTP request cannot be performed, or fails.
+    async fn get_json<T>(url: &str) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let resp = reqwest::Client::builder()
+            .timeout(Self::REQUEST_TIMEOUT)
+            .build()
+            .map_err(Error::RequestFailed)?
+            .get(url)
+            .send()
             .await
             .map_err(Error::RequestFailed)?;
         if !resp.status().is_success() {
             return Err(Error::BadStatus(resp.status()));
         }
         Ok(resp
-            .json::<Response<Video>>()
+            .json::<Response<T>>()
             .await
             .map_err(Error::BadBody)?
             .data)
@@ -85,6 +146,22 @@ pub enum Error {
     BadBody(reqwest::Error),
 }
 
+impl Error {
+    /// Indicates whether this [`Error`] is a transient one, so the failed
+    /// request is worth retrying.
+    ///
+    /// Only timeouts, connection failures and `5xx` responses are considered
+    /// transient, while `4xx` responses (like non-existing video) are not.
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::RequestFailed(e) => e.is_timeout() || e.is_connect(),
+            Self::BadStatus(s) => s.is_server_error(),
+            Self::BadBody(_) => false,
+        }
+    }
+}
+
 /// Successful response, returned by [allatra.video][1] site API.
 ///
 /// [1]: https://allatra.video
@@ -198,8 +275,77 @@ pub enum Resolution {
 
 #[cfg(test)]
 mod spec {
+    use std::{
+        io::{Read as _, Write as _},
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
     use super::*;
 
+    /// Spawns a mock HTTP server responding with the given `statuses` one by
+    /// one (and with `200 OK` once they're exhausted), returning its base URL
+    /// and a counter of the received requests.
+    fn mock_api(statuses: &'static [u16]) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let _ = thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buf = [0; 1024];
+                let _ = stream.read(&mut buf).unwrap();
+
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let status = statuses.get(n).copied().unwrap_or(200);
+                let body = if status == 200 {
+                    r#"{"data":{"youtubeId":"abc","duration":60,"sources":[]}}"#
+                } else {
+                    ""
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nConnection: close\r\n\
+                     Content-Type: application/json\r\n\
+                     Content-Length: {}\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body,
+                )
+                .unwrap();
+            }
+        });
+        (url, hits)
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let (url, hits) = mock_api(&[503, 500]);
+
+        let res = Api::get_videos_yt_at(&url, &"abc".into()).await;
+        assert!(res.is_ok(), "failed to request video: {}", res.unwrap_err());
+        assert_eq!(res.unwrap().duration, Duration::from_secs(60));
+        assert_eq!(hits.load(Ordering::SeqCst), 3, "incorrect retries");
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (url, hits) = mock_api(&[404]);
+
+        let res = Api::get_videos_yt_at(&url, &"abc".into()).await;
+        assert!(
+            matches!(res, Err(Error::BadStatus(s)) if s.as_u16() == 404),
+            "unexpected result: {:?}",
+            res,
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 1, "incorrect retries");
+    }
+
     #[tokio::test]
     async fn retrieves_truth_of_life() {
         let res = Api::get_videos_yt(&"Q69gFVmrCiI".into()).await;
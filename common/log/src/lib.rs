@@ -20,17 +20,23 @@
     unused_results
 )]
 
+use std::str::FromStr;
+
 pub use slog::{self, Drain};
 pub use slog_scope::{self as log, logger};
 
 /// Initializes global logger with the given verbosity `level` ([`Info`] by
-/// default, if [`None`]), returning its guard that should be held as long as
-/// program runs.
+/// default, if [`None`]) and per-module `filters` overriding it, returning its
+/// guard that should be held as long as program runs.
 ///
 /// [`Info`]: slog::Level::Info
-pub fn init(level: Option<slog::Level>) -> slog_scope::GlobalLoggerGuard {
+pub fn init(
+    level: Option<slog::Level>,
+    filters: ModuleFilters,
+) -> slog_scope::GlobalLoggerGuard {
     let guard = slog_scope::set_global_logger(main_logger(
         level.unwrap_or(slog::Level::Info),
+        filters,
     ));
     slog_stdlog::init().unwrap();
     guard
@@ -38,9 +44,12 @@ pub fn init(level: Option<slog::Level>) -> slog_scope::GlobalLoggerGuard {
 
 /// Creates, configures and returns main [`Logger`] of the application.
 ///
+/// Records of modules matching the given [`ModuleFilters`] are filtered by
+/// their levels, while all the other ones are filtered by the given `level`.
+///
 /// [`Logger`]: slog::Logger
 #[must_use]
-pub fn main_logger(level: slog::Level) -> slog::Logger {
+pub fn main_logger(level: slog::Level, filters: ModuleFilters) -> slog::Logger {
     use slog::Drain as _;
     use slog_async::OverflowStrategy::Drop;
 
@@ -48,7 +57,10 @@ pub fn main_logger(level: slog::Level) -> slog::Logger {
     let drain = slog_term::CompactFormat::new(decorator).build().fuse();
 
     let drain = drain
-        .filter_level(level)
+        .filter(move |rec| {
+            rec.level()
+                .is_at_least(filters.level_for(rec.module()).unwrap_or(level))
+        })
         .filter(|rec| {
             // Disable annoying DEBUG logs from `hyper` crate.
             !(rec.level() == slog::Level::Debug
@@ -63,3 +75,110 @@ pub fn main_logger(level: slog::Level) -> slog::Logger {
 
     slog::Logger::root(drain, slog::o!())
 }
+
+/// Per-module overrides of logs verbosity level.
+///
+/// Parses from a comma-separated list of `module=level` pairs, like
+/// `hyper=info,actix_web::middleware=warn`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ModuleFilters(Vec<(String, slog::Level)>);
+
+impl ModuleFilters {
+    /// Returns the verbosity level to filter records of the given `module`
+    /// with, if there is any override for it.
+    ///
+    /// A filter matches the `module` itself and all its submodules. If there
+    /// are several matching filters, then the most specific one is used.
+    #[must_use]
+    pub fn level_for(&self, module: &str) -> Option<slog::Level> {
+        self.0
+            .iter()
+            .filter(|(prefix, _)| {
+                module.strip_prefix(prefix.as_str()).map_or(false, |rest| {
+                    rest.is_empty() || rest.starts_with("::")
+                })
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, lvl)| *lvl)
+    }
+}
+
+impl FromStr for ModuleFilters {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(|f| {
+                let mut parts = f.splitn(2, '=').map(str::trim);
+                let module = parts.next().unwrap_or_default();
+                let lvl = parts.next().unwrap_or_default();
+                if module.is_empty() {
+                    return Err(format!("'{}' filter has no module", f));
+                }
+                #[allow(clippy::map_err_ignore)]
+                let lvl = slog::Level::from_str(lvl).map_err(|_| {
+                    format!(
+                        "'{}' filter has invalid verbosity level, allowed \
+                         levels are: CRIT | ERRO | WARN | INFO | DEBG | TRCE",
+                        f,
+                    )
+                })?;
+                Ok((module.to_owned(), lvl))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+#[cfg(test)]
+mod module_filters_spec {
+    use slog::Level;
+
+    use super::ModuleFilters;
+
+    #[test]
+    fn parses_pairs() {
+        let filters: ModuleFilters =
+            "warp=info, sqlx=WARN,hyper::proto=debug".parse().unwrap();
+
+        assert_eq!(
+            filters,
+            ModuleFilters(vec![
+                ("warp".into(), Level::Info),
+                ("sqlx".into(), Level::Warning),
+                ("hyper::proto".into(), Level::Debug),
+            ]),
+        );
+        assert_eq!("".parse(), Ok(ModuleFilters::default()));
+    }
+
+    #[test]
+    fn rejects_invalid_pairs() {
+        for input in &["warp", "warp=loud", "=info", "warp=info,sqlx"] {
+            assert!(
+                input.parse::<ModuleFilters>().is_err(),
+                "'{}' is parsed",
+                input,
+            );
+        }
+    }
+
+    #[test]
+    fn matches_module_prefixes() {
+        let filters: ModuleFilters =
+            "hyper=warn,hyper::proto=trace,sqlx=error".parse().unwrap();
+
+        for (module, expected) in &[
+            ("hyper", Some(Level::Warning)),
+            ("hyper::client", Some(Level::Warning)),
+            ("hyper::proto::h1::io", Some(Level::Trace)),
+            ("sqlx::query", Some(Level::Error)),
+            ("hyperx", None),
+            ("ephyr_restreamer::ffmpeg", None),
+        ] {
+            assert_eq!(filters.level_for(module), *expected, "{}", module);
+        }
+    }
+}
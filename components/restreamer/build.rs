@@ -1,6 +1,13 @@
+use std::env;
+
 use actix_web_static_files::NpmBuild;
 
 fn main() -> anyhow::Result<()> {
+    // Web UI is built only when it's required to be embedded into the binary.
+    if env::var_os("CARGO_FEATURE_UI").is_none() {
+        return Ok(());
+    }
+
     NpmBuild::new("./")
         .executable("yarn")
         .install()?
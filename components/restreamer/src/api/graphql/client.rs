@@ -2,25 +2,33 @@
 //!
 //! [GraphQL]: https://graphql.com
 
-use std::collections::HashSet;
+use std::convert::TryInto as _;
 
 use actix_web::http::StatusCode;
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
 use futures::stream::BoxStream;
 use futures_signals::signal::SignalExt as _;
-use juniper::{graphql_object, graphql_subscription, GraphQLObject, RootNode};
+use juniper::{
+    graphql_object, graphql_subscription, GraphQLInputObject, GraphQLObject,
+    RootNode,
+};
 use once_cell::sync::Lazy;
 use rand::Rng as _;
+use url::Url;
 
 use crate::{
     api::graphql,
-    dvr, spec,
+    dvr, ffmpeg, spec,
     state::{
-        Delay, InputEndpointKind, InputId, InputKey, InputSrcUrl, Label,
-        MixinId, MixinSrcUrl, OutputDstUrl, OutputId, Restream, RestreamId,
-        RestreamKey, Volume,
+        Delay, FfmpegLogLevel, Input, InputEndpointKind, InputId, InputKey,
+        InputSrc, InputSrcUrl, InvalidMixins, KeyframeInterval, Label,
+        MixDuration, MixinId, MixinSrcUrl, Output, OutputDstUrl, OutputFormat,
+        OutputId, PresetId, Restream, RestreamId, RestreamKey, Revision,
+        SrsVhost, SrtPassphrase, Status, StatusEvent, TranscodingProfile,
+        Volume, VolumeChange,
     },
-    Spec,
+    teamspeak, Spec, State,
 };
 
 use super::Context;
@@ -73,6 +81,8 @@ impl MutationsRoot {
         restream_id: Option<RestreamId>,
         context: &Context,
     ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
         let spec = serde_json::from_str::<Spec>(&spec)?.into_v1();
 
         Ok(if let Some(id) = restream_id {
@@ -86,23 +96,70 @@ impl MutationsRoot {
                             "JSON spec should contain exactly one Restream",
                         )
                 })?;
+            let mut restreams = context.state()?.restreams.lock_mut();
+            let others = restreams.iter().filter(|r| r.id != id);
+            Restream::validate_spec(&spec, others).map_err(|e| {
+                graphql::Error::new("INVALID_SPEC")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
             #[allow(clippy::find_map)] // due to moving `spec` inside closure
-            context
-                .state()
-                .restreams
-                .lock_mut()
-                .iter_mut()
-                .find(|r| r.id == id)
-                .map(|r| {
-                    r.apply(spec, replace);
-                    true
-                })
+            restreams.iter_mut().find(|r| r.id == id).map(|r| {
+                r.apply(spec, replace);
+                true
+            })
         } else {
-            context.state().apply(spec, replace);
+            context.state()?.apply(spec, replace).map_err(|e| {
+                graphql::Error::new("INVALID_SPEC")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
             Some(true)
         })
     }
 
+    /// Fetches a JSON spec of `Restream`s from the specified HTTP(S) `url`
+    /// and applies it to this server.
+    ///
+    /// If `replace` is `true` then replaces all the existing `Restream`s with
+    /// the one defined by the fetched spec. Otherwise, merges the fetched spec
+    /// with existing `Restream`s.
+    ///
+    /// ### Result
+    ///
+    /// Always returns `true` if the spec has been fetched and applied
+    /// successfully.
+    #[graphql(arguments(
+        url(description = "HTTP(S) URL to fetch JSON spec from."),
+        replace(
+            description = "Indicator whether the fetched spec should replace \
+                           existing definitions.",
+            default = false,
+        ),
+    ))]
+    async fn import_from_url(
+        url: Url,
+        replace: bool,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.require_admin()?;
+
+        let spec = Spec::fetch(&url).await.map_err(|e| {
+            graphql::Error::new("INVALID_SPEC")
+                .status(StatusCode::BAD_REQUEST)
+                .message(&e)
+        })?;
+        context
+            .state()?
+            .apply(spec.into_v1(), replace)
+            .map_err(|e| {
+                graphql::Error::new("INVALID_SPEC")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })?;
+        Ok(true)
+    }
+
     /// Sets a new `Restream` or updates an existing one (if `id` is specified).
     ///
     /// ### Idempotency
@@ -144,6 +201,11 @@ impl MutationsRoot {
         ),
         id(description = "ID of the `Restream` to be updated rather than \
                           creating a new one."),
+        srs_vhost(description = "Optional SRS `vhost` to route the \
+                                 `Restream`'s live streams through.\
+                                 \n\n\
+                                 If not specified then the default SRS \
+                                 `vhost` is used."),
     ))]
     fn set_restream(
         key: RestreamKey,
@@ -153,57 +215,70 @@ impl MutationsRoot {
         with_backup: bool,
         with_hls: bool,
         id: Option<RestreamId>,
+        srs_vhost: Option<SrsVhost>,
         context: &Context,
     ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
         let input_src = if with_backup {
             Some(spec::v1::InputSrc::FailoverInputs(vec![
                 spec::v1::Input {
                     key: InputKey::new("main").unwrap(),
                     endpoints: vec![spec::v1::InputEndpoint {
                         kind: InputEndpointKind::Rtmp,
+                        hls_encoding: None,
                     }],
-                    src: src.map(spec::v1::InputSrc::RemoteUrl),
+                    src: src.map(|u| spec::v1::InputSrc::RemoteUrl(u.into())),
+                    hls_ladder: vec![],
                     enabled: true,
                 },
                 spec::v1::Input {
                     key: InputKey::new("backup").unwrap(),
                     endpoints: vec![spec::v1::InputEndpoint {
                         kind: InputEndpointKind::Rtmp,
+                        hls_encoding: None,
                     }],
-                    src: backup_src.map(spec::v1::InputSrc::RemoteUrl),
+                    src: backup_src
+                        .map(|u| spec::v1::InputSrc::RemoteUrl(u.into())),
+                    hls_ladder: vec![],
                     enabled: true,
                 },
             ]))
         } else {
-            src.map(spec::v1::InputSrc::RemoteUrl)
+            src.map(|u| spec::v1::InputSrc::RemoteUrl(u.into()))
         };
 
         let mut endpoints = vec![spec::v1::InputEndpoint {
             kind: InputEndpointKind::Rtmp,
+            hls_encoding: None,
         }];
         if with_hls {
             endpoints.push(spec::v1::InputEndpoint {
                 kind: InputEndpointKind::Hls,
+                hls_encoding: None,
             });
         }
 
         let spec = spec::v1::Restream {
             key,
             label,
+            srs_vhost,
             input: spec::v1::Input {
                 key: InputKey::new("origin").unwrap(),
                 endpoints,
                 src: input_src,
+                hls_ladder: vec![],
                 enabled: true,
             },
             outputs: vec![],
+            presets: vec![],
         };
 
         #[allow(clippy::option_if_let_else)] // due to consuming `spec`
         Ok(if let Some(id) = id {
-            context.state().edit_restream(id, spec)
+            context.state()?.edit_restream(id, spec)
         } else {
-            context.state().add_restream(spec).map(Some)
+            context.state()?.add_restream(spec).map(Some)
         }
         .map_err(|e| {
             graphql::Error::new("DUPLICATE_RESTREAM_KEY")
@@ -213,6 +288,37 @@ impl MutationsRoot {
         .map(|_| true))
     }
 
+    /// Copies a `Restream` by its `id` as a new `Restream` with the given
+    /// `newKey`.
+    ///
+    /// All `Output`s of the copied `Restream` are disabled.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if `Restream` with the given `id` doesn't exist,
+    /// otherwise always returns `true`.
+    #[graphql(arguments(
+        id(description = "ID of the `Restream` to be copied."),
+        new_key(description = "Unique key to set the copied `Restream` with."),
+    ))]
+    fn copy_restream(
+        id: RestreamId,
+        new_key: RestreamKey,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context
+            .state()?
+            .copy_restream(id, new_key)
+            .map_err(|e| {
+                graphql::Error::new("DUPLICATE_RESTREAM_KEY")
+                    .status(StatusCode::CONFLICT)
+                    .message(&e)
+            })?
+            .map(|_| true))
+    }
+
     /// Removes a `Restream` by its `id`.
     ///
     /// ### Result
@@ -222,9 +328,37 @@ impl MutationsRoot {
     #[graphql(arguments(id(
         description = "ID of the `Restream` to be removed."
     )))]
-    fn remove_restream(id: RestreamId, context: &Context) -> Option<bool> {
-        context.state().remove_restream(id)?;
-        Some(true)
+    fn remove_restream(
+        id: RestreamId,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.remove_restream(id).map(|_| true))
+    }
+
+    /// Reorders all the `Restream`s on this server to match the given `order`
+    /// of their IDs.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the order of `Restream`s has been changed, or `false`
+    /// if it's the same already.
+    #[graphql(arguments(order(
+        description = "IDs of all the existing `Restream`s in the desired \
+                       order."
+    )))]
+    fn set_restreams_order(
+        order: Vec<RestreamId>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.require_admin()?;
+
+        context.state()?.set_restreams_order(&order).map_err(|e| {
+            graphql::Error::new("INVALID_RESTREAMS_ORDER")
+                .status(StatusCode::BAD_REQUEST)
+                .message(&e)
+        })
     }
 
     /// Enables a `Restream` by its `id`.
@@ -238,8 +372,13 @@ impl MutationsRoot {
     #[graphql(arguments(id(
         description = "ID of the `Restream` to be enabled."
     )))]
-    fn enable_restream(id: RestreamId, context: &Context) -> Option<bool> {
-        context.state().enable_restream(id)
+    fn enable_restream(
+        id: RestreamId,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.enable_restream(id))
     }
 
     /// Disables a `Restream` by its `id`.
@@ -254,8 +393,32 @@ impl MutationsRoot {
     #[graphql(arguments(id(
         description = "ID of the `Restream` to be disabled."
     )))]
-    fn disable_restream(id: RestreamId, context: &Context) -> Option<bool> {
-        context.state().disable_restream(id)
+    fn disable_restream(
+        id: RestreamId,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.disable_restream(id))
+    }
+
+    /// Restarts all the re-streaming processes of a `Restream` by its `id`,
+    /// resetting their runtime statuses.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if a `Restream` with the given `id` has been restarted,
+    /// and `null` if it doesn't exist.
+    #[graphql(arguments(id(
+        description = "ID of the `Restream` to be restarted."
+    )))]
+    fn restart_restream(
+        id: RestreamId,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.restart_restream(id).map(|_| true))
     }
 
     /// Enables an `Input` by its `id`.
@@ -275,8 +438,10 @@ impl MutationsRoot {
         id: InputId,
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().enable_input(id, restream_id)
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.enable_input(id, restream_id))
     }
 
     /// Disables an `Input` by its `id`.
@@ -297,8 +462,10 @@ impl MutationsRoot {
         id: InputId,
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().disable_input(id, restream_id)
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.disable_input(id, restream_id))
     }
 
     /// Sets a new `Output` or updates an existing one (if `id` is specified).
@@ -334,68 +501,103 @@ impl MutationsRoot {
         ),
         id(description = "ID of the `Output` to be updated rather than \
                           creating a new one."),
+        extra_args(
+            description = "Optional additional FFmpeg arguments to be passed \
+                           right before the destination URL.\
+                           \n\n\
+                           Passed \"as is\", without any shell \
+                           interpretation.",
+            default = Vec::new(),
+        ),
+        mix_duration(
+            description = "Policy of how long a mixed live stream of the \
+                           `Output` lasts.\
+                           \n\n\
+                           Has effect only when there are `mixins`.",
+            default = MixDuration::default(),
+        ),
+        srt_latency_ms(description = "Optional latency (in milliseconds) of \
+                                      the SRT connection.\
+                                      \n\n\
+                                      Has effect only for SRT destinations."),
+        dvr_segment_secs(description = "Optional duration (in seconds) of \
+                                        segments to split a DVR recording \
+                                        into.\
+                                        \n\n\
+                                        Has effect only for `file:///` \
+                                        destinations."),
+        srt_passphrase(description = "Optional passphrase (10-79 characters \
+                                      long) to encrypt the SRT connection \
+                                      with.\
+                                      \n\n\
+                                      Has effect only for SRT destinations."),
+        reconnect(
+            description = "Indicator whether FFmpeg should reconnect to a \
+                           remote HTTP source of the `Output` on its \
+                           failures.\
+                           \n\n\
+                           Has no effect for RTMP sources.",
+            default = false,
+        ),
+        loglevel(description = "Optional level of FFmpeg logs to re-stream \
+                                to the `Output` with, overriding the \
+                                default one."),
+        format(description = "Optional FFmpeg container format to re-stream \
+                              to the `Output` in, overriding the one chosen \
+                              by its destination URL."),
+        feeds_siblings(
+            description = "Indicator whether the `Output` should feed its \
+                           sibling `Output`s, so they pull a live stream \
+                           from its `dst` on the local SRS server rather \
+                           than from the `Input`.\
+                           \n\n\
+                           Allowed only for local SRS destinations.",
+            default = false,
+        ),
     ))]
-    fn set_output(
+    async fn set_output(
         restream_id: RestreamId,
         dst: OutputDstUrl,
         label: Option<Label>,
         mixins: Vec<MixinSrcUrl>,
         id: Option<OutputId>,
+        extra_args: Vec<String>,
+        mix_duration: MixDuration,
+        srt_latency_ms: Option<i32>,
+        dvr_segment_secs: Option<i32>,
+        srt_passphrase: Option<String>,
+        reconnect: bool,
+        loglevel: Option<FfmpegLogLevel>,
+        format: Option<OutputFormat>,
+        feeds_siblings: bool,
         context: &Context,
     ) -> Result<Option<bool>, graphql::Error> {
-        if mixins.len() > 5 {
-            return Err(graphql::Error::new("TOO_MUCH_MIXIN_URLS")
-                .status(StatusCode::BAD_REQUEST)
-                .message("Maximum 5 mixing URLs are allowed"));
-        }
-        if !mixins.is_empty() {
-            let mut unique = HashSet::with_capacity(mixins.len());
-            for m in &mixins {
-                if let Some(dup) = unique.replace(m) {
-                    return Err(graphql::Error::new("DUPLICATE_MIXIN_URL")
-                        .status(StatusCode::BAD_REQUEST)
-                        .message(&format!(
-                            "Duplicate Output.mixin.src: {}",
-                            dup,
-                        )));
-                }
-            }
-            if mixins.iter().filter(|u| u.scheme() == "ts").take(2).count() > 1
-            {
-                return Err(graphql::Error::new(
-                    "TOO_MUCH_TEAMSPEAK_MIXIN_URLS",
-                )
-                .status(StatusCode::BAD_REQUEST)
-                .message("Only one TeamSpeak URL is allowed"));
-            }
-        }
+        context.require_admin()?;
 
-        let spec = spec::v1::Output {
+        let spec = OutputSpec {
             dst,
             label,
-            volume: Volume::ORIGIN,
-            mixins: mixins
-                .into_iter()
-                .map(|src| {
-                    let delay = (src.scheme() == "ts")
-                        .then(|| Delay::from_millis(3500))
-                        .flatten()
-                        .unwrap_or_default();
-                    spec::v1::Mixin {
-                        src,
-                        volume: Volume::ORIGIN,
-                        delay,
-                    }
-                })
-                .collect(),
-            enabled: false,
-        };
+            mixins,
+            extra_args,
+            mix_duration,
+            srt_latency_ms,
+            dvr_segment_secs,
+            srt_passphrase,
+            reconnect,
+            loglevel,
+            format,
+            feeds_siblings,
+        }
+        .into_spec(context.config()?.max_mixins)?;
+        if context.config()?.check_mixin_urls {
+            check_mixins_reachable(&spec).await?;
+        }
 
         #[allow(clippy::option_if_let_else)] // due to consuming `spec`
         Ok(if let Some(id) = id {
-            context.state().edit_output(restream_id, id, spec)
+            context.state()?.edit_output(restream_id, id, spec)
         } else {
-            context.state().add_output(restream_id, spec)
+            context.state()?.add_output(restream_id, spec)
         }
         .map_err(|e| {
             graphql::Error::new("DUPLICATE_OUTPUT_URL")
@@ -405,6 +607,56 @@ impl MutationsRoot {
         .map(|_| true))
     }
 
+    /// Adds multiple new `Output`s into the specified `Restream` at once.
+    ///
+    /// All the `Output`s are validated before adding any of them, so either
+    /// all of them are added, or none (in case of any error, including `dst`
+    /// duplicates within the specified `Restream` or the given `outputs`).
+    ///
+    /// ### Idempotency
+    ///
+    /// Non-idempotent. Always creates new `Output`s and errors on the `dst`
+    /// duplicates within the specified `Restream`.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if a `Restream` with the given `restreamId` doesn't
+    /// exist, otherwise always returns IDs of the created `Output`s (in the
+    /// same order as the given `outputs`).
+    #[graphql(arguments(
+        restream_id(
+            description = "ID of the `Restream` to add new `Output`s to."
+        ),
+        outputs(description = "Specifications of the `Output`s to be added."),
+    ))]
+    async fn set_outputs(
+        restream_id: RestreamId,
+        outputs: Vec<OutputSpec>,
+        context: &Context,
+    ) -> Result<Option<Vec<OutputId>>, graphql::Error> {
+        context.require_admin()?;
+
+        let max_mixins = context.config()?.max_mixins;
+        let specs = outputs
+            .into_iter()
+            .map(|o| o.into_spec(max_mixins))
+            .collect::<Result<Vec<_>, _>>()?;
+        if context.config()?.check_mixin_urls {
+            for spec in &specs {
+                check_mixins_reachable(spec).await?;
+            }
+        }
+
+        context
+            .state()?
+            .add_outputs(restream_id, specs)
+            .map_err(|e| {
+                graphql::Error::new("DUPLICATE_OUTPUT_URL")
+                    .status(StatusCode::CONFLICT)
+                    .message(&e)
+            })
+    }
+
     /// Removes an `Output` by its `id` from the specified `Restream`.
     ///
     /// ### Result
@@ -420,8 +672,49 @@ impl MutationsRoot {
         id: OutputId,
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().remove_output(id, restream_id).map(|_| true)
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context
+            .state()?
+            .remove_output(id, restream_id)
+            .map(|_| true))
+    }
+
+    /// Probes whether the destination of an `Output` by its `id` in the
+    /// specified `Restream` accepts connections.
+    ///
+    /// Neither enables the `Output`, nor spawns any re-streaming process for
+    /// it. SRT and file destinations are not probed.
+    ///
+    /// ### Result
+    ///
+    /// Returns `ProbeResult` of the destination, or `null` if the specified
+    /// `Restream`/`Output` doesn't exist.
+    #[graphql(arguments(
+        id(description = "ID of the `Output` to be probed."),
+        restream_id(description = "ID of the `Restream` to probe the \
+                                   `Output` in."),
+    ))]
+    async fn probe_output(
+        id: OutputId,
+        restream_id: RestreamId,
+        context: &Context,
+    ) -> Result<Option<ProbeResult>, graphql::Error> {
+        context.require_admin()?;
+
+        let dst = context
+            .state()?
+            .restreams
+            .lock_ref()
+            .iter()
+            .find(|r| r.id == restream_id)
+            .and_then(|r| r.outputs.iter().find(|o| o.id == id))
+            .map(|o| o.dst.clone());
+        Ok(match dst {
+            Some(dst) => Some(dst.probe().await.into()),
+            None => None,
+        })
     }
 
     /// Enables an `Output` by its `id` in the specified `Restream`.
@@ -442,8 +735,10 @@ impl MutationsRoot {
         id: OutputId,
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().enable_output(id, restream_id)
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.enable_output(id, restream_id))
     }
 
     /// Disables an `Output` by its `id` in the specified `Restream`.
@@ -464,8 +759,10 @@ impl MutationsRoot {
         id: OutputId,
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().disable_output(id, restream_id)
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.disable_output(id, restream_id))
     }
 
     /// Enables all `Output`s in the specified `Restream`.
@@ -484,8 +781,10 @@ impl MutationsRoot {
     fn enable_all_outputs(
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().enable_all_outputs(restream_id)
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.enable_all_outputs(restream_id))
     }
 
     /// Disables all `Output`s in the specified `Restream`.
@@ -504,8 +803,83 @@ impl MutationsRoot {
     fn disable_all_outputs(
         restream_id: RestreamId,
         context: &Context,
-    ) -> Option<bool> {
-        context.state().disable_all_outputs(restream_id)
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.disable_all_outputs(restream_id))
+    }
+
+    /// Switches the specified `Output` between re-streaming a live stream "as
+    /// is" and transcoding it with the given `profile`.
+    ///
+    /// Only the re-streaming process of the specified `Output` is restarted.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if a `TranscodingProfile` has been changed, `false` if it
+    /// has the same value already, or `null` if the specified `Output` doesn't
+    /// exist.
+    ///
+    /// Fails if a `profile` is given for an `Output` having `Mixin`s, or
+    /// having a non-RTMP/SRT destination, as it cannot be transcoded.
+    #[graphql(arguments(
+        restream_id(description = "ID of the `Restream` to switch the \
+                                   `Output` in."),
+        output_id(description = "ID of the switched `Output`."),
+        profile(description = "`TranscodingProfile` to transcode a live \
+                               stream with.\
+                               \n\n\
+                               If `null`, then a live stream is re-streamed \
+                               \"as is\", without transcoding."),
+    ))]
+    fn set_output_transcode(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        profile: Option<TranscodingProfile>,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        context
+            .state()?
+            .set_output_transcoding(restream_id, output_id, profile)
+            .map_err(|e| {
+                graphql::Error::new("TRANSCODING_NOT_SUPPORTED")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })
+    }
+
+    /// Sets an interval between keyframes of the specified `Output` when it's
+    /// transcoded.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if a `KeyframeInterval` has been changed, `false` if it
+    /// has the same value already, or `null` if the specified `Output` doesn't
+    /// exist.
+    #[graphql(arguments(
+        restream_id(description = "ID of the `Restream` to tune the \
+                                   `Output` in."),
+        output_id(description = "ID of the tuned `Output`."),
+        interval(description = "Interval between keyframes in seconds.\
+                                \n\n\
+                                If `null`, then the default one is used, \
+                                aligned with HLS segments."),
+    ))]
+    fn set_output_keyframe_interval(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        interval: Option<KeyframeInterval>,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.set_output_keyframe_interval(
+            restream_id,
+            output_id,
+            interval,
+        ))
     }
 
     /// Tunes a `Volume` rate of the specified `Output` or one of its `Mixin`s.
@@ -531,15 +905,127 @@ impl MutationsRoot {
         mixin_id: Option<MixinId>,
         volume: Volume,
         context: &Context,
-    ) -> Option<bool> {
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.tune_volume(
+            restream_id,
+            output_id,
+            mixin_id,
+            volume,
+        ))
+    }
+
+    /// Tunes `Volume` rates of multiple `Output`s or their `Mixin`s in the
+    /// specified `Restream` at once.
+    ///
+    /// All the changes are applied atomically, as a single one.
+    ///
+    /// ### Result
+    ///
+    /// Returns results of each of the changes in the same order: `true` if a
+    /// `Volume` rate has been changed, `false` if it has the same value
+    /// already, or `null` if the specified `Output` or `Mixin` doesn't exist.
+    ///
+    /// Returns `null` if the specified `Restream` doesn't exist.
+    #[graphql(arguments(
+        restream_id(description = "ID of the `Restream` to tune the \
+                                   `Output`s in."),
+        changes(description = "Changes of `Volume` rates to be applied."),
+    ))]
+    fn tune_volumes(
+        restream_id: RestreamId,
+        changes: Vec<VolumeChange>,
+        context: &Context,
+    ) -> Result<Option<Vec<Option<bool>>>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.tune_volumes(restream_id, &changes))
+    }
+
+    /// Adds a new `Preset` of `Volume` rates to the specified `Restream`.
+    ///
+    /// ### Result
+    ///
+    /// Returns ID of the added `Preset`, or `null` if the specified `Restream`
+    /// doesn't exist.
+    #[graphql(arguments(
+        restream_id(description = "ID of the `Restream` to add the `Preset` \
+                                   to."),
+        name(description = "Unique (in the `Restream`) name of the `Preset`."),
+        volumes(description = "`Volume` rates to be stored in the `Preset`."),
+    ))]
+    fn add_preset(
+        restream_id: RestreamId,
+        name: Label,
+        volumes: Vec<VolumeChange>,
+        context: &Context,
+    ) -> Result<Option<PresetId>, graphql::Error> {
+        context.require_admin()?;
+
         context
-            .state()
-            .tune_volume(restream_id, output_id, mixin_id, volume)
+            .state()?
+            .add_preset(restream_id, name, &volumes)
+            .map_err(|e| {
+                graphql::Error::new("DUPLICATE_PRESET_NAME")
+                    .status(StatusCode::CONFLICT)
+                    .message(&e)
+            })
+    }
+
+    /// Applies `Volume` rates of the specified `Preset` to the `Output`s and
+    /// their `Mixin`s of its `Restream` at once, as `tuneVolumes` does.
+    ///
+    /// ### Result
+    ///
+    /// Returns results of each of the `Preset`'s `Volume` rates in the same
+    /// order, as `tuneVolumes` does, or `null` if the specified
+    /// `Restream`/`Preset` doesn't exist.
+    #[graphql(arguments(
+        restream_id(description = "ID of the `Restream` to apply the \
+                                   `Preset` in."),
+        id(description = "ID of the `Preset` to be applied."),
+    ))]
+    fn apply_preset(
+        restream_id: RestreamId,
+        id: PresetId,
+        context: &Context,
+    ) -> Result<Option<Vec<Option<bool>>>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context.state()?.apply_preset(restream_id, id))
+    }
+
+    /// Removes a `Preset` by its `id` from the specified `Restream`.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if the specified `Restream`/`Preset` doesn't exist,
+    /// otherwise always returns `true`.
+    #[graphql(arguments(
+        id(description = "ID of the `Preset` to be removed."),
+        restream_id(description = "ID of the `Restream` to remove the \
+                                   `Preset` from."),
+    ))]
+    fn remove_preset(
+        id: PresetId,
+        restream_id: RestreamId,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context
+            .state()?
+            .remove_preset(id, restream_id)
+            .map(|_| true))
     }
 
     /// Tunes a `Delay` of the specified `Mixin` before mix it into its
     /// `Output`.
     ///
+    /// Exactly one of `delay` (milliseconds) or `delaySeconds` arguments must
+    /// be specified.
+    ///
     /// ### Result
     ///
     /// Returns `true` if a `Delay` has been changed, `false` if it has the same
@@ -552,17 +1038,116 @@ impl MutationsRoot {
         mixin_id(description = "ID of the tuned `Mixin`."),
         delay(description = "Number of milliseconds to delay the `Mixin` \
                              before mix it into its `Output`."),
+        delay_seconds(description = "Number of seconds (fractional ones are \
+                                     allowed) to delay the `Mixin` before \
+                                     mix it into its `Output`."),
     ))]
     fn tune_delay(
         restream_id: RestreamId,
         output_id: OutputId,
         mixin_id: MixinId,
-        delay: Delay,
+        delay: Option<Delay>,
+        delay_seconds: Option<f64>,
         context: &Context,
-    ) -> Option<bool> {
-        context
-            .state()
-            .tune_delay(restream_id, output_id, mixin_id, delay)
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        let delay = match (delay, delay_seconds) {
+            (Some(d), None) => d,
+            (None, Some(secs)) => {
+                Delay::from_secs_f64(secs).ok_or_else(|| {
+                    graphql::Error::new("INVALID_DELAY")
+                        .status(StatusCode::BAD_REQUEST)
+                        .message("Delay seconds must be a non-negative number")
+                })?
+            }
+            _ => {
+                return Err(graphql::Error::new("AMBIGUOUS_DELAY")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(
+                        "Exactly one of `delay` or `delaySeconds` must be \
+                         specified",
+                    ));
+            }
+        };
+
+        Ok(context
+            .state()?
+            .tune_delay(restream_id, output_id, mixin_id, delay))
+    }
+
+    /// Tunes a `Delay` of the specified `Output` before re-streaming it to its
+    /// destination.
+    ///
+    /// Has no effect on `Output`s with `Mixin`s. Changing a `Delay` restarts
+    /// the re-streaming process of the `Output`.
+    ///
+    /// Note, that a `Delay` only offsets timestamps of the re-streamed live
+    /// stream, while its media is still sent to the destination as soon as it's
+    /// received. So, it doesn't hold the live stream back in real time, and is
+    /// respected only by destinations playing the stream out by timestamps.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if a `Delay` has been changed, `false` if it has the same
+    /// value already, or `null` if the specified `Output` doesn't exist.
+    #[graphql(arguments(
+        restream_id(description = "ID of the `Restream` to tune the the \
+                                   `Output` in."),
+        output_id(description = "ID of the tuned `Output`."),
+        delay(description = "Number of milliseconds to delay the `Output` \
+                             before re-streaming it."),
+    ))]
+    fn tune_output_delay(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        delay: Delay,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        Ok(context
+            .state()?
+            .tune_output_delay(restream_id, output_id, delay))
+    }
+
+    /// Tunes a sidechain ducking of the specified [TeamSpeak] `Mixin`, making
+    /// the original audio of its `Output` quieten while the `Mixin` sounds.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if a sidechain ducking has been changed, `false` if it
+    /// has the same value already, or `null` if the specified `Output` or
+    /// `Mixin` doesn't exist.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[graphql(arguments(
+        restream_id(description = "ID of the `Restream` to tune the the \
+                                   `Mixin` in."),
+        output_id(description = "ID of the `Output` of the tuned `Mixin`."),
+        mixin_id(description = "ID of the tuned `Mixin`."),
+        sidechain(
+            description = "Indicator whether the `Output` audio should \
+                                 be ducked under the `Mixin` audio."
+        ),
+    ))]
+    fn tune_sidechain(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: MixinId,
+        sidechain: bool,
+        context: &Context,
+    ) -> Result<Option<bool>, graphql::Error> {
+        context.require_admin()?;
+
+        context
+            .state()?
+            .tune_sidechain(restream_id, output_id, mixin_id, sidechain)
+            .map_err(|e| {
+                graphql::Error::new("SIDECHAIN_NOT_SUPPORTED")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            })
     }
 
     /// Removes the specified recorded file.
@@ -576,7 +1161,12 @@ impl MutationsRoot {
                        \n\n\
                        Use the exact value returned by `Query.dvrFiles`."
     )))]
-    async fn remove_dvr_file(path: String) -> Result<bool, graphql::Error> {
+    async fn remove_dvr_file(
+        path: String,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.require_admin()?;
+
         if path.starts_with('/') || path.contains("../") {
             return Err(graphql::Error::new("INVALID_DVR_FILE_PATH")
                 .status(StatusCode::BAD_REQUEST)
@@ -586,6 +1176,89 @@ impl MutationsRoot {
         Ok(dvr::Storage::global().remove_file(path).await)
     }
 
+    /// Makes a snapshot of the current state of this server, which may be
+    /// restored later with `restoreSnapshot` mutation.
+    ///
+    /// Only the configured number of the latest snapshots is kept.
+    ///
+    /// ### Result
+    ///
+    /// Returns the name of the made snapshot file.
+    async fn snapshot(context: &Context) -> Result<String, graphql::Error> {
+        context.require_admin()?;
+
+        let cfg = context.config()?;
+        let dir = cfg.snapshots_dir.as_ref().ok_or_else(|| {
+            graphql::Error::new("SNAPSHOTS_DISABLED")
+                .status(StatusCode::NOT_IMPLEMENTED)
+                .message("Snapshots are not configured on this server")
+        })?;
+
+        let (dir, keep, state) =
+            (dir.clone(), cfg.snapshots_keep, context.state()?.clone());
+        Ok(state.snapshot(dir, keep).await?)
+    }
+
+    /// Restores the state of this server from the specified snapshot,
+    /// replacing all the existing `Restream`s with the snapshotted ones.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the snapshot has been restored.
+    #[graphql(arguments(filename(
+        description = "Name of the snapshot file to restore from.\
+                       \n\n\
+                       Use the exact value returned by `snapshot` mutation."
+    )))]
+    async fn restore_snapshot(
+        filename: String,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.require_admin()?;
+
+        let dir =
+            context.config()?.snapshots_dir.as_ref().ok_or_else(|| {
+                graphql::Error::new("SNAPSHOTS_DISABLED")
+                    .status(StatusCode::NOT_IMPLEMENTED)
+                    .message("Snapshots are not configured on this server")
+            })?;
+
+        let (dir, state) = (dir.clone(), context.state()?.clone());
+        state.restore_snapshot(dir, &filename).await.map_err(|e| {
+            graphql::Error::new("INVALID_SNAPSHOT")
+                .status(StatusCode::BAD_REQUEST)
+                .message(&e)
+        })?;
+        Ok(true)
+    }
+
+    /// Sets or unsets the notice about maintenance to be shown to this
+    /// server's users.
+    ///
+    /// The notice doesn't affect re-streaming at all.
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if the notice has been changed or unset, otherwise
+    /// `false` if nothing changes.
+    #[graphql(arguments(message(
+        description = "Notice message to be shown. If `null` or blank then \
+                       unsets the current notice."
+    )))]
+    fn set_maintenance_notice(
+        message: Option<String>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.require_admin()?;
+
+        if message.as_ref().map_or(false, |m| m.chars().count() > 500) {
+            return Err(graphql::Error::new("TOO_LONG_MAINTENANCE_NOTICE")
+                .status(StatusCode::BAD_REQUEST)
+                .message("Maximum 500 characters are allowed"));
+        }
+        Ok(context.state()?.set_maintenance_notice(message))
+    }
+
     /// Sets or unsets the password to protect this GraphQL API with.
     ///
     /// Once password is set, any subsequent requests to this GraphQL API should
@@ -611,10 +1284,12 @@ impl MutationsRoot {
         old: Option<String>,
         context: &Context,
     ) -> Result<bool, graphql::Error> {
+        context.require_admin()?;
+
         static HASH_CFG: Lazy<argon2::Config<'static>> =
             Lazy::new(argon2::Config::default);
 
-        let mut current = context.state().password_hash.lock_mut();
+        let mut current = context.state()?.password_hash.lock_mut();
 
         if let Some(hash) = &*current {
             match old {
@@ -647,6 +1322,43 @@ impl MutationsRoot {
         });
         Ok(true)
     }
+
+    /// Sets or unsets the password granting read-only access to this GraphQL
+    /// API.
+    ///
+    /// Requests authorized with this password are allowed to perform queries
+    /// and subscriptions only, while any mutations are forbidden. Has effect
+    /// only if the main password is set (see `Mutation.setPassword`).
+    ///
+    /// ### Result
+    ///
+    /// Returns `true` if password has been changed or unset, otherwise `false`
+    /// if nothing changes.
+    #[graphql(arguments(new(
+        description = "New password to be set. In `null` then unsets the \
+                       current password."
+    )))]
+    fn set_viewer_password(
+        new: Option<String>,
+        context: &Context,
+    ) -> Result<bool, graphql::Error> {
+        context.require_admin()?;
+
+        let mut current = context.state()?.viewer_password_hash.lock_mut();
+        if current.is_none() && new.is_none() {
+            return Ok(false);
+        }
+
+        *current = new.map(|v| {
+            argon2::hash_encoded(
+                v.as_bytes(),
+                &rand::thread_rng().gen::<[u8; 32]>(),
+                &argon2::Config::default(),
+            )
+            .unwrap()
+        });
+        Ok(true)
+    }
 }
 
 /// Root of all [GraphQL queries][1] in the [`Schema`].
@@ -658,16 +1370,166 @@ pub struct QueriesRoot;
 #[graphql_object(name = "Query", context = Context)]
 impl QueriesRoot {
     /// Returns the current `Info` parameters of this server.
-    fn info(context: &Context) -> Info {
-        Info {
-            public_host: context.config().public_host.clone().unwrap(),
-            password_hash: context.state().password_hash.get_cloned(),
+    fn info(context: &Context) -> Result<Info, graphql::Error> {
+        Ok(Info {
+            public_host: context.config()?.public_host.clone().unwrap(),
+            password_hash: context.state()?.password_hash.get_cloned(),
+        })
+    }
+
+    /// Returns the current notice about maintenance of this server, if any.
+    fn maintenance_notice(
+        context: &Context,
+    ) -> Result<Option<String>, graphql::Error> {
+        Ok(context.state()?.maintenance_notice.get_cloned())
+    }
+
+    /// Returns the current `Revision` of this server's state.
+    ///
+    /// Intended to be passed as `sinceRevision` when resubscribing to
+    /// `allRestreams` on reconnects.
+    fn revision(context: &Context) -> Result<Revision, graphql::Error> {
+        Ok(context.state()?.revision.get())
+    }
+
+    /// Returns the current time of this server.
+    ///
+    /// Intended for clients to compare it with their own clock, and to warn
+    /// about a clock skew, as the schedules depend on a correct wall-clock
+    /// time.
+    fn server_time() -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    /// Returns the most recent `StatusEvent`s of this server, from the newest
+    /// to the oldest one.
+    ///
+    /// Only the last 1000 `StatusEvent`s are kept, and they're reset on this
+    /// server restart.
+    #[graphql(arguments(
+        limit(
+            description = "Maximum number of `StatusEvent`s to return.",
+            default = 100,
+        ),
+        before(description = "Optional `StatusEvent.id` to return only the \
+                              older `StatusEvent`s than.\
+                              \n\n\
+                              Intended for paging."),
+    ))]
+    fn events(
+        limit: i32,
+        before: Option<i32>,
+        context: &Context,
+    ) -> Result<Vec<StatusEvent>, graphql::Error> {
+        let limit = limit.try_into().map_err(|_| {
+            graphql::Error::new("INVALID_LIMIT")
+                .status(StatusCode::BAD_REQUEST)
+                .message("Limit cannot be negative")
+        })?;
+        Ok(context.state()?.status_events.page(limit, before))
+    }
+
+    /// Returns aggregated `ServerInfo` summary of this server.
+    ///
+    /// Much cheaper than querying all the `Restream`s, so is intended for
+    /// status widgets and dashboards.
+    fn server_info(context: &Context) -> Result<ServerInfo, graphql::Error> {
+        Ok(ServerInfo::new(context.state()?))
+    }
+
+    /// Returns estimated latencies of all the `Output`s of the specified
+    /// `Restream`.
+    ///
+    /// Returns `null` if there is no `Restream` with such `id`.
+    #[graphql(arguments(id(
+        description = "ID of the `Restream` to estimate latencies of."
+    )))]
+    fn restream_latency(
+        id: RestreamId,
+        context: &Context,
+    ) -> Result<Option<Vec<OutputLatency>>, graphql::Error> {
+        let restreams = context.state()?.restreams.lock_ref();
+        let restream = if let Some(r) = restreams.iter().find(|r| r.id == id) {
+            r
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(
+            restream
+                .outputs
+                .iter()
+                .map(|o| OutputLatency {
+                    output_id: o.id,
+                    estimated: restream
+                        .estimate_latency(o)
+                        .as_millis()
+                        .try_into()
+                        .unwrap_or(i32::MAX),
+                })
+                .collect(),
+        ))
+    }
+
+    /// Returns diagnostics of all the [TeamSpeak] connections used by
+    /// `Mixin`s at the moment.
+    ///
+    /// Intended for debugging of `Mixin`s audio.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    fn teamspeak_diagnostics() -> Vec<TeamspeakDiagnostics> {
+        ffmpeg::teamspeak_diagnostics()
+            .into_iter()
+            .map(|(mixin_id, d)| TeamspeakDiagnostics {
+                mixin_id,
+                host: d.host,
+                channel: d.channel,
+                name: d.name,
+                state: d.state,
+                reconnects: d.reconnects.try_into().unwrap_or(i32::MAX),
+            })
+            .collect()
+    }
+
+    /// Returns command line arguments of the [FFmpeg] process re-streaming the
+    /// specified `Output`, with all the secrets (passphrases, passwords)
+    /// being redacted.
+    ///
+    /// Intended for debugging, so is available only in debug mode.
+    ///
+    /// ### Result
+    ///
+    /// Returns `null` if the specified `Output` doesn't exist or has no
+    /// running [FFmpeg] process.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[graphql(arguments(
+        restream_id(description = "ID of the `Restream` of the `Output`."),
+        output_id(description = "ID of the `Output` to return [FFmpeg] \
+                                 command line arguments of."),
+    ))]
+    fn ffmpeg_args(
+        restream_id: RestreamId,
+        output_id: OutputId,
+        context: &Context,
+    ) -> Result<Option<Vec<String>>, graphql::Error> {
+        if !context.config()?.debug {
+            return Err(graphql::Error::new("DEBUG_MODE_REQUIRED")
+                .status(StatusCode::FORBIDDEN)
+                .message("FFmpeg arguments are available in debug mode only"));
         }
+        let exists = context.state()?.restreams.lock_ref().iter().any(|r| {
+            r.id == restream_id && r.outputs.iter().any(|o| o.id == output_id)
+        });
+        Ok(exists
+            .then(|| ffmpeg::ffmpeg_args(output_id.into()))
+            .flatten())
     }
 
     /// Returns all the `Restream`s happening on this server.
-    fn all_restreams(context: &Context) -> Vec<Restream> {
-        context.state().restreams.get_cloned()
+    fn all_restreams(
+        context: &Context,
+    ) -> Result<Vec<Restream>, graphql::Error> {
+        Ok(context.state()?.restreams.get_cloned())
     }
 
     /// Returns list of recorded files of the specified `Output`.
@@ -689,6 +1551,22 @@ impl QueriesRoot {
         dvr::Storage::global().list_files(id).await
     }
 
+    /// Returns usage of the disk where recorded files of `Output`s are stored.
+    ///
+    /// Intended for warning about the disk being full soon, as recordings fail
+    /// once it is.
+    async fn dvr_disk_usage() -> Result<DvrDiskUsage, graphql::Error> {
+        dvr::Storage::global()
+            .disk_usage()
+            .await
+            .map(Into::into)
+            .map_err(|e| {
+                graphql::Error::new("DVR_DISK_USAGE_UNAVAILABLE")
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .message(&e)
+            })
+    }
+
     /// Returns `Restream`s happening on this server and identifiable by the
     /// given `ids` in an exportable JSON format.
     ///
@@ -705,7 +1583,7 @@ impl QueriesRoot {
         context: &Context,
     ) -> Result<Option<String>, graphql::Error> {
         let restreams = context
-            .state()
+            .state()?
             .restreams
             .get_cloned()
             .into_iter()
@@ -733,10 +1611,12 @@ pub struct SubscriptionsRoot;
 #[graphql_subscription(name = "Subscription", context = Context)]
 impl SubscriptionsRoot {
     /// Subscribes to updates of `Info` parameters of this server.
-    async fn info(context: &Context) -> BoxStream<'static, Info> {
-        let public_host = context.config().public_host.clone().unwrap();
-        context
-            .state()
+    async fn info(
+        context: &Context,
+    ) -> Result<BoxStream<'static, Info>, graphql::Error> {
+        let public_host = context.config()?.public_host.clone().unwrap();
+        Ok(context
+            .state()?
             .password_hash
             .signal_cloned()
             .dedupe_cloned()
@@ -745,20 +1625,35 @@ impl SubscriptionsRoot {
                 password_hash: h,
             })
             .to_stream()
-            .boxed()
+            .boxed())
     }
 
-    /// Subscribes to updates of all `Restream`s happening on this server.
-    async fn all_restreams(
+    /// Subscribes to updates of the notice about maintenance of this server.
+    async fn maintenance_notice(
         context: &Context,
-    ) -> BoxStream<'static, Vec<Restream>> {
-        context
-            .state()
-            .restreams
+    ) -> Result<BoxStream<'static, Option<String>>, graphql::Error> {
+        Ok(context
+            .state()?
+            .maintenance_notice
             .signal_cloned()
             .dedupe_cloned()
             .to_stream()
-            .boxed()
+            .boxed())
+    }
+
+    /// Subscribes to updates of all `Restream`s happening on this server.
+    #[graphql(arguments(since_revision(
+        description = "Optional `Revision` of the state the client has \
+                       already, to skip the initial emission of the current \
+                       `Restream`s if they're of the same `Revision`.\
+                       \n\n\
+                       Intended for resuming subscription on reconnects."
+    )))]
+    async fn all_restreams(
+        since_revision: Option<Revision>,
+        context: &Context,
+    ) -> Result<BoxStream<'static, Vec<Restream>>, graphql::Error> {
+        Ok(context.state()?.restreams_since(since_revision))
     }
 }
 
@@ -781,3 +1676,629 @@ pub struct Info {
     /// [1]: https://en.wikipedia.org/wiki/Basic_access_authentication
     pub password_hash: Option<String>,
 }
+
+/// Specification of a new `Output` to be added into a `Restream`.
+#[derive(Clone, Debug, GraphQLInputObject)]
+pub struct OutputSpec {
+    /// Destination URL to re-stream a live stream onto.
+    pub dst: OutputDstUrl,
+
+    /// Optional label to add a new `Output` with.
+    pub label: Option<Label>,
+
+    /// Optional `MixinSrcUrl`s to mix into this `Output`.
+    #[graphql(default)]
+    pub mixins: Vec<MixinSrcUrl>,
+
+    /// Optional additional FFmpeg arguments to be passed right before the
+    /// destination URL.
+    #[graphql(default)]
+    pub extra_args: Vec<String>,
+
+    /// Policy of how long a mixed live stream of the `Output` lasts.
+    ///
+    /// Has effect only when there are `mixins`.
+    #[graphql(default)]
+    pub mix_duration: MixDuration,
+
+    /// Optional latency (in milliseconds) of the SRT connection.
+    pub srt_latency_ms: Option<i32>,
+
+    /// Optional duration (in seconds) of segments to split a DVR recording
+    /// into.
+    pub dvr_segment_secs: Option<i32>,
+
+    /// Optional passphrase (10-79 characters long) to encrypt the SRT
+    /// connection with.
+    pub srt_passphrase: Option<String>,
+
+    /// Indicator whether FFmpeg should reconnect to a remote HTTP source of
+    /// this `Output` on its failures.
+    #[graphql(default)]
+    pub reconnect: bool,
+
+    /// Optional level of FFmpeg logs to re-stream to this `Output` with,
+    /// overriding the default one.
+    pub loglevel: Option<FfmpegLogLevel>,
+
+    /// Optional FFmpeg container format to re-stream to this `Output` in,
+    /// overriding the one chosen by its destination URL.
+    pub format: Option<OutputFormat>,
+
+    /// Indicator whether the `Output` should feed its sibling `Output`s, so
+    /// they pull a live stream from its `dst` on the local SRS server rather
+    /// than from the `Input`.
+    #[graphql(default)]
+    pub feeds_siblings: bool,
+}
+
+impl OutputSpec {
+    /// Validates this [`OutputSpec`] and converts it into a
+    /// [`spec::v1::Output`], ready to be added into a [`State`].
+    ///
+    /// # Errors
+    ///
+    /// If this [`OutputSpec`] contains invalid values, or more than
+    /// `max_mixins` [`OutputSpec::mixins`].
+    fn into_spec(
+        self,
+        max_mixins: usize,
+    ) -> Result<spec::v1::Output, graphql::Error> {
+        let srt_latency_ms: Option<u32> = self
+            .srt_latency_ms
+            .map(|ms| ms.try_into())
+            .transpose()
+            .map_err(|_| {
+                graphql::Error::new("INVALID_SRT_LATENCY")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message("SRT latency cannot be negative")
+            })?;
+        let dvr_segment_secs = self
+            .dvr_segment_secs
+            .map(|secs| {
+                secs.try_into()
+                    .ok()
+                    .filter(|s: &u32| *s > 0)
+                    .ok_or_else(|| {
+                        graphql::Error::new("INVALID_DVR_SEGMENT")
+                            .status(StatusCode::BAD_REQUEST)
+                            .message("DVR segment duration should be positive")
+                    })
+            })
+            .transpose()?;
+        let srt_passphrase = self
+            .srt_passphrase
+            .map(|p| {
+                SrtPassphrase::new(p).ok_or_else(|| {
+                    graphql::Error::new("INVALID_SRT_PASSPHRASE")
+                        .status(StatusCode::BAD_REQUEST)
+                        .message(
+                            "SRT passphrase should be 10-79 characters long",
+                        )
+                })
+            })
+            .transpose()?;
+
+        Output::validate_extra_args(&self.dst, &self.extra_args).map_err(
+            |e| {
+                graphql::Error::new("INVALID_OUTPUT_EXTRA_ARGS")
+                    .status(StatusCode::BAD_REQUEST)
+                    .message(&e)
+            },
+        )?;
+
+        if self.mixins.len() > max_mixins {
+            return Err(graphql::Error::new("TOO_MUCH_MIXIN_URLS")
+                .status(StatusCode::BAD_REQUEST)
+                .message(&format!(
+                    "Maximum {} mixing URLs are allowed",
+                    max_mixins,
+                )));
+        }
+        Output::validate_mixins(&self.mixins).map_err(|e| {
+            let code = match e {
+                InvalidMixins::Duplicate(_) => "DUPLICATE_MIXIN_URL",
+                InvalidMixins::TooMuchTeamspeak => {
+                    "TOO_MUCH_TEAMSPEAK_MIXIN_URLS"
+                }
+            };
+            graphql::Error::new(code)
+                .status(StatusCode::BAD_REQUEST)
+                .message(&e)
+        })?;
+
+        Ok(spec::v1::Output {
+            dst: self.dst,
+            label: self.label,
+            volume: Volume::ORIGIN,
+            mixins: self
+                .mixins
+                .into_iter()
+                .map(|src| {
+                    let delay = (src.scheme() == "ts")
+                        .then(|| Delay::from_millis(3500))
+                        .flatten()
+                        .unwrap_or_default();
+                    spec::v1::Mixin {
+                        src,
+                        volume: Volume::ORIGIN,
+                        delay,
+                        sidechain: false,
+                    }
+                })
+                .collect(),
+            transcoding: None,
+            keyframe_interval: None,
+            delay: Delay::default(),
+            mix_duration: self.mix_duration,
+            srt_latency_ms,
+            dvr_segment_secs,
+            srt_passphrase,
+            extra_args: self.extra_args,
+            reconnect: self.reconnect,
+            loglevel: self.loglevel,
+            format: self.format,
+            feeds_siblings: self.feeds_siblings,
+            enabled: false,
+        })
+    }
+}
+
+#[cfg(test)]
+impl OutputSpec {
+    /// Creates a new [`OutputSpec`] to be used in tests, re-streaming a live
+    /// stream "as is" to the given `dst`.
+    #[must_use]
+    pub fn with_dst(dst: &str) -> Self {
+        Self {
+            dst: OutputDstUrl::new(dst.parse().unwrap()).unwrap(),
+            label: None,
+            mixins: vec![],
+            extra_args: vec![],
+            mix_duration: MixDuration::default(),
+            srt_latency_ms: None,
+            dvr_segment_secs: None,
+            srt_passphrase: None,
+            reconnect: false,
+            loglevel: None,
+            format: None,
+            feeds_siblings: false,
+        }
+    }
+}
+
+/// Checks all the HTTP [MP3] `Mixin`s of the given [`spec::v1::Output`] being
+/// reachable.
+///
+/// # Errors
+///
+/// If any of the `Mixin`s is unreachable.
+///
+/// [MP3]: https://en.wikipedia.org/wiki/MP3
+async fn check_mixins_reachable(
+    spec: &spec::v1::Output,
+) -> Result<(), graphql::Error> {
+    for m in &spec.mixins {
+        m.src.check_reachable().await.map_err(|e| {
+            graphql::Error::new("UNREACHABLE_MIXIN_URL")
+                .status(StatusCode::BAD_REQUEST)
+                .message(&format!("Output.mixin.src is unreachable: {}", e))
+        })?;
+    }
+    Ok(())
+}
+
+/// Result of probing an `Output` destination.
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct ProbeResult {
+    /// Indicator whether the destination accepts connections.
+    pub success: bool,
+
+    /// Human-readable description of the result.
+    pub message: String,
+}
+
+impl From<anyhow::Result<()>> for ProbeResult {
+    fn from(res: anyhow::Result<()>) -> Self {
+        match res {
+            Ok(()) => Self {
+                success: true,
+                message: "Destination accepts connections".into(),
+            },
+            Err(e) => Self {
+                success: false,
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+/// Latency added to a live stream by a `Restream` when re-streaming it to an
+/// `Output`.
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct OutputLatency {
+    /// ID of the `Output` this latency is estimated for.
+    pub output_id: OutputId,
+
+    /// Estimated latency in milliseconds.
+    ///
+    /// It's a sum of the known buffers and delays of the re-streaming
+    /// pipeline, rather than a measured end-to-end value.
+    pub estimated: i32,
+}
+
+/// Diagnostics of a [TeamSpeak] connection used by a `Mixin`.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct TeamspeakDiagnostics {
+    /// ID of the `Mixin` this connection is used by.
+    pub mixin_id: MixinId,
+
+    /// Host (with optional port) of the [TeamSpeak] server.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub host: String,
+
+    /// [TeamSpeak] channel being joined.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub channel: String,
+
+    /// Nickname the `Mixin` is represented with in the [TeamSpeak] channel.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub name: String,
+
+    /// Current state of the connection.
+    pub state: teamspeak::ConnectionState,
+
+    /// Number of reconnection attempts performed so far.
+    pub reconnects: i32,
+}
+
+/// Usage of the disk where recorded files of `Output`s are stored.
+#[derive(Clone, Copy, Debug, GraphQLObject, PartialEq)]
+pub struct DvrDiskUsage {
+    /// Total size of the disk (in bytes).
+    pub total_bytes: f64,
+
+    /// Disk space (in bytes) available for new recordings.
+    pub free_bytes: f64,
+
+    /// Disk space (in bytes) occupied by the recorded files.
+    pub used_by_recordings: f64,
+}
+
+impl From<dvr::DiskUsage> for DvrDiskUsage {
+    #[allow(clippy::cast_precision_loss)] // bytes fit into `f64` mantissa
+    fn from(usage: dvr::DiskUsage) -> Self {
+        Self {
+            total_bytes: usage.total_bytes as f64,
+            free_bytes: usage.free_bytes as f64,
+            used_by_recordings: usage.used_by_recordings as f64,
+        }
+    }
+}
+
+/// Aggregated summary of this server's `Restream`s.
+#[derive(Clone, Debug, Eq, GraphQLObject, PartialEq)]
+pub struct ServerInfo {
+    /// Total number of `Restream`s on this server.
+    pub restream_count: i32,
+
+    /// Number of `Input`s (including failover ones) having at least one
+    /// `InputEndpoint` being `ONLINE`.
+    pub inputs_online: i32,
+
+    /// Number of `Output`s being `ONLINE`.
+    pub outputs_online: i32,
+
+    /// Number of `Output`s being enabled.
+    pub outputs_enabled: i32,
+
+    /// Version of this server.
+    pub version: String,
+}
+
+impl ServerInfo {
+    /// Computes a new [`ServerInfo`] out of the current [`State`].
+    #[must_use]
+    pub fn new(state: &State) -> Self {
+        fn count_online_inputs(input: &Input) -> i32 {
+            let mut count = i32::from(
+                input.endpoints.iter().any(|e| e.status == Status::Online),
+            );
+            if let Some(InputSrc::Failover(s)) = &input.src {
+                count += s.inputs.iter().map(count_online_inputs).sum::<i32>();
+            }
+            count
+        }
+
+        let mut info = Self {
+            restream_count: 0,
+            inputs_online: 0,
+            outputs_online: 0,
+            outputs_enabled: 0,
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+        };
+        for r in state.restreams.lock_ref().iter() {
+            info.restream_count += 1;
+            info.inputs_online += count_online_inputs(&r.input);
+            for o in &r.outputs {
+                info.outputs_online += i32::from(o.status == Status::Online);
+                info.outputs_enabled += i32::from(o.enabled);
+            }
+        }
+        info
+    }
+}
+
+#[cfg(test)]
+mod probe_result_spec {
+    use std::net::TcpListener;
+
+    use url::Url;
+
+    use crate::state::OutputDstUrl;
+
+    use super::ProbeResult;
+
+    async fn probe(url: &str) -> ProbeResult {
+        OutputDstUrl::new(Url::parse(url).unwrap())
+            .unwrap()
+            .probe()
+            .await
+            .into()
+    }
+
+    #[actix_rt::test]
+    async fn succeeds_on_accepting_destination() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let res = probe(&format!("rtmp://127.0.0.1:{}/live/key", port)).await;
+
+        assert!(res.success, "{:?}", res);
+    }
+
+    #[actix_rt::test]
+    async fn fails_on_refused_connection() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let res = probe(&format!("rtmp://127.0.0.1:{}/live/key", port)).await;
+
+        assert!(!res.success, "{:?}", res);
+        assert!(
+            res.message.contains(&format!("127.0.0.1:{}", port)),
+            "{}",
+            res.message,
+        );
+        assert!(!res.message.contains("key"), "secret is exposed");
+    }
+
+    #[actix_rt::test]
+    async fn skips_srt_destination() {
+        let res = probe("srt://127.0.0.1:1?streamid=key").await;
+
+        assert!(res.success, "{:?}", res);
+    }
+}
+
+#[cfg(test)]
+mod server_info_spec {
+    use crate::{
+        spec,
+        state::{State, Status},
+    };
+
+    use super::ServerInfo;
+
+    #[test]
+    fn summarizes_state() {
+        let state = State::with_restreams(vec![
+            spec::v1::Restream {
+                outputs: vec![
+                    spec::v1::Output::with_dst("rtmp://example.com/live/a"),
+                    spec::v1::Output::with_dst("rtmp://example.com/live/b"),
+                    spec::v1::Output {
+                        enabled: false,
+                        ..spec::v1::Output::with_dst(
+                            "rtmp://example.com/live/c",
+                        )
+                    },
+                ],
+                ..spec::v1::Restream::with_key("first")
+            },
+            spec::v1::Restream {
+                outputs: vec![spec::v1::Output {
+                    enabled: false,
+                    ..spec::v1::Output::with_dst("rtmp://example.com/live/d")
+                }],
+                ..spec::v1::Restream::with_key("second")
+            },
+        ]);
+        {
+            let mut restreams = state.restreams.lock_mut();
+            let first = &mut restreams[0];
+            first.input.endpoints[0].status = Status::Online;
+            first.outputs[0].status = Status::Online;
+        }
+
+        assert_eq!(
+            ServerInfo::new(&state),
+            ServerInfo {
+                restream_count: 2,
+                inputs_online: 1,
+                outputs_online: 1,
+                outputs_enabled: 2,
+                version: env!("CARGO_PKG_VERSION").to_owned(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod max_mixins_spec {
+    use actix_web::test;
+    use juniper::Variables;
+    use structopt::StructOpt as _;
+
+    use crate::{
+        api::graphql::Context,
+        cli::Opts,
+        spec,
+        state::{MixinSrcUrl, State},
+    };
+
+    use super::{schema, OutputSpec};
+
+    fn with_mixins(count: usize) -> OutputSpec {
+        OutputSpec {
+            mixins: (0..count)
+                .map(|n| {
+                    let url = format!("https://example.com/{}.mp3", n);
+                    MixinSrcUrl::new(url.parse().unwrap()).unwrap()
+                })
+                .collect(),
+            ..OutputSpec::with_dst("rtmp://example.com/live/test")
+        }
+    }
+
+    #[test]
+    fn enforces_configured_limit() {
+        for max in &[0, 5, 8] {
+            let spec = with_mixins(*max).into_spec(*max);
+            assert!(spec.is_ok(), "disallows {} mixins: {:?}", max, spec);
+
+            let err = with_mixins(max + 1).into_spec(*max).unwrap_err();
+            assert_eq!(err.code, "TOO_MUCH_MIXIN_URLS");
+        }
+    }
+
+    #[actix_rt::test]
+    async fn rejects_batch_outputs_over_configured_limit() {
+        let opts = Opts::from_iter(&[
+            "ephyr-restreamer",
+            "--max-mixins",
+            "1",
+            "--check-mixin-urls",
+            "false",
+        ]);
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("test")]);
+        let id = state.restreams.lock_ref()[0].id;
+        let ctx = Context::new(
+            test::TestRequest::default()
+                .app_data(opts)
+                .app_data(state.clone())
+                .to_http_request(),
+        );
+        let query = format!(
+            r#"mutation {{
+                setOutputs(restreamId: "{}", outputs: [
+                    {{ dst: "rtmp://example.com/live/a" }},
+                    {{
+                        dst: "rtmp://example.com/live/b",
+                        mixins: [
+                            "https://example.com/0.mp3",
+                            "https://example.com/1.mp3",
+                        ],
+                    }},
+                ])
+            }}"#,
+            id,
+        );
+
+        let (_, errs) =
+            juniper::execute(&query, None, &schema(), &Variables::new(), &ctx)
+                .await
+                .unwrap();
+
+        assert_eq!(errs.len(), 1, "errors: {:?}", errs);
+        let code = errs[0]
+            .error()
+            .extensions()
+            .as_object_value()
+            .and_then(|o| o.get_field_value("code"))
+            .and_then(|v| v.as_scalar_value::<String>())
+            .cloned();
+        assert_eq!(code.as_deref(), Some("TOO_MUCH_MIXIN_URLS"));
+        assert!(state.restreams.lock_ref()[0].outputs.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod server_time_spec {
+    use chrono::{DateTime, Duration, Utc};
+    use juniper::Variables;
+
+    use crate::api::graphql::Context;
+
+    use super::schema;
+
+    #[actix_rt::test]
+    async fn returns_current_time() {
+        let (res, errs) = juniper::execute(
+            "{ serverTime }",
+            None,
+            &schema(),
+            &Variables::new(),
+            &Context::fake(),
+        )
+        .await
+        .unwrap();
+        assert!(errs.is_empty(), "errors: {:?}", errs);
+
+        let time = res
+            .as_object_value()
+            .and_then(|o| o.get_field_value("serverTime"))
+            .and_then(|v| v.as_scalar_value::<String>())
+            .unwrap();
+        let time = DateTime::parse_from_rfc3339(time).unwrap();
+        let skew = Utc::now().signed_duration_since(time);
+        assert!(
+            skew >= Duration::zero() && skew < Duration::seconds(5),
+            "unexpected server time: {}",
+            time,
+        );
+    }
+}
+
+#[cfg(test)]
+mod fake_context_spec {
+    use juniper::Variables;
+
+    use crate::api::graphql::Context;
+
+    use super::schema;
+
+    #[actix_rt::test]
+    async fn errors_instead_of_panicking() {
+        for query in &["{ info { publicHost } }", "{ allRestreams { id } }"] {
+            let (res, errs) = juniper::execute(
+                query,
+                None,
+                &schema(),
+                &Variables::new(),
+                &Context::fake(),
+            )
+            .await
+            .unwrap();
+
+            assert!(res.is_null(), "data returned for: {}", query);
+            assert_eq!(errs.len(), 1, "errors for {}: {:?}", query, errs);
+            let code = errs[0]
+                .error()
+                .extensions()
+                .as_object_value()
+                .and_then(|o| o.get_field_value("code"))
+                .and_then(|v| v.as_scalar_value::<String>())
+                .cloned();
+            assert_eq!(code.as_deref(), Some("MISSING_APP_DATA"));
+        }
+    }
+}
@@ -28,7 +28,8 @@ impl Context {
         Self(Some(SendWrapper::new(req)))
     }
 
-    /// Creates a fake [`Context`], which panics on use.
+    /// Creates a fake [`Context`], which has no [`HttpRequest`] to provide
+    /// data from, so its accessors return errors.
     ///
     /// Intended for situations where we cannot provide [`HttpRequest`] for
     /// operation execution (running introspection locally, for example).
@@ -40,21 +41,91 @@ impl Context {
 
     /// Returns [`cli::Opts`] parameters stored in [`HttpRequest`]'s context.
     ///
+    /// # Errors
+    ///
+    /// If this [`Context`] is [fake][1] or has no [`cli::Opts`] stored.
+    ///
     /// [`cli::Opts`]: crate::cli::Opts
+    /// [1]: Context::fake
     #[inline]
-    #[must_use]
-    pub fn config(&self) -> &crate::cli::Opts {
-        self.app_data::<crate::cli::Opts>().unwrap()
+    pub fn config(&self) -> Result<&crate::cli::Opts, Error> {
+        self.require_app_data::<crate::cli::Opts>()
     }
 
     /// Returns current [`State`] stored in [`HttpRequest`]'s context.
     ///
+    /// # Errors
+    ///
+    /// If this [`Context`] is [fake][1] or has no [`State`] stored.
+    ///
     /// [`State`]: crate::State
+    /// [1]: Context::fake
+    #[inline]
+    pub fn state(&self) -> Result<&crate::State, Error> {
+        self.require_app_data::<crate::State>()
+    }
+
+    /// Returns application data of the given type stored in [`HttpRequest`]'s
+    /// context.
+    ///
+    /// # Errors
+    ///
+    /// If this [`Context`] is [fake][1] or has no such data stored.
+    ///
+    /// [1]: Context::fake
+    fn require_app_data<T: 'static>(&self) -> Result<&T, Error> {
+        self.0
+            .as_ref()
+            .and_then(|req| req.app_data::<T>())
+            .ok_or_else(|| {
+                Error::new("MISSING_APP_DATA")
+                    .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                    .message(&format!(
+                        "No `{}` is provided for executing the operation",
+                        std::any::type_name::<T>(),
+                    ))
+            })
+    }
+
+    /// Returns [`Role`] the [`HttpRequest`] has been authorized with.
+    ///
+    /// [`Role::Admin`] is assumed if the [`HttpRequest`] has been authorized
+    /// with no [`Role`] explicitly (no password is set, for example), or this
+    /// [`Context`] is [fake][1].
+    ///
+    /// [1]: Context::fake
     #[inline]
     #[must_use]
-    pub fn state(&self) -> &crate::State {
-        self.app_data::<crate::State>().unwrap()
+    pub fn role(&self) -> Role {
+        self.0
+            .as_ref()
+            .and_then(|req| req.extensions().get::<Role>().copied())
+            .unwrap_or(Role::Admin)
     }
+
+    /// Ensures the [`HttpRequest`] has been authorized with [`Role::Admin`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`HttpRequest`] has been authorized with [`Role::Viewer`] only.
+    pub fn require_admin(&self) -> Result<(), Error> {
+        match self.role() {
+            Role::Admin => Ok(()),
+            Role::Viewer => Err(Error::new("ADMIN_REQUIRED")
+                .status(http::StatusCode::FORBIDDEN)
+                .message("Admin password required for this action")),
+        }
+    }
+}
+
+/// Scope of access to GraphQL API an [`HttpRequest`] is authorized with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    /// Full access, including mutations.
+    Admin,
+
+    /// Read-only access, allowing queries and subscriptions only.
+    Viewer,
 }
 
 impl Deref for Context {
@@ -1,10 +1,19 @@
 //! CLI (command line interface).
 
-use std::{fmt, net::IpAddr, path::PathBuf, str::FromStr as _};
+use std::{
+    fmt, fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
 use anyhow::anyhow;
-use ephyr_log::slog;
-use structopt::StructOpt;
+use ephyr_log::{slog, ModuleFilters};
+use serde::{de::Error as _, Deserialize, Deserializer};
+use structopt::{clap, StructOpt};
+
+use crate::state::FfmpegLogLevel;
 
 /// CLI (command line interface) of the re-streamer server.
 #[derive(Clone, Debug, StructOpt)]
@@ -14,6 +23,24 @@ pub struct Opts {
     #[structopt(short, long, help = "Enables debug mode")]
     pub debug: bool,
 
+    /// Path to a [TOML] (or JSON, if it has `.json` extension) file to read
+    /// the values of [`Opts`] from.
+    ///
+    /// Values of the file are used only for the [`Opts`] not specified
+    /// explicitly via CLI flags or environment variables.
+    ///
+    /// [TOML]: https://toml.io
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_CONFIG",
+        help = "Path to a config file",
+        long_help = "Path to a TOML (or JSON, if it has `.json` extension) \
+                     config file with values of the options named as \
+                     `snake_case` keys. CLI flags and environment variables \
+                     take precedence over the values of the file."
+    )]
+    pub config: Option<PathBuf>,
+
     /// IP address for the server to listen client HTTP requests on.
     #[structopt(
         long,
@@ -35,6 +62,22 @@ pub struct Opts {
     )]
     pub client_http_port: u16,
 
+    /// Origins allowed to perform [CORS] requests to the client HTTP server.
+    ///
+    /// If empty, then only same-origin requests are allowed.
+    ///
+    /// [CORS]: https://developer.mozilla.org/en-US/docs/Web/HTTP/CORS
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_CORS_ALLOWED_ORIGINS",
+        use_delimiter = true,
+        help = "Origins allowed for CORS requests to client HTTP",
+        long_help = "Comma-separated list of origins allowed to perform CORS \
+                     requests to the client HTTP server (only same-origin \
+                     requests are allowed if none specified)"
+    )]
+    pub cors_allowed_origins: Vec<String>,
+
     /// IP address for the server to listen RTMP callback HTTP requests on.
     #[structopt(
         long,
@@ -68,6 +111,43 @@ pub struct Opts {
     )]
     pub state_path: PathBuf,
 
+    /// Path to a directory to keep rolling snapshots of the server's state
+    /// spec in.
+    ///
+    /// If [`None`], then no snapshots are made.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SNAPSHOTS_DIR",
+        help = "Path to a dir to keep state snapshots in",
+        long_help = "Path to a directory to keep rolling snapshots of the \
+                     server's state spec in (disabled by default)"
+    )]
+    pub snapshots_dir: Option<PathBuf>,
+
+    /// Interval to make snapshots of the server's state spec with.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SNAPSHOTS_INTERVAL",
+        default_value = "1h",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Interval to make state snapshots with",
+        long_help = "Interval to make snapshots of the server's state spec \
+                     with"
+    )]
+    pub snapshots_interval: Duration,
+
+    /// Number of the latest snapshots of the server's state spec to keep.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SNAPSHOTS_KEEP",
+        default_value = "24",
+        parse(try_from_str = Self::parse_snapshots_keep),
+        help = "Number of state snapshots to keep",
+        long_help = "Number of the latest snapshots of the server's state \
+                     spec to keep"
+    )]
+    pub snapshots_keep: usize,
+
     /// Path to [SRS] installation directory.
     ///
     /// [SRS]: https://github.com/ossrs/srs
@@ -100,6 +180,22 @@ pub struct Opts {
     )]
     pub srs_http_dir: PathBuf,
 
+    /// Grace period to wait before kicking off [SRS] clients of a disabled
+    /// (or re-keyed) `Input`, so they're kept if it comes back meanwhile.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_SRS_KICKOFF_GRACE",
+        default_value = "2s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Grace period before kicking SRS clients off",
+        long_help = "Grace period to wait before kicking off SRS clients of \
+                     a disabled (or re-keyed) input, so they're kept if it \
+                     comes back meanwhile (zero kicks them right away)"
+    )]
+    pub srs_kickoff_grace: Duration,
+
     /// Path to [FFmpeg] binary.
     ///
     /// [FFmpeg]: https://ffmpeg.org
@@ -113,6 +209,292 @@ pub struct Opts {
     )]
     pub ffmpeg_path: PathBuf,
 
+    /// Maximum duration to wait for [FFmpeg] processes to be stopped on
+    /// shutdown.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_SHUTDOWN_TIMEOUT",
+        default_value = "5s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Max duration to wait for FFmpeg processes on shutdown",
+        long_help = "Maximum duration to wait for FFmpeg processes to be \
+                     stopped on shutdown, before exiting anyway"
+    )]
+    pub ffmpeg_shutdown_timeout: Duration,
+
+    /// Duration of [FFmpeg] copying (re-streaming "as is") process to keep
+    /// running before it's considered online.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_COPY_ONLINE_DELAY",
+        default_value = "5s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Delay before considering copying FFmpeg process online",
+        long_help = "Duration of FFmpeg copying (re-streaming \"as is\") \
+                     process to keep running before it's considered online"
+    )]
+    pub ffmpeg_copy_online_delay: Duration,
+
+    /// Duration of [FFmpeg] transcoding process to keep running before it's
+    /// considered online.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_TRANSCODING_ONLINE_DELAY",
+        default_value = "10s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Delay before considering transcoding FFmpeg process online",
+        long_help = "Duration of FFmpeg transcoding process to keep running \
+                     before it's considered online"
+    )]
+    pub ffmpeg_transcoding_online_delay: Duration,
+
+    /// Duration of [FFmpeg] mixing process to keep running before it's
+    /// considered online.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_MIXING_ONLINE_DELAY",
+        default_value = "10s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Delay before considering mixing FFmpeg process online",
+        long_help = "Duration of FFmpeg mixing process to keep running \
+                     before it's considered online"
+    )]
+    pub ffmpeg_mixing_online_delay: Duration,
+
+    /// Maximum duration of [FFmpeg] process to stay initializing before it's
+    /// considered wedged and is restarted.
+    ///
+    /// Should be greater than online delays. Zero disables restarting.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_INITIALIZING_TIMEOUT",
+        default_value = "60s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Timeout of FFmpeg process initializing before restarting it",
+        long_help = "Maximum duration of FFmpeg process to stay initializing \
+                     before it's considered wedged and is restarted \
+                     (should be greater than online delays, \
+                     zero disables restarting)"
+    )]
+    pub ffmpeg_initializing_timeout: Duration,
+
+    /// Default level of logs (`-loglevel`) to spawn [FFmpeg] processes with,
+    /// unless overridden by an `Output`.
+    ///
+    /// If [`None`], then [FFmpeg] defaults are used.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_LOGLEVEL",
+        help = "Default FFmpeg log level",
+        long_help = "Default level of logs (-loglevel) to spawn FFmpeg \
+                     processes with, unless overridden by an output \
+                     (FFmpeg defaults are used if not specified)"
+    )]
+    pub ffmpeg_loglevel: Option<FfmpegLogLevel>,
+
+    /// Maximum number of packets for [FFmpeg] mixing process to buffer while
+    /// waiting for all its output streams to be initialized
+    /// (`-max_muxing_queue_size`).
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_MAX_MUXING_QUEUE_SIZE",
+        default_value = "50000000",
+        help = "Max muxing queue size of mixing FFmpeg process",
+        long_help = "Maximum number of packets for FFmpeg mixing process to \
+                     buffer while waiting for all its output streams to be \
+                     initialized (-max_muxing_queue_size)"
+    )]
+    pub ffmpeg_max_muxing_queue_size: u32,
+
+    /// Maximum number of packets for [FFmpeg] mixing process to queue when
+    /// reading from a `Mixin` input (`-thread_queue_size`).
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_FFMPEG_THREAD_QUEUE_SIZE",
+        default_value = "512",
+        help = "Thread queue size of mixing FFmpeg process inputs",
+        long_help = "Maximum number of packets for FFmpeg mixing process to \
+                     queue when reading from a mixin input \
+                     (-thread_queue_size)"
+    )]
+    pub ffmpeg_thread_queue_size: u32,
+
+    /// Indicator whether the mixed audio of `Output`s with `Mixin`s should be
+    /// normalized to [`Opts::normalize_target`] loudness ([EBU R128]).
+    ///
+    /// [EBU R128]: https://en.wikipedia.org/wiki/EBU_R_128
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_NORMALIZE",
+        default_value = "false",
+        parse(try_from_str),
+        help = "Whether to normalize loudness of mixed audio",
+        long_help = "Indicator whether the mixed audio of outputs with \
+                     mixins should be normalized (EBU R128) to the target \
+                     integrated loudness"
+    )]
+    pub normalize: bool,
+
+    /// Target integrated loudness (in [LUFS]) to normalize the mixed audio to,
+    /// if [`Opts::normalize`] is enabled.
+    ///
+    /// [LUFS]: https://en.wikipedia.org/wiki/LUFS
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_NORMALIZE_TARGET",
+        default_value = "-16",
+        allow_hyphen_values = true,
+        parse(try_from_str = Self::parse_loudness_target),
+        help = "Target integrated loudness (LUFS) of mixed audio",
+        long_help = "Target integrated loudness (in LUFS, from -70 to -5) to \
+                     normalize the mixed audio to"
+    )]
+    pub normalize_target: i8,
+
+    /// Maximum delay (in seconds) for [FFmpeg] to wait between reconnection
+    /// attempts to a remote [HLS] source being pulled.
+    ///
+    /// Zero value disables reconnection at all.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_HLS_RECONNECT_DELAY_MAX",
+        default_value = "5",
+        help = "Max delay (in seconds) between HLS input reconnects",
+        long_help = "Maximum delay (in seconds) for FFmpeg to wait between \
+                     reconnection attempts to a remote HLS source being \
+                     pulled. Zero value disables reconnection at all."
+    )]
+    pub hls_reconnect_delay_max: u32,
+
+    /// Indicator whether [FFmpeg] should reconnect to a remote [HLS] source
+    /// being pulled on HTTP `4xx` and `5xx` errors too (like transient `404`s
+    /// of not yet available segments), not only on network errors.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_HLS_RECONNECT_ON_HTTP_ERROR",
+        default_value = "true",
+        parse(try_from_str),
+        help = "Whether to reconnect HLS inputs on HTTP errors",
+        long_help = "Indicator whether FFmpeg should reconnect to a remote \
+                     HLS source being pulled on HTTP 4xx and 5xx errors too \
+                     (like transient 404s of not yet available segments), \
+                     not only on network errors."
+    )]
+    pub hls_reconnect_on_http_error: bool,
+
+    /// Indicator whether HTTP [MP3] `Mixin` URLs should be checked for being
+    /// reachable before being added to an `Output`.
+    ///
+    /// [MP3]: https://en.wikipedia.org/wiki/MP3
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_CHECK_MIXIN_URLS",
+        default_value = "true",
+        parse(try_from_str),
+        help = "Whether to check HTTP MP3 mixin URLs being reachable",
+        long_help = "Indicator whether HTTP MP3 mixin URLs should be checked \
+                     (with a HEAD request responding 2xx status and audio \
+                     content type) before being added to an output."
+    )]
+    pub check_mixin_urls: bool,
+
+    /// Maximum number of `Mixin`s allowed to be set for a single `Output`.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_MAX_MIXINS",
+        default_value = "5",
+        help = "Max number of mixins per output",
+        long_help = "Maximum number of mixins allowed to be set for a single \
+                     output via GraphQL API"
+    )]
+    pub max_mixins: usize,
+
+    /// Maximum number of concurrent connections to [TeamSpeak] servers.
+    ///
+    /// Zero value disables the limit at all.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_TS_MAX_CONNECTIONS",
+        default_value = "100",
+        help = "Max number of concurrent TeamSpeak connections",
+        long_help = "Maximum number of concurrent connections to TeamSpeak \
+                     servers. Mixins beyond this limit are queued until some \
+                     connection is released. Zero value disables the limit \
+                     at all."
+    )]
+    pub ts_max_connections: usize,
+
+    /// Name to represent this application with in [TeamSpeak] channels.
+    ///
+    /// Used as a prefix of the client name of each `Mixin` having no `name`
+    /// query parameter specified explicitly in its URL. If [`None`], then
+    /// [`DEFAULT_NAME_PREFIX`] is used.
+    ///
+    /// [`DEFAULT_NAME_PREFIX`]: crate::teamspeak::DEFAULT_NAME_PREFIX
+    /// [TeamSpeak]: https://teamspeak.com
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_TS_NAME",
+        help = "Name to represent this server with in TeamSpeak",
+        long_help = "Name to represent this server with in TeamSpeak \
+                     channels. Prefixes client names of mixins having no \
+                     `name` query parameter in their URLs, so several \
+                     servers joining the same TeamSpeak channel can be \
+                     distinguished."
+    )]
+    pub ts_name: Option<String>,
+
+    /// Maximum length of `Restream` and `Input` keys.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_MAX_KEY_LEN",
+        default_value = "20",
+        help = "Max length of Restream and Input keys",
+        long_help = "Maximum length of Restream and Input keys, used to form \
+                     their endpoints URLs"
+    )]
+    pub max_key_len: usize,
+
+    /// Maximum duration to wait for [TeamSpeak] connections to disconnect
+    /// cleanly on shutdown.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_TS_DISCONNECT_TIMEOUT",
+        default_value = "5s",
+        parse(try_from_str = humantime::parse_duration),
+        help = "Max duration to wait for TeamSpeak disconnects on shutdown",
+        long_help = "Maximum duration to wait for TeamSpeak connections to \
+                     disconnect cleanly on shutdown, before proceeding with \
+                     it anyway"
+    )]
+    pub ts_disconnect_timeout: Duration,
+
     /// Host to access the re-streamer server in public networks.
     ///
     /// If [`None`], then it will be auto-detected.
@@ -134,16 +516,43 @@ pub struct Opts {
                 OFF | CRIT | ERRO | WARN | INFO | DEBG | TRCE"
     )]
     pub verbose: Option<slog::Level>,
+
+    /// Per-module overrides of the server logs verbosity level.
+    #[structopt(
+        long,
+        env = "EPHYR_RESTREAMER_LOG_FILTERS",
+        default_value = "",
+        help = "Per-module logs verbosity levels: \
+                <module>=<level>[,<module>=<level>...]",
+        long_help = "Per-module overrides of logs verbosity level, as \
+                     comma-separated list of `<module>=<level>` pairs \
+                     (like `hyper=info,actix_web=warn`). A filter applies to \
+                     the module and all its submodules."
+    )]
+    pub log_filters: ModuleFilters,
 }
 
 impl Opts {
-    /// Parses CLI [`Opts`] from command line arguments.
+    /// Parses CLI [`Opts`] from command line arguments, back-filling the
+    /// unspecified ones from the [`Opts::config`] file, if any.
     ///
     /// Prints the error message and quits the program in case of failure.
-    #[inline]
     #[must_use]
     pub fn from_args() -> Self {
-        <Self as StructOpt>::from_args()
+        let matches = Self::clap().get_matches();
+        let mut opts = Self::from_clap(&matches);
+        if let Some(path) = opts.config.clone() {
+            ConfigFile::load(&path)
+                .unwrap_or_else(|e| {
+                    clap::Error::with_description(
+                        &e.to_string(),
+                        clap::ErrorKind::InvalidValue,
+                    )
+                    .exit()
+                })
+                .back_fill(&mut opts, &matches);
+        }
+        opts
     }
 
     /// Parses [`slog::Level`] from the given string.
@@ -168,6 +577,340 @@ impl Opts {
             )
         })
     }
+
+    /// Parses target integrated loudness (in [LUFS]) from the given string,
+    /// ensuring it's in range supported by [FFmpeg]'s `loudnorm` filter.
+    ///
+    /// # Errors
+    ///
+    /// If the string is not an integer in `-70..=-5` range.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [LUFS]: https://en.wikipedia.org/wiki/LUFS
+    pub fn parse_loudness_target(val: &str) -> Result<i8, anyhow::Error> {
+        let target = val.parse::<i8>()?;
+        if !(-70..=-5).contains(&target) {
+            return Err(anyhow!(
+                "'{}' is invalid target loudness, allowed range is -70..-5",
+                val,
+            ));
+        }
+        Ok(target)
+    }
+
+    /// Parses a number of the latest state snapshots to keep from the given
+    /// string, ensuring it keeps at least the one just made.
+    ///
+    /// # Errors
+    ///
+    /// If the string is not a positive integer.
+    pub fn parse_snapshots_keep(val: &str) -> Result<usize, anyhow::Error> {
+        let keep = val.parse::<usize>()?;
+        if keep == 0 {
+            return Err(anyhow!(
+                "'{}' is invalid number of snapshots to keep, it should be \
+                 positive",
+                val,
+            ));
+        }
+        Ok(keep)
+    }
+}
+
+/// Partial [`Opts`] read from an [`Opts::config`] file.
+///
+/// Its keys are named after the [`Opts`] fields, and none of them is
+/// mandatory.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Value of [`Opts::debug`].
+    pub debug: Option<bool>,
+
+    /// Value of [`Opts::client_http_ip`].
+    pub client_http_ip: Option<IpAddr>,
+
+    /// Value of [`Opts::client_http_port`].
+    pub client_http_port: Option<u16>,
+
+    /// Value of [`Opts::cors_allowed_origins`].
+    pub cors_allowed_origins: Option<Vec<String>>,
+
+    /// Value of [`Opts::callback_http_ip`].
+    pub callback_http_ip: Option<IpAddr>,
+
+    /// Value of [`Opts::callback_http_port`].
+    pub callback_http_port: Option<u16>,
+
+    /// Value of [`Opts::state_path`].
+    pub state_path: Option<PathBuf>,
+
+    /// Value of [`Opts::snapshots_dir`].
+    pub snapshots_dir: Option<PathBuf>,
+
+    /// Value of [`Opts::snapshots_interval`].
+    #[serde(default, with = "serde_humantime::option")]
+    pub snapshots_interval: Option<Duration>,
+
+    /// Value of [`Opts::snapshots_keep`].
+    #[serde(
+        default,
+        deserialize_with = "ConfigFile::deserialize_snapshots_keep"
+    )]
+    pub snapshots_keep: Option<usize>,
+
+    /// Value of [`Opts::srs_path`].
+    pub srs_path: Option<PathBuf>,
+
+    /// Value of [`Opts::srs_http_dir`].
+    pub srs_http_dir: Option<PathBuf>,
+
+    /// Value of [`Opts::srs_kickoff_grace`].
+    #[serde(default, with = "serde_humantime::option")]
+    pub srs_kickoff_grace: Option<Duration>,
+
+    /// Value of [`Opts::ffmpeg_path`].
+    pub ffmpeg_path: Option<PathBuf>,
+
+    /// Value of [`Opts::ffmpeg_shutdown_timeout`].
+    #[serde(default, with = "serde_humantime::option")]
+    pub ffmpeg_shutdown_timeout: Option<Duration>,
+
+    /// Value of [`Opts::ffmpeg_copy_online_delay`].
+    #[serde(default, with = "serde_humantime::option")]
+    pub ffmpeg_copy_online_delay: Option<Duration>,
+
+    /// Value of [`Opts::ffmpeg_transcoding_online_delay`].
+    #[serde(default, with = "serde_humantime::option")]
+    pub ffmpeg_transcoding_online_delay: Option<Duration>,
+
+    /// Value of [`Opts::ffmpeg_mixing_online_delay`].
+    #[serde(default, with = "serde_humantime::option")]
+    pub ffmpeg_mixing_online_delay: Option<Duration>,
+
+    /// Value of [`Opts::ffmpeg_initializing_timeout`].
+    #[serde(default, with = "serde_humantime::option")]
+    pub ffmpeg_initializing_timeout: Option<Duration>,
+
+    /// Value of [`Opts::ffmpeg_loglevel`].
+    pub ffmpeg_loglevel: Option<FfmpegLogLevel>,
+
+    /// Value of [`Opts::ffmpeg_max_muxing_queue_size`].
+    pub ffmpeg_max_muxing_queue_size: Option<u32>,
+
+    /// Value of [`Opts::ffmpeg_thread_queue_size`].
+    pub ffmpeg_thread_queue_size: Option<u32>,
+
+    /// Value of [`Opts::normalize`].
+    pub normalize: Option<bool>,
+
+    /// Value of [`Opts::normalize_target`].
+    #[serde(default, deserialize_with = "ConfigFile::deserialize_loudness")]
+    pub normalize_target: Option<i8>,
+
+    /// Value of [`Opts::hls_reconnect_delay_max`].
+    pub hls_reconnect_delay_max: Option<u32>,
+
+    /// Value of [`Opts::hls_reconnect_on_http_error`].
+    pub hls_reconnect_on_http_error: Option<bool>,
+
+    /// Value of [`Opts::check_mixin_urls`].
+    pub check_mixin_urls: Option<bool>,
+
+    /// Value of [`Opts::max_mixins`].
+    pub max_mixins: Option<usize>,
+
+    /// Value of [`Opts::ts_max_connections`].
+    pub ts_max_connections: Option<usize>,
+
+    /// Value of [`Opts::ts_name`].
+    pub ts_name: Option<String>,
+
+    /// Value of [`Opts::max_key_len`].
+    pub max_key_len: Option<usize>,
+
+    /// Value of [`Opts::ts_disconnect_timeout`].
+    #[serde(default, with = "serde_humantime::option")]
+    pub ts_disconnect_timeout: Option<Duration>,
+
+    /// Value of [`Opts::public_host`].
+    pub public_host: Option<String>,
+
+    /// Value of [`Opts::verbose`].
+    #[serde(default, deserialize_with = "ConfigFile::deserialize_log_level")]
+    pub verbose: Option<slog::Level>,
+
+    /// Value of [`Opts::log_filters`].
+    #[serde(default, deserialize_with = "ConfigFile::deserialize_from_str")]
+    pub log_filters: Option<ModuleFilters>,
+}
+
+/// Generates [`ConfigFile::back_fill()`] for the given [`ConfigFile`] fields,
+/// along with the [`ConfigFile::FIELDS`] listing them.
+macro_rules! back_fill {
+    ($($field:ident),* $(,)?) => {
+        /// Names of the [`ConfigFile`] fields being set to [`Opts`] by
+        /// [`ConfigFile::back_fill()`].
+        pub const FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+        /// Sets the values of this [`ConfigFile`] to the given [`Opts`], which
+        /// have been specified neither via CLI flags (according to the given
+        /// `matches`), nor via environment variables (as declared by
+        /// [`Opts::clap()`]).
+        pub fn back_fill(
+            self,
+            opts: &mut Opts,
+            matches: &clap::ArgMatches<'_>,
+        ) {
+            // `clap` reads the environment variable of an option right when
+            // it's declared, so has its value only if the variable is set.
+            let app = Opts::clap();
+            let is_set = |name: &str| {
+                matches.occurrences_of(name) > 0
+                    || app.p.opts.iter().any(|o| {
+                        o.b.name == name
+                            && matches!(o.v.env, Some((_, Some(_))))
+                    })
+            };
+
+            $(
+                if let Some(val) = self.$field {
+                    if !is_set(stringify!($field)) {
+                        opts.$field = val.into();
+                    }
+                }
+            )*
+        }
+    };
+}
+
+impl ConfigFile {
+    /// Reads a [`ConfigFile`] from the given `path`.
+    ///
+    /// The file is parsed as JSON if it has `.json` extension, or as [TOML]
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be read or parsed.
+    ///
+    /// [TOML]: https://toml.io
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            anyhow!("Failed to read '{}' config file: {}", path.display(), e)
+        })?;
+        let is_json = path.extension().map_or(false, |ext| ext == "json");
+        Self::parse(&contents, is_json).map_err(|e| {
+            anyhow!("Failed to parse '{}' config file: {}", path.display(), e)
+        })
+    }
+
+    /// Parses a [`ConfigFile`] from the given JSON or [TOML] `contents`.
+    ///
+    /// # Errors
+    ///
+    /// If the `contents` cannot be parsed.
+    ///
+    /// [TOML]: https://toml.io
+    pub fn parse(contents: &str, is_json: bool) -> Result<Self, anyhow::Error> {
+        Ok(if is_json {
+            serde_json::from_str(contents)?
+        } else {
+            toml::from_str(contents)?
+        })
+    }
+
+    back_fill! {
+        debug,
+        client_http_ip,
+        client_http_port,
+        cors_allowed_origins,
+        callback_http_ip,
+        callback_http_port,
+        state_path,
+        snapshots_dir,
+        snapshots_interval,
+        snapshots_keep,
+        srs_path,
+        srs_http_dir,
+        srs_kickoff_grace,
+        ffmpeg_path,
+        ffmpeg_shutdown_timeout,
+        ffmpeg_copy_online_delay,
+        ffmpeg_transcoding_online_delay,
+        ffmpeg_mixing_online_delay,
+        ffmpeg_initializing_timeout,
+        ffmpeg_loglevel,
+        ffmpeg_max_muxing_queue_size,
+        ffmpeg_thread_queue_size,
+        normalize,
+        normalize_target,
+        hls_reconnect_delay_max,
+        hls_reconnect_on_http_error,
+        check_mixin_urls,
+        max_mixins,
+        ts_max_connections,
+        ts_name,
+        max_key_len,
+        ts_disconnect_timeout,
+        public_host,
+        verbose,
+        log_filters,
+    }
+
+    /// Deserializes an optional target integrated loudness, validating it with
+    /// [`Opts::parse_loudness_target()`].
+    fn deserialize_loudness<'de, D>(de: D) -> Result<Option<i8>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<i8>::deserialize(de)?
+            .map(|v| Opts::parse_loudness_target(&v.to_string()))
+            .transpose()
+            .map_err(D::Error::custom)
+    }
+
+    /// Deserializes an optional number of state snapshots to keep, validating
+    /// it with [`Opts::parse_snapshots_keep()`].
+    fn deserialize_snapshots_keep<'de, D>(
+        de: D,
+    ) -> Result<Option<usize>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<usize>::deserialize(de)?
+            .map(|v| Opts::parse_snapshots_keep(&v.to_string()))
+            .transpose()
+            .map_err(D::Error::custom)
+    }
+
+    /// Deserializes an optional [`slog::Level`] with
+    /// [`Opts::parse_log_level()`].
+    fn deserialize_log_level<'de, D>(
+        de: D,
+    ) -> Result<Option<slog::Level>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(de)?
+            .map(|v| Opts::parse_log_level(&v))
+            .transpose()
+            .map_err(D::Error::custom)
+    }
+
+    /// Deserializes an optional value from a string with its [`FromStr`]
+    /// implementation.
+    fn deserialize_from_str<'de, D, T>(de: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        Option::<String>::deserialize(de)?
+            .map(|v| v.parse())
+            .transpose()
+            .map_err(D::Error::custom)
+    }
 }
 
 /// Error type indicating non-zero process exit code.
@@ -186,3 +929,107 @@ impl From<()> for Failure {
         Self
     }
 }
+
+#[cfg(test)]
+mod config_file_spec {
+    use std::iter;
+
+    use structopt::StructOpt as _;
+
+    use super::{ConfigFile, Opts};
+
+    const TOML: &str = r#"
+        client_http_port = 9090
+        max_mixins = 7
+        ffmpeg_initializing_timeout = "2m"
+        ts_name = "ephyr-test"
+    "#;
+
+    fn opts(args: &[&str], contents: &str, is_json: bool) -> Opts {
+        let matches = Opts::clap().get_matches_from(
+            iter::once("ephyr-restreamer").chain(args.iter().copied()),
+        );
+        let mut opts = Opts::from_clap(&matches);
+        ConfigFile::parse(contents, is_json)
+            .unwrap()
+            .back_fill(&mut opts, &matches);
+        opts
+    }
+
+    #[test]
+    fn uses_file_values_when_flags_absent() {
+        let opts = opts(&[], TOML, false);
+
+        assert_eq!(opts.client_http_port, 9090);
+        assert_eq!(opts.max_mixins, 7);
+        assert_eq!(opts.ffmpeg_initializing_timeout.as_secs(), 120);
+        assert_eq!(opts.ts_name.as_deref(), Some("ephyr-test"));
+        assert_eq!(opts.callback_http_port, 8081, "default is lost");
+    }
+
+    #[test]
+    fn overrides_file_values_with_flags() {
+        let opts = opts(
+            &["--client-http-port", "8080", "--ts-name", "cli"],
+            TOML,
+            false,
+        );
+
+        assert_eq!(opts.client_http_port, 8080);
+        assert_eq!(opts.ts_name.as_deref(), Some("cli"));
+        assert_eq!(opts.max_mixins, 7);
+    }
+
+    #[test]
+    fn parses_json() {
+        let opts = opts(&[], r#"{"max_mixins": 3, "debug": true}"#, true);
+
+        assert_eq!(opts.max_mixins, 3);
+        assert!(opts.debug);
+    }
+
+    #[test]
+    fn rejects_zero_snapshots_keep() {
+        let res = Opts::clap().get_matches_from_safe(vec![
+            "ephyr-restreamer",
+            "--snapshots-keep",
+            "0",
+        ]);
+        assert!(res.is_err());
+
+        let opts = opts(&["--snapshots-keep", "1"], "", false);
+        assert_eq!(opts.snapshots_keep, 1);
+    }
+
+    #[test]
+    fn covers_all_options() {
+        let app = Opts::clap();
+        let names = app.p.flags.iter().map(|f| f.b.name);
+        for name in names.chain(app.p.opts.iter().map(|o| o.b.name)) {
+            if ["config", "help", "version"].contains(&name) {
+                continue;
+            }
+            assert!(
+                ConfigFile::FIELDS.contains(&name),
+                "`{}` option is not back-filled from config file",
+                name,
+            );
+            let err =
+                ConfigFile::parse(&format!(r#"{{"{}": {{}}}}"#, name), true)
+                    .unwrap_err();
+            assert!(
+                !err.to_string().contains("unknown field"),
+                "`{}` option cannot be read from config file",
+                name,
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_and_invalid_values() {
+        assert!(ConfigFile::parse("unknown = 1", false).is_err());
+        assert!(ConfigFile::parse("normalize_target = 10", false).is_err());
+        assert!(ConfigFile::parse("verbose = \"LOUD\"", false).is_err());
+        assert!(ConfigFile::parse("snapshots_keep = 0", false).is_err());
+    }
+}
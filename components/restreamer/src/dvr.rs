@@ -24,6 +24,12 @@ use crate::state;
 /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
 static STORAGE: OnceCell<Storage> = OnceCell::new();
 
+/// Available disk space (in bytes) below which a warning is logged on starting
+/// a new [DVR] recording, as it's likely to fail soon.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+pub const LOW_FREE_SPACE_BYTES: u64 = 1024 * 1024 * 1024;
+
 /// Storage of [DVR] files.
 ///
 /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
@@ -72,32 +78,43 @@ impl Storage {
         Url::from_file_path(full).unwrap()
     }
 
-    /// Lists stored [DVR] files of the given [`state::Output`].
+    /// Lists stored [DVR] files of the given [`state::Output`], including the
+    /// ones in nested subdirectories.
     ///
     /// Returns them as relative paths to this [`Storage::root_path`].
     ///
     /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
     pub async fn list_files(&self, id: state::OutputId) -> Vec<String> {
-        let dir = &self.root_path;
+        let root = &self.root_path;
 
-        let mut output_dir = dir.clone();
+        let mut output_dir = root.clone();
         output_dir.push(id.to_string());
 
-        fs::read_dir(output_dir)
-            .try_flatten_stream()
-            .try_filter_map(|i| async move {
-                Ok(i.file_type().await?.is_file().then(|| i.path()).and_then(
-                    |p| Some(p.strip_prefix(dir).ok()?.display().to_string()),
-                ))
-            })
-            .try_collect()
-            .await
-            .unwrap_or_else(|e| {
+        let mut files = vec![];
+        let mut dirs = vec![output_dir];
+        while let Some(dir) = dirs.pop() {
+            let res: io::Result<()> = async {
+                let mut entries = fs::read_dir(dir).await?;
+                while let Some(i) = entries.next_entry().await? {
+                    let typ = i.file_type().await?;
+                    if typ.is_dir() {
+                        dirs.push(i.path());
+                    } else if typ.is_file() {
+                        if let Ok(p) = i.path().strip_prefix(root) {
+                            files.push(p.display().to_string());
+                        }
+                    }
+                }
+                Ok(())
+            }
+            .await;
+            if let Err(e) = res {
                 if e.kind() != io::ErrorKind::NotFound {
                     log::error!("Failed to list {} DVR files: {}", id, e);
                 }
-                vec![]
-            })
+            }
+        }
+        files
     }
 
     /// Removes a [DVR] file from this [`Storage`] identified by its relative
@@ -125,6 +142,25 @@ impl Storage {
         true
     }
 
+    /// Computes the current [`DiskUsage`] of the disk this [`Storage`] resides
+    /// on.
+    ///
+    /// # Errors
+    ///
+    /// If the disk space cannot be queried, or the stored [DVR] files fail to
+    /// be traversed.
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    pub async fn disk_usage(&self) -> io::Result<DiskUsage> {
+        let used_by_recordings = dir_size(&self.root_path).await?;
+        let (total_bytes, free_bytes) = disk_space(&self.root_path)?;
+        Ok(DiskUsage {
+            total_bytes,
+            free_bytes,
+            used_by_recordings,
+        })
+    }
+
     /// Cleans up any [DVR] files of this [`Storage`] not being associated with
     /// [`state::Output`]s of the given renewed [`state::Restream`]s.
     ///
@@ -160,6 +196,108 @@ impl Storage {
     }
 }
 
+/// Usage of a disk where [DVR] files are stored.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DiskUsage {
+    /// Total size of the disk (in bytes).
+    pub total_bytes: u64,
+
+    /// Disk space (in bytes) available for new recordings.
+    pub free_bytes: u64,
+
+    /// Disk space (in bytes) occupied by the stored [DVR] files.
+    ///
+    /// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+    pub used_by_recordings: u64,
+}
+
+impl DiskUsage {
+    /// Indicates whether the available disk space of this [`DiskUsage`] is
+    /// below the given `threshold` (in bytes).
+    #[inline]
+    #[must_use]
+    pub fn is_low(&self, threshold: u64) -> bool {
+        self.free_bytes < threshold
+    }
+}
+
+/// Computes the total size (in bytes) of all the files in the given `dir`,
+/// including the ones in nested subdirectories.
+///
+/// Non-existent `dir` is considered empty.
+///
+/// # Errors
+///
+/// If the `dir` fails to be traversed.
+async fn dir_size(dir: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = match fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        while let Some(i) = entries.next_entry().await? {
+            let meta = i.metadata().await?;
+            if meta.is_dir() {
+                dirs.push(i.path());
+            } else if meta.is_file() {
+                size += meta.len();
+            }
+        }
+    }
+    Ok(size)
+}
+
+/// Returns the total and the available space (in bytes) of the disk the given
+/// `path` resides on.
+///
+/// The `path` is not required to exist, as the disk is queried via its
+/// nearest existing ancestor.
+///
+/// # Errors
+///
+/// If the disk space cannot be queried.
+fn disk_space(path: &Path) -> io::Result<(u64, u64)> {
+    let path = path.ancestors().find(|p| p.exists()).unwrap_or(path);
+    Ok((fs2::total_space(path)?, fs2::available_space(path)?))
+}
+
+/// Logs a warning if the available disk space of the given `dir` is below the
+/// given `threshold` (in bytes), as recordings will silently fail once the
+/// disk is full.
+///
+/// Returns whether the warning has been logged.
+fn warn_if_low_free_space(dir: &Path, threshold: u64) -> bool {
+    let usage = match disk_space(dir) {
+        Ok((total_bytes, free_bytes)) => DiskUsage {
+            total_bytes,
+            free_bytes,
+            used_by_recordings: 0,
+        },
+        Err(e) => {
+            log::error!(
+                "Failed to check available disk space of {}: {}",
+                dir.display(),
+                e,
+            );
+            return false;
+        }
+    };
+    let is_low = usage.is_low(threshold);
+    if is_low {
+        log::warn!(
+            "Only {} bytes of disk space are available for DVR files in {}",
+            usage.free_bytes,
+            dir.display(),
+        );
+    }
+    is_low
+}
+
 /// Creates a new recording file path from the given DVR file [`Url`] (formed by
 /// [`Storage::file_url()`]) appended with the current timestamp in microseconds
 /// to ensure its uniqueness.
@@ -171,23 +309,54 @@ impl Storage {
 /// If cannot create a file path from the given [`Url`], or fails to create its
 /// parent directory.
 pub async fn new_file_path(url: &Url) -> io::Result<PathBuf> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    new_suffixed_file_path(url, &now.as_micros().to_string()).await
+}
+
+/// Creates a new [strftime] pattern of recording segments' file paths from the
+/// given DVR file [`Url`] (formed by [`Storage::file_url()`]) appended with
+/// the start time of each segment.
+///
+/// Also, ensures that the appropriate parent directory for the files exists.
+///
+/// # Errors
+///
+/// If cannot create a file path from the given [`Url`], or fails to create its
+/// parent directory.
+///
+/// [strftime]: https://man7.org/linux/man-pages/man3/strftime.3.html
+pub async fn new_segment_path_pattern(url: &Url) -> io::Result<PathBuf> {
+    new_suffixed_file_path(url, "%Y%m%d_%H%M%S").await
+}
+
+/// Creates a new file path from the given DVR file [`Url`] with its file name
+/// appended with the given `suffix`, ensuring that its parent directory
+/// exists.
+///
+/// # Errors
+///
+/// If cannot create a file path from the given [`Url`], or fails to create its
+/// parent directory.
+async fn new_suffixed_file_path(
+    url: &Url,
+    suffix: &str,
+) -> io::Result<PathBuf> {
     let mut path = url.to_file_path().map_err(|_| {
         io::Error::new(io::ErrorKind::Other, "File URL contains bad file path")
     })?;
 
     if let Some(dir) = path.parent() {
         fs::create_dir_all(dir).await?;
+        let _ = warn_if_low_free_space(dir, LOW_FREE_SPACE_BYTES);
     }
 
-    let now = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap();
-
     let mut file_name = OsString::new();
     if let Some(name) = path.file_stem() {
         file_name.push(name)
     }
-    file_name.push(format!("_{}.", now.as_micros()));
+    file_name.push(format!("_{}.", suffix));
     if let Some(ext) = path.extension() {
         file_name.push(ext)
     }
@@ -195,3 +364,96 @@ pub async fn new_file_path(url: &Url) -> io::Result<PathBuf> {
 
     Ok(path)
 }
+
+/// Returns [FFmpeg] muxer (`-f` format) to record a live stream into the file
+/// at the given `path` with, according to its extension.
+///
+/// Supported [DVR] containers are:
+/// - [FLV] (`.flv` extension), the default one;
+/// - [Matroska] (`.mkv` extension), resilient to recording interruptions;
+/// - [MPEG-TS] (`.ts` extension).
+///
+/// Returns [`None`] if the `path` has no supported extension.
+///
+/// [DVR]: https://en.wikipedia.org/wiki/Digital_video_recorder
+/// [FFmpeg]: https://ffmpeg.org
+/// [FLV]: https://en.wikipedia.org/wiki/Flash_Video
+/// [Matroska]: https://en.wikipedia.org/wiki/Matroska
+/// [MPEG-TS]: https://en.wikipedia.org/wiki/MPEG_transport_stream
+#[must_use]
+pub fn file_format<P: AsRef<Path>>(path: P) -> Option<&'static str> {
+    match path.as_ref().extension()?.to_str()? {
+        "flv" => Some("flv"),
+        "mkv" => Some("matroska"),
+        "ts" => Some("mpegts"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod disk_usage_spec {
+    use std::env;
+
+    use tokio::fs;
+    use uuid::Uuid;
+
+    use super::{
+        warn_if_low_free_space, DiskUsage, Storage, LOW_FREE_SPACE_BYTES,
+    };
+
+    #[actix_rt::test]
+    async fn sums_up_recordings_size() {
+        let root = env::temp_dir().join(format!("ephyr-{}", Uuid::new_v4()));
+        let nested = root.join(Uuid::new_v4().to_string()).join("live");
+        fs::create_dir_all(&nested).await.unwrap();
+        fs::write(root.join("orphan.flv"), vec![0; 100])
+            .await
+            .unwrap();
+        fs::write(nested.join("stream_1.mkv"), vec![0; 250])
+            .await
+            .unwrap();
+
+        let usage = Storage {
+            root_path: root.clone(),
+        }
+        .disk_usage()
+        .await
+        .unwrap();
+
+        assert_eq!(usage.used_by_recordings, 350);
+        assert!(usage.total_bytes >= usage.free_bytes);
+        assert!(usage.total_bytes >= usage.used_by_recordings);
+
+        let _ = fs::remove_dir_all(&root).await;
+    }
+
+    #[actix_rt::test]
+    async fn considers_missing_root_empty() {
+        let root = env::temp_dir().join(format!("ephyr-{}", Uuid::new_v4()));
+
+        let usage = Storage { root_path: root }.disk_usage().await.unwrap();
+
+        assert_eq!(usage.used_by_recordings, 0);
+        assert!(usage.total_bytes >= usage.free_bytes);
+    }
+
+    #[test]
+    fn detects_low_free_space() {
+        let usage = |free_bytes| DiskUsage {
+            total_bytes: 10 * LOW_FREE_SPACE_BYTES,
+            free_bytes,
+            used_by_recordings: 0,
+        };
+
+        assert!(usage(LOW_FREE_SPACE_BYTES - 1).is_low(LOW_FREE_SPACE_BYTES));
+        assert!(!usage(LOW_FREE_SPACE_BYTES).is_low(LOW_FREE_SPACE_BYTES));
+    }
+
+    #[test]
+    fn warns_on_low_free_space_only() {
+        let dir = env::temp_dir().join(format!("ephyr-{}", Uuid::new_v4()));
+
+        assert!(warn_if_low_free_space(&dir, u64::MAX));
+        assert!(!warn_if_low_free_space(&dir, 0));
+    }
+}
@@ -57,7 +57,7 @@ pub fn run() -> Result<(), cli::Failure> {
 
     // This guard should be held till the end of the program for the logger
     // to present in global context.
-    mem::forget(ephyr_log::init(cfg.verbose));
+    mem::forget(ephyr_log::init(cfg.verbose, cfg.log_filters.clone()));
 
     server::run(cfg)
 }
@@ -1,6 +1,10 @@
 //! HTTP servers.
 
-use std::{net::IpAddr, time::Duration};
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use ephyr_log::log;
 use futures::future;
@@ -8,7 +12,7 @@ use tokio::{fs, time};
 
 use crate::{
     cli::{Failure, Opts},
-    dvr, ffmpeg, srs, teamspeak, State,
+    dvr, ffmpeg, srs, state, teamspeak, State,
 };
 
 /// Initializes and runs all application's HTTP servers.
@@ -37,6 +41,12 @@ pub async fn run(mut cfg: Opts) -> Result<(), Failure> {
             log::error!("Failed to resolve FFmpeg binary path: {}", e)
         })?;
 
+    if cfg.max_key_len == 0 {
+        log::error!("Max length of Restream and Input keys cannot be zero");
+        return Err(Failure);
+    }
+    state::set_key_max_len(cfg.max_key_len);
+
     let state = State::try_new(&cfg.state_path)
         .await
         .map_err(|e| log::error!("Failed to initialize server state: {}", e))?;
@@ -46,6 +56,7 @@ pub async fn run(mut cfg: Opts) -> Result<(), Failure> {
         &srs::Config {
             callback_port: cfg.callback_http_port,
             http_server_dir: cfg.srs_http_dir.clone().into(),
+            rtc_candidate: cfg.public_host.clone().unwrap(),
             log_level: cfg.verbose.map(Into::into).unwrap_or_default(),
         },
     )
@@ -61,39 +72,96 @@ pub async fn run(mut cfg: Opts) -> Result<(), Failure> {
         },
     );
 
-    let mut restreamers =
-        ffmpeg::RestreamersPool::new(ffmpeg_path, state.clone());
+    if let Some(dir) = cfg.snapshots_dir.clone() {
+        let (state, keep) = (state.clone(), cfg.snapshots_keep);
+        let mut interval = time::interval(cfg.snapshots_interval);
+        drop(tokio::spawn(async move {
+            loop {
+                let _ = interval.tick().await;
+                match state.snapshot(&dir, keep).await {
+                    Ok(name) => {
+                        log::debug!("State snapshot '{}' is made", name)
+                    }
+                    Err(e) => log::error!("Failed to snapshot state: {}", e),
+                }
+            }
+        }));
+    }
+
+    teamspeak::ConnectionsLimit::new(cfg.ts_max_connections)
+        .set_global()
+        .map_err(|e| {
+            log::error!(
+                "Failed to initialize TeamSpeak connections limit: {}",
+                e
+            )
+        })?;
+    if let Some(name) = cfg.ts_name.clone() {
+        teamspeak::set_name_prefix(name).map_err(|e| {
+            log::error!("Failed to initialize TeamSpeak name: {}", e)
+        })?;
+    }
+
+    let restreamers = Arc::new(Mutex::new(ffmpeg::RestreamersPool::new(
+        ffmpeg_path,
+        ffmpeg::Config::new(&cfg, srs.hls_dir()),
+        state.clone(),
+    )));
+    let pool = restreamers.clone();
     State::on_change("spawn_restreamers", &state.restreams, move |restreams| {
-        restreamers.apply(&restreams);
+        pool.lock().unwrap().apply(&restreams);
         future::ready(())
     });
 
+    // HTTP servers stop gracefully on termination signals (SIGTERM, SIGINT),
+    // so this completes when the application is going to exit.
     future::try_join(
         self::client::run(&cfg, state.clone()),
         self::callback::run(&cfg, state),
     )
     .await?;
 
+    // Explicitly stop all FFmpeg processes, so no orphans are left holding
+    // RTMP endpoints.
+    let stopping = restreamers
+        .lock()
+        .unwrap()
+        .shutdown(cfg.ffmpeg_shutdown_timeout);
+    stopping.await;
+
     drop(srs);
     // Wait for all the async `Drop`s to proceed well.
-    teamspeak::finish_all_disconnects().await;
+    teamspeak::finish_all_disconnects(cfg.ts_disconnect_timeout).await;
 
     Ok(())
 }
 
 /// Client HTTP server responding to client requests.
 pub mod client {
-    use std::time::Duration;
+    use std::{
+        collections::HashMap,
+        net::IpAddr,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
 
+    use actix_cors::Cors;
     use actix_service::Service as _;
     use actix_web::{
-        dev::ServiceRequest, get, middleware, route, web, App, Error,
-        HttpRequest, HttpResponse, HttpServer,
+        dev::ServiceRequest,
+        error, get,
+        http::{
+            header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, LOCATION},
+            StatusCode,
+        },
+        middleware, post, route, web, App, Error, HttpRequest, HttpResponse,
+        HttpServer,
     };
     use actix_web_httpauth::extractors::{
         basic::{self, BasicAuth},
         AuthExtractor as _, AuthExtractorConfig, AuthenticationError,
     };
+    #[cfg(feature = "ui")]
     use actix_web_static_files::ResourceFiles;
     use ephyr_log::log;
     use futures::{future, FutureExt as _};
@@ -102,13 +170,16 @@ pub mod client {
         graphql_handler, subscriptions::subscriptions_handler,
     };
     use juniper_graphql_ws::ConnectionConfig;
+    use serde::Deserialize;
 
     use crate::{
         api,
         cli::{Failure, Opts},
+        state::{InputEndpointKind, InputKey, RestreamKey},
         State,
     };
 
+    #[cfg(feature = "ui")]
     pub mod public_dir {
         #![allow(clippy::must_use_candidate, unused_results)]
         #![doc(hidden)]
@@ -118,7 +189,12 @@ pub mod client {
 
     /// Runs client HTTP server.
     ///
-    /// Client HTTP server serves [`api::graphql::client`] on `/` endpoint.
+    /// Client HTTP server serves [`api::graphql::client`] on `/api` endpoint.
+    ///
+    /// # Web UI
+    ///
+    /// If built with `ui` feature (enabled by default) then additionally serves
+    /// the embedded Web UI on `/` endpoint.
     ///
     /// # Playground
     ///
@@ -126,23 +202,40 @@ pub mod client {
     /// [GraphQL Playground][2] on `/api/playground` endpoint with no
     /// authorization required.
     ///
+    /// # CORS
+    ///
+    /// If [`Opts::cors_allowed_origins`] are specified, then [CORS]
+    /// requests (including preflight `OPTIONS` ones) from these origins are
+    /// allowed. Otherwise, only same-origin requests are served.
+    ///
     /// # Errors
     ///
     /// If [`HttpServer`] cannot run due to already used port, etc.
     /// The actual error is logged.
     ///
+    /// [`Opts::cors_allowed_origins`]: crate::cli::Opts::cors_allowed_origins
     /// [`cli::Opts::debug`]: crate::cli::Opts::debug
+    /// [CORS]: https://developer.mozilla.org/en-US/docs/Web/HTTP/CORS
     /// [2]: https://github.com/graphql/graphql-playground
     pub async fn run(cfg: &Opts, state: State) -> Result<(), Failure> {
         let in_debug_mode = cfg.debug;
+        let cors_origins = cfg.cors_allowed_origins.clone();
 
         let stored_cfg = cfg.clone();
+        // Should be shared between all the workers, so is created outside.
+        let auth_limiter = AuthLimiter::new(
+            AuthLimiter::MAX_FAILED_ATTEMPTS,
+            AuthLimiter::WINDOW,
+        );
 
         Ok(HttpServer::new(move || {
-            let public_dir_files = public_dir::generate();
+            // CORS middleware is registered after the authorization one to
+            // wrap it, so preflight requests are answered without requiring
+            // any credentials.
             let mut app = App::new()
                 .app_data(stored_cfg.clone())
                 .app_data(state.clone())
+                .app_data(auth_limiter.clone())
                 .app_data(
                     basic::Config::default().realm("Any login is allowed"),
                 )
@@ -152,11 +245,18 @@ pub mod client {
                     Ok(req) => srv.call(req).left_future(),
                     Err(e) => future::err(e).right_future(),
                 })
-                .service(graphql);
+                .wrap(cors(&cors_origins))
+                .service(graphql)
+                .service(whip);
             if in_debug_mode {
                 app = app.service(playground);
             }
-            app.service(ResourceFiles::new("/", public_dir_files))
+            #[cfg(feature = "ui")]
+            {
+                app = app
+                    .service(ResourceFiles::new("/", public_dir::generate()));
+            }
+            app
         })
         .bind((cfg.client_http_ip, cfg.client_http_port))
         .map_err(|e| log::error!("Failed to bind client HTTP server: {}", e))?
@@ -186,6 +286,81 @@ pub mod client {
         }
     }
 
+    /// Path of the [`whip`] endpoint.
+    const WHIP_PATH: &str = "/rtc/v1/whip/";
+
+    /// Parameters of a [WHIP] request, identifying the `Input` to publish
+    /// into.
+    ///
+    /// [WHIP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whip
+    #[derive(Clone, Debug, Deserialize)]
+    struct WhipQuery {
+        /// Key of the `Restream` to publish into.
+        app: RestreamKey,
+
+        /// Key of the `Input` to publish into.
+        stream: InputKey,
+    }
+
+    /// Endpoint proxying [WHIP] requests of browsers to the local [SRS]
+    /// server, which accepts them into [`InputEndpointKind::Whip`] endpoints.
+    ///
+    /// Whether the requested `Input` really has such endpoint is checked by
+    /// [SRS] HTTP Callback, once the browser starts publishing.
+    ///
+    /// Requires no authorization (see [`authorize`]), as publishing clients
+    /// cannot provide any credentials, the same way [RTMP] ones don't.
+    ///
+    /// # Errors
+    ///
+    /// If the requested `Input` cannot have a [WHIP] endpoint, or the local
+    /// [SRS] server cannot be reached.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [WHIP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whip
+    #[post("/rtc/v1/whip/")]
+    async fn whip(
+        query: web::Query<WhipQuery>,
+        offer: String,
+    ) -> Result<HttpResponse, Error> {
+        let url = InputEndpointKind::Whip
+            .whip_url(&query.app, &query.stream)
+            .ok_or_else(|| {
+                error::ErrorBadRequest("Input cannot have WHIP endpoint")
+            })?;
+        let resp = reqwest::Client::new()
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/sdp")
+            .body(offer)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| {
+                log::error!("Failed to proxy WHIP request to SRS: {}", e);
+                error::ErrorBadGateway("SRS server is unavailable")
+            })?;
+
+        let status = StatusCode::from_u16(resp.status().as_u16())
+            .unwrap_or(StatusCode::BAD_GATEWAY);
+        let location = resp
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .map(ToOwned::to_owned);
+        let answer = resp.text().await.map_err(|e| {
+            log::error!("Failed to read WHIP response of SRS: {}", e);
+            error::ErrorBadGateway("SRS server is unavailable")
+        })?;
+
+        let mut res = HttpResponse::build(status);
+        let _ = res.content_type("application/sdp");
+        if let Some(location) = location {
+            let _ = res.header(LOCATION, location);
+        }
+        Ok(res.body(answer))
+    }
+
     /// Endpoint serving [GraphQL Playground][1] for exploring
     /// [`api::graphql::client`].
     ///
@@ -203,18 +378,57 @@ pub mod client {
             .body(html)
     }
 
+    /// Builds [CORS] middleware allowing requests from the given `origins`.
+    ///
+    /// Disabled if no `origins` are given, so only same-origin requests are
+    /// served.
+    ///
+    /// [CORS]: https://developer.mozilla.org/en-US/docs/Web/HTTP/CORS
+    fn cors(origins: &[String]) -> middleware::Condition<Cors> {
+        let cors = origins
+            .iter()
+            .fold(Cors::default(), |cors, o| cors.allowed_origin(o))
+            .allowed_methods(vec!["GET", "POST"])
+            .allowed_headers(vec![ACCEPT, AUTHORIZATION, CONTENT_TYPE])
+            .supports_credentials()
+            .max_age(3600);
+        middleware::Condition::new(!origins.is_empty(), cors)
+    }
+
     /// Performs [`HttpRequest`] [Basic authorization][1] as middleware against
     /// [`State::password_hash`]. Doesn't consider username anyhow.
     ///
-    /// No-op if [`State::password_hash`] is [`None`].
+    /// If the password matches [`State::viewer_password_hash`] instead, then
+    /// the [`HttpRequest`] is authorized with [`Role::Viewer`] only, so is not
+    /// allowed to perform GraphQL mutations.
+    ///
+    /// No-op if [`State::password_hash`] is [`None`], or for [`whip`] requests.
     ///
+    /// Failed attempts are tracked per client IP by [`AuthLimiter`], so once
+    /// there are too many of them, the client is answered with
+    /// `429 Too Many Requests` without checking its password at all.
+    ///
+    /// [`Role::Viewer`]: api::graphql::Role::Viewer
     /// [1]: https://en.wikipedia.org/wiki/Basic_access_authentication
     fn authorize(req: ServiceRequest) -> Result<ServiceRequest, Error> {
-        let hash =
-            match req.app_data::<State>().unwrap().password_hash.get_cloned() {
-                Some(h) => h,
-                None => return Ok(req),
-            };
+        if req.path() == WHIP_PATH {
+            return Ok(req);
+        }
+
+        let state = req.app_data::<State>().unwrap();
+        let hash = match state.password_hash.get_cloned() {
+            Some(h) => h,
+            None => return Ok(req),
+        };
+        let viewer_hash = state.viewer_password_hash.get_cloned();
+
+        let limiter = req.app_data::<AuthLimiter>().unwrap();
+        let ip = req.peer_addr().map(|addr| addr.ip());
+        if ip.map_or(false, |ip| limiter.is_throttled(ip)) {
+            return Err(error::ErrorTooManyRequests(
+                "Too many failed authorization attempts",
+            ));
+        }
 
         let err = || {
             AuthenticationError::new(
@@ -227,11 +441,340 @@ pub mod client {
 
         let auth = BasicAuth::from_service_request(&req).into_inner()?;
         let pass = auth.password().ok_or_else(err)?;
-        if argon2::verify_encoded(hash.as_str(), pass.as_bytes()) != Ok(true) {
-            return Err(err().into());
+        if argon2::verify_encoded(hash.as_str(), pass.as_bytes()) == Ok(true) {
+            return Ok(req);
+        }
+        if viewer_hash.map_or(false, |h| {
+            argon2::verify_encoded(h.as_str(), pass.as_bytes()) == Ok(true)
+        }) {
+            req.extensions_mut().insert(api::graphql::Role::Viewer);
+            return Ok(req);
+        }
+
+        if let Some(ip) = ip {
+            limiter.register_failure(ip);
+        }
+        Err(err().into())
+    }
+
+    /// Sliding-window limiter of failed [`authorize`] attempts, tracked per
+    /// client IP.
+    ///
+    /// Protects [`State::password_hash`] from being brute-forced.
+    #[derive(Clone, Debug)]
+    pub struct AuthLimiter {
+        /// Moments of the recent failed attempts of each client IP.
+        failures: Arc<Mutex<HashMap<IpAddr, Vec<Instant>>>>,
+
+        /// Maximum number of failed attempts allowed within the
+        /// [`AuthLimiter::window`].
+        max_attempts: usize,
+
+        /// Duration of the sliding window to count failed attempts within.
+        ///
+        /// Once throttled, a client IP stays so until its oldest failed attempt
+        /// leaves this window.
+        window: Duration,
+    }
+
+    impl AuthLimiter {
+        /// Default maximum number of failed attempts allowed within the
+        /// [`AuthLimiter::WINDOW`].
+        pub const MAX_FAILED_ATTEMPTS: usize = 5;
+
+        /// Default duration of the sliding window to count failed attempts
+        /// within.
+        pub const WINDOW: Duration = Duration::from_secs(60);
+
+        /// Creates a new [`AuthLimiter`] allowing `max_attempts` failed
+        /// attempts within the given `window` for each client IP.
+        #[inline]
+        #[must_use]
+        pub fn new(max_attempts: usize, window: Duration) -> Self {
+            Self {
+                failures: Arc::new(Mutex::new(HashMap::new())),
+                max_attempts,
+                window,
+            }
+        }
+
+        /// Indicates whether the given client `ip` has exceeded its allowed
+        /// number of failed attempts within the current window.
+        #[must_use]
+        pub fn is_throttled(&self, ip: IpAddr) -> bool {
+            let mut failures = self.failures.lock().unwrap();
+            let now = Instant::now();
+            let window = self.window;
+            // Prune expired failures of all the IPs, so the map doesn't grow
+            // with IPs having no recent failures.
+            failures.retain(|_, moments| {
+                moments.retain(|at| now.duration_since(*at) < window);
+                !moments.is_empty()
+            });
+            failures
+                .get(&ip)
+                .map_or(false, |moments| moments.len() >= self.max_attempts)
+        }
+
+        /// Registers a new failed attempt of the given client `ip`.
+        pub fn register_failure(&self, ip: IpAddr) {
+            self.failures
+                .lock()
+                .unwrap()
+                .entry(ip)
+                .or_default()
+                .push(Instant::now());
+        }
+    }
+
+    #[cfg(test)]
+    mod spec {
+        use actix_web::{
+            http::{
+                header::{
+                    ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_METHOD,
+                    AUTHORIZATION, ORIGIN,
+                },
+                Method, StatusCode,
+            },
+            test, App,
+        };
+        #[cfg(feature = "ui")]
+        use actix_web_static_files::ResourceFiles;
+
+        use super::*;
+
+        #[actix_rt::test]
+        async fn answers_cors_preflight() {
+            let origins = vec!["https://dashboard.example.com".to_owned()];
+            let mut app = test::init_service(
+                App::new().wrap(cors(&origins)).service(graphql),
+            )
+            .await;
+
+            let req = test::TestRequest::with_uri("/api")
+                .method(Method::OPTIONS)
+                .header(ORIGIN, "https://dashboard.example.com")
+                .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert!(resp.status().is_success(), "status: {}", resp.status());
+            assert_eq!(
+                resp.headers()
+                    .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+                    .map(|h| h.to_str().unwrap()),
+                Some("https://dashboard.example.com"),
+            );
+
+            let req = test::TestRequest::with_uri("/api")
+                .method(Method::OPTIONS)
+                .header(ORIGIN, "https://evil.example.com")
+                .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert!(
+                !resp.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN),
+                "disallowed origin is allowed",
+            );
+        }
+
+        #[actix_rt::test]
+        async fn forbids_mutations_for_viewer() {
+            let hash = |pass: &str| {
+                argon2::hash_encoded(
+                    pass.as_bytes(),
+                    b"somesaltvalue",
+                    &argon2::Config::default(),
+                )
+                .unwrap()
+            };
+            let state = State::default();
+            state.password_hash.set(Some(hash("admin")));
+            state.viewer_password_hash.set(Some(hash("viewer")));
+
+            let mut app = test::init_service(
+                App::new()
+                    .app_data(state)
+                    .app_data(AuthLimiter::new(10, AuthLimiter::WINDOW))
+                    .app_data(basic::Config::default())
+                    .data(api::graphql::client::schema())
+                    .wrap_fn(|req, srv| match authorize(req) {
+                        Ok(req) => srv.call(req).left_future(),
+                        Err(e) => future::err(e).right_future(),
+                    })
+                    .service(graphql),
+            )
+            .await;
+
+            let mutation = r#"mutation { setMaintenanceNotice(message: "x") }"#;
+            let viewer = "YW55OnZpZXdlcg=="; // any:viewer
+            let admin = "YW55OmFkbWlu"; // any:admin
+            for (creds, query, is_allowed) in vec![
+                (viewer, mutation, false),
+                (viewer, "{ maintenanceNotice }", true),
+                (admin, mutation, true),
+            ] {
+                let req = test::TestRequest::post()
+                    .uri("/api")
+                    .header(AUTHORIZATION, format!("Basic {}", creds))
+                    .set_json(&serde_json::json!({ "query": query }))
+                    .to_request();
+                let resp = test::call_service(&mut app, req).await;
+                let body = test::read_body(resp).await;
+                let body = String::from_utf8_lossy(&body);
+                assert_eq!(
+                    !body.contains("ADMIN_REQUIRED"),
+                    is_allowed,
+                    "query: {}, body: {}",
+                    query,
+                    body,
+                );
+            }
+        }
+
+        #[actix_rt::test]
+        async fn throttles_failed_auth_attempts() {
+            let state = State::default();
+            state.password_hash.set(Some(
+                argon2::hash_encoded(
+                    b"admin",
+                    b"somesaltvalue",
+                    &argon2::Config::default(),
+                )
+                .unwrap(),
+            ));
+
+            let mut app = test::init_service(
+                App::new()
+                    .app_data(state)
+                    .app_data(AuthLimiter::new(3, AuthLimiter::WINDOW))
+                    .app_data(basic::Config::default())
+                    .wrap_fn(|req, srv| match authorize(req) {
+                        Ok(req) => srv.call(req).left_future(),
+                        Err(e) => future::err(e).right_future(),
+                    })
+                    .service(graphql),
+            )
+            .await;
+
+            let request = |ip: &str, creds: &str| {
+                test::TestRequest::post()
+                    .uri("/api")
+                    .peer_addr(format!("{}:1234", ip).parse().unwrap())
+                    .header(AUTHORIZATION, format!("Basic {}", creds))
+                    .set_json(&serde_json::json!({
+                        "query": "{ maintenanceNotice }",
+                    }))
+                    .to_request()
+            };
+            let wrong = "YW55Ondyb25n"; // any:wrong
+            let admin = "YW55OmFkbWlu"; // any:admin
+
+            for _ in 0..3 {
+                let resp =
+                    test::call_service(&mut app, request("1.1.1.1", wrong))
+                        .await;
+                assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+            }
+            for creds in &[wrong, admin] {
+                let resp =
+                    test::call_service(&mut app, request("1.1.1.1", creds))
+                        .await;
+                assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+            }
+
+            let resp =
+                test::call_service(&mut app, request("2.2.2.2", wrong)).await;
+            assert_eq!(
+                resp.status(),
+                StatusCode::UNAUTHORIZED,
+                "other IPs are throttled too",
+            );
+        }
+
+        #[actix_rt::test]
+        async fn allows_whip_without_credentials() {
+            let state = State::default();
+            state.password_hash.set(Some(
+                argon2::hash_encoded(
+                    b"admin",
+                    b"somesaltvalue",
+                    &argon2::Config::default(),
+                )
+                .unwrap(),
+            ));
+
+            let mut app = test::init_service(
+                App::new()
+                    .app_data(state)
+                    .app_data(AuthLimiter::new(3, AuthLimiter::WINDOW))
+                    .app_data(basic::Config::default())
+                    .wrap_fn(|req, srv| match authorize(req) {
+                        Ok(req) => srv.call(req).left_future(),
+                        Err(e) => future::err(e).right_future(),
+                    })
+                    .service(graphql)
+                    .service(whip),
+            )
+            .await;
+
+            let req = test::TestRequest::post()
+                .uri("/rtc/v1/whip/?app=test&stream=origin")
+                .to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_ne!(resp.status(), StatusCode::UNAUTHORIZED);
+
+            let req = test::TestRequest::post()
+                .uri("/api")
+                .set_json(&serde_json::json!({
+                    "query": "{ maintenanceNotice }",
+                }))
+                .to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        #[test]
+        fn forgets_failures_outside_window() {
+            let limiter = AuthLimiter::new(1, Duration::from_millis(50));
+            let ip = "1.1.1.1".parse().unwrap();
+
+            limiter.register_failure(ip);
+            assert!(limiter.is_throttled(ip));
+
+            std::thread::sleep(Duration::from_millis(60));
+            assert!(!limiter.is_throttled(ip));
         }
 
-        Ok(req)
+        #[test]
+        fn prunes_stale_failures_of_other_ips() {
+            let limiter = AuthLimiter::new(1, Duration::from_millis(50));
+            let (stale, other) =
+                ("1.1.1.1".parse().unwrap(), "2.2.2.2".parse().unwrap());
+
+            limiter.register_failure(stale);
+            std::thread::sleep(Duration::from_millis(60));
+            assert!(!limiter.is_throttled(other));
+
+            assert!(limiter.failures.lock().unwrap().is_empty());
+        }
+
+        #[cfg(feature = "ui")]
+        #[actix_rt::test]
+        async fn serves_embedded_index_html() {
+            let mut app = test::init_service(
+                App::new()
+                    .service(ResourceFiles::new("/", public_dir::generate())),
+            )
+            .await;
+
+            let req = test::TestRequest::get().uri("/").to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert!(resp.status().is_success());
+
+            let body = test::read_body(resp).await;
+            assert_eq!(&*body, public_dir::generate()["index.html"].data);
+        }
     }
 }
 
@@ -240,11 +783,13 @@ pub mod client {
 /// [SRS]: https://github.com/ossrs/srs
 pub mod callback {
     use actix_web::{error, middleware, post, web, App, Error, HttpServer};
+    use chrono::Utc;
     use ephyr_log::log;
 
     use crate::{
         api::srs::callback,
         cli::{Failure, Opts},
+        ffmpeg,
         state::{Input, InputEndpointKind, InputSrc, State, Status},
     };
 
@@ -259,9 +804,11 @@ pub mod callback {
     /// [SRS]: https://github.com/ossrs/srs
     /// [1]: https://github.com/ossrs/srs/wiki/v3_EN_HTTPCallback
     pub async fn run(cfg: &Opts, state: State) -> Result<(), Failure> {
+        let opts = cfg.clone();
         Ok(HttpServer::new(move || {
             App::new()
                 .data(state.clone())
+                .data(opts.clone())
                 .wrap(middleware::Logger::default())
                 .service(on_callback)
         })
@@ -286,12 +833,13 @@ pub mod callback {
     async fn on_callback(
         req: web::Json<callback::Request>,
         state: web::Data<State>,
+        cfg: web::Data<Opts>,
     ) -> Result<&'static str, Error> {
         match req.action {
             callback::Event::OnConnect => on_connect(&req, &*state),
-            callback::Event::OnPublish => on_start(&req, &*state, true),
+            callback::Event::OnPublish => on_start(&req, &*state, true, &cfg),
             callback::Event::OnUnpublish => on_stop(&req, &*state, true),
-            callback::Event::OnPlay => on_start(&req, &*state, false),
+            callback::Event::OnPlay => on_start(&req, &*state, false, &cfg),
             callback::Event::OnStop => on_stop(&req, &*state, false),
             callback::Event::OnHls => on_hls(&req, &*state),
         }
@@ -324,6 +872,9 @@ pub mod callback {
     /// [`Status::Online`] (if [`callback::Event::OnPublish`]) and remembers the
     /// connected [SRS] client.
     ///
+    /// Once an [`InputEndpointKind::Rtmp`] goes [`Status::Online`], its
+    /// [`StreamInfo`] is probed in background.
+    ///
     /// # Errors
     ///
     /// - If [`callback::Request::vhost`], [`callback::Request::app`] or
@@ -334,12 +885,14 @@ pub mod callback {
     ///
     /// [`InputEndpoint`]: crate::state::InputEndpoint
     /// [`state::Restream`]: crate::state::Restream
+    /// [`StreamInfo`]: crate::state::StreamInfo
     ///
     /// [SRS]: https://github.com/ossrs/srs
     fn on_start(
         req: &callback::Request,
         state: &State,
         publishing: bool,
+        cfg: &Opts,
     ) -> Result<(), Error> {
         /// Traverses the given [`Input`] and all its [`Input::srcs`] looking
         /// for the one matching the specified `stream` and being enabled.
@@ -361,6 +914,7 @@ pub mod callback {
         let stream = req.stream.as_deref().unwrap_or_default();
         let kind = match req.vhost.as_str() {
             "hls" => InputEndpointKind::Hls,
+            "whip" => InputEndpointKind::Whip,
             _ => InputEndpointKind::Rtmp,
         };
 
@@ -370,6 +924,12 @@ pub mod callback {
             .find(|r| r.input.enabled && r.key == *req.app)
             .ok_or_else(|| error::ErrorNotFound("Such `app` doesn't exist"))?;
 
+        // Siblings feeder publishes its stream locally, and the fed `Output`s
+        // play it locally too, so there is no `Input` to track.
+        if req.ip.is_loopback() && restream.is_feeder_stream(stream) {
+            return Ok(());
+        }
+
         let input =
             lookup_input(&mut restream.input, stream).ok_or_else(|| {
                 error::ErrorNotFound("Such `stream` doesn't exist")
@@ -385,7 +945,8 @@ pub mod callback {
 
         if publishing {
             if !req.ip.is_loopback()
-                && (input.src.is_some() || !endpoint.is_rtmp())
+                && (input.src.is_some()
+                    || endpoint.kind == InputEndpointKind::Hls)
             {
                 return Err(error::ErrorForbidden(
                     "Such `stream` is allowed only locally",
@@ -398,6 +959,27 @@ pub mod callback {
                 endpoint.srs_publisher_id = Some(req.client_id.into());
             }
 
+            if endpoint.status != Status::Online {
+                state.status_events.record(
+                    &restream.key,
+                    endpoint.id,
+                    endpoint.status,
+                    Status::Online,
+                );
+                input.last_streamed_at = Some(Utc::now());
+                if endpoint.kind == InputEndpointKind::Rtmp {
+                    drop(tokio::spawn(ffmpeg::probe_stream_info(
+                        cfg.ffmpeg_path.clone(),
+                        endpoint.kind.rtmp_url(
+                            &restream.key,
+                            &input.key,
+                            restream.srs_vhost.as_ref(),
+                        ),
+                        endpoint.id,
+                        state.clone(),
+                    )));
+                }
+            }
             endpoint.status = Status::Online;
         } else {
             // `srs::ClientId` kicks the client when `Drop`ped, so we should be
@@ -447,6 +1029,7 @@ pub mod callback {
         let stream = req.stream.as_deref().unwrap_or_default();
         let kind = match req.vhost.as_str() {
             "hls" => InputEndpointKind::Hls,
+            "whip" => InputEndpointKind::Whip,
             _ => InputEndpointKind::Rtmp,
         };
 
@@ -456,6 +1039,10 @@ pub mod callback {
             .find(|r| r.key == *req.app)
             .ok_or_else(|| error::ErrorNotFound("Such `app` doesn't exist"))?;
 
+        if restream.is_feeder_stream(stream) {
+            return Ok(());
+        }
+
         let input =
             lookup_input(&mut restream.input, stream).ok_or_else(|| {
                 error::ErrorNotFound("Such `stream` doesn't exist")
@@ -469,8 +1056,20 @@ pub mod callback {
                 error::ErrorForbidden("Such `vhost` is not allowed")
             })?;
 
+        // The client has gone by itself, so there is nothing to revive.
+        endpoint.forget_kickoff(req.client_id);
+
         if publishing {
             endpoint.srs_publisher_id = None;
+            endpoint.stream_info = None;
+            if endpoint.status != Status::Offline {
+                state.status_events.record(
+                    &restream.key,
+                    endpoint.id,
+                    endpoint.status,
+                    Status::Offline,
+                );
+            }
             endpoint.status = Status::Offline;
         } else {
             let _ = endpoint.srs_player_ids.remove(&req.client_id);
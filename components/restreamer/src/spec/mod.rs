@@ -5,21 +5,39 @@
 
 pub mod v1;
 
+use std::time::Duration;
+
+use anyhow::anyhow;
 use derive_more::From;
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use url::Url;
 
 /// All supported versions of shareable (exportable and importable)
 /// specifications of application's [`State`].
 ///
 /// [`State`]: crate::state::State
 #[derive(Clone, Debug, Deserialize, From, Serialize)]
-#[serde(tag = "version", rename_all = "lowercase")]
+#[serde(tag = "version", rename_all = "lowercase", remote = "Self")]
 pub enum Spec {
     /// Version 1 of this [`Spec`].
     V1(v1::Spec),
 }
 
 impl Spec {
+    /// Returns the latest version of [`Spec`] supported by this build.
+    #[inline]
+    #[must_use]
+    pub const fn latest_supported_version() -> u32 {
+        1
+    }
+
+    /// Parses the given `version` tag of a [`Spec`] (like `v1`) into its
+    /// number.
+    #[must_use]
+    fn parse_version(version: &str) -> Option<u32> {
+        version.strip_prefix('v')?.parse().ok()
+    }
+
     /// Converts this [`Spec`] into a [`v1::Spec`].
     #[inline]
     #[must_use]
@@ -28,4 +46,221 @@ impl Spec {
             Self::V1(s) => s,
         }
     }
+
+    /// Maximum allowed size (in bytes) of a JSON [`Spec`] fetched with
+    /// [`Spec::fetch()`].
+    pub const MAX_FETCH_SIZE: usize = 10 * 1024 * 1024;
+
+    /// Fetches a JSON [`Spec`] from the given HTTP(S) [`Url`] with a `GET`
+    /// request.
+    ///
+    /// # Errors
+    ///
+    /// - If the given [`Url`] is not an HTTP(S) one.
+    /// - If the request fails, or responds with a non-2xx status.
+    /// - If the response body exceeds [`Spec::MAX_FETCH_SIZE`].
+    /// - If the response body is not a valid JSON [`Spec`].
+    pub async fn fetch(url: &Url) -> anyhow::Result<Self> {
+        if !matches!(url.scheme(), "http" | "https") || !url.has_host() {
+            return Err(anyhow!("'{}' is not an HTTP(S) URL", url));
+        }
+
+        let mut resp = reqwest::Client::new()
+            .get(url.clone())
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to request '{}': {}", url, e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "'{}' responded with bad status: {}",
+                url,
+                resp.status(),
+            ));
+        }
+        let too_large = || {
+            anyhow!(
+                "'{}' responded with body larger than {} bytes",
+                url,
+                Self::MAX_FETCH_SIZE,
+            )
+        };
+        if resp
+            .content_length()
+            .map_or(false, |l| l > Self::MAX_FETCH_SIZE as u64)
+        {
+            return Err(too_large());
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| anyhow!("Failed to read '{}': {}", url, e))?
+        {
+            if body.len() + chunk.len() > Self::MAX_FETCH_SIZE {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        serde_json::from_slice(&body)
+            .map_err(|e| anyhow!("Invalid JSON spec at '{}': {}", url, e))
+    }
+}
+
+impl Serialize for Spec {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Self::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Spec {
+    /// Deserializes a [`Spec`] checking its `version` first, so an unsupported
+    /// one is reported clearly rather than with an opaque [`serde`] error.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = serde_json::Value::deserialize(deserializer)?;
+        let version = raw
+            .get("version")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| D::Error::missing_field("version"))?;
+        let latest = Self::latest_supported_version();
+        match Self::parse_version(version) {
+            Some(n) if (1..=latest).contains(&n) => {}
+            Some(n) => {
+                return Err(D::Error::custom(format!(
+                    "unsupported spec version {}, this build supports up to {}",
+                    n, latest,
+                )))
+            }
+            None => {
+                return Err(D::Error::custom(format!(
+                    "invalid spec version '{}', this build supports up to {}",
+                    version, latest,
+                )))
+            }
+        }
+        Self::deserialize(raw).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod version_spec {
+    use super::Spec;
+
+    #[test]
+    fn accepts_supported_version() {
+        let res = serde_json::from_str::<Spec>(
+            r#"{"version": "v1", "restreams": []}"#,
+        );
+
+        assert!(res.is_ok(), "{:?}", res);
+    }
+
+    #[test]
+    fn reports_unsupported_version() {
+        let err = serde_json::from_str::<Spec>(
+            r#"{"version": "v2", "restreams": [], "whatever": {}}"#,
+        )
+        .unwrap_err();
+
+        assert!(
+            err.to_string().contains(
+                "unsupported spec version 2, this build supports up to 1",
+            ),
+            "{}",
+            err,
+        );
+    }
+
+    #[test]
+    fn reports_invalid_version() {
+        let err = serde_json::from_str::<Spec>(
+            r#"{"version": "latest", "restreams": []}"#,
+        )
+        .unwrap_err();
+
+        assert!(err.to_string().contains("invalid spec version"), "{}", err);
+    }
+}
+
+#[cfg(test)]
+mod fetch_spec {
+    use actix_web::{test, web, App, HttpResponse};
+    use url::Url;
+
+    use crate::State;
+
+    use super::Spec;
+
+    const SPEC: &str = r#"{
+        "version": "v1",
+        "restreams": [{
+            "key": "fetched",
+            "input": {"key": "origin", "endpoints": [{"kind": "rtmp"}]},
+            "outputs": []
+        }]
+    }"#;
+
+    fn mock_server() -> test::TestServer {
+        test::start(|| {
+            App::new()
+                .route(
+                    "/spec.json",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .content_type("application/json")
+                            .body(SPEC)
+                    }),
+                )
+                .route(
+                    "/huge.json",
+                    web::get().to(|| {
+                        HttpResponse::Ok()
+                            .body(vec![b' '; Spec::MAX_FETCH_SIZE + 1])
+                    }),
+                )
+        })
+    }
+
+    #[actix_rt::test]
+    async fn applies_fetched_spec() {
+        let srv = mock_server();
+        let state = State::default();
+
+        let spec = Spec::fetch(&Url::parse(&srv.url("/spec.json")).unwrap())
+            .await
+            .unwrap();
+        state.apply(spec.into_v1(), true).unwrap();
+
+        let restreams = state.restreams.get_cloned();
+        assert_eq!(restreams.len(), 1);
+        assert_eq!(&*restreams[0].key, "fetched");
+    }
+
+    #[actix_rt::test]
+    async fn rejects_non_http_url() {
+        let res =
+            Spec::fetch(&Url::parse("file:///etc/ephyr/spec.json").unwrap())
+                .await;
+
+        assert!(res.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn rejects_too_large_body() {
+        let srv = mock_server();
+
+        let res =
+            Spec::fetch(&Url::parse(&srv.url("/huge.json")).unwrap()).await;
+
+        assert!(res.is_err());
+    }
 }
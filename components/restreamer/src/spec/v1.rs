@@ -3,7 +3,7 @@
 //!
 //! [`State`]: state::State
 
-use std::collections::HashSet;
+use std::{collections::HashSet, iter};
 
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 
@@ -54,6 +54,13 @@ pub struct Restream {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub label: Option<state::Label>,
 
+    /// Optional [SRS] `vhost` to route this [`Restream`]'s live streams
+    /// through.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srs_vhost: Option<state::SrsVhost>,
+
     /// [`Input`] that a live stream is received from.
     pub input: Input,
 
@@ -64,6 +71,15 @@ pub struct Restream {
         skip_serializing_if = "Vec::is_empty"
     )]
     pub outputs: Vec<Output>,
+
+    /// [`Preset`]s of [`Output`]s' and their [`Mixin`]s' volumes, which may be
+    /// applied at once.
+    #[serde(
+        default,
+        deserialize_with = "Restream::deserialize_presets",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub presets: Vec<Preset>,
 }
 
 impl Restream {
@@ -87,6 +103,88 @@ impl Restream {
 
         Ok(outputs)
     }
+
+    /// Deserializes [`Restream::presets`] ensuring its invariants preserved.
+    fn deserialize_presets<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Preset>, D::Error> {
+        let presets = <Vec<Preset>>::deserialize(deserializer)?;
+
+        if !presets.is_empty() {
+            let mut unique = HashSet::with_capacity(presets.len());
+            for p in &presets {
+                if let Some(name) = unique.replace(&p.name) {
+                    return Err(D::Error::custom(format!(
+                        "Duplicate Preset.name in Restream.presets: {}",
+                        name,
+                    )));
+                }
+            }
+        }
+
+        Ok(presets)
+    }
+}
+
+#[cfg(test)]
+impl Restream {
+    /// Creates a new [`Restream`] with the given `key` to be used in tests.
+    ///
+    /// It accepts a live stream into a single [RTMP] `origin` [`Input`], and
+    /// has no [`Output`]s.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    #[must_use]
+    pub fn with_key(key: &str) -> Self {
+        Self {
+            key: state::RestreamKey::new(key).unwrap(),
+            label: None,
+            srs_vhost: None,
+            input: Input {
+                key: state::InputKey::new("origin").unwrap(),
+                endpoints: vec![InputEndpoint {
+                    kind: state::InputEndpointKind::Rtmp,
+                    hls_encoding: None,
+                }],
+                src: None,
+                hls_ladder: vec![],
+                enabled: true,
+            },
+            outputs: vec![],
+            presets: vec![],
+        }
+    }
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::Preset`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Preset {
+    /// Unique (in its [`Restream`]) name of this [`Preset`].
+    pub name: state::Label,
+
+    /// [`PresetVolume`]s to be applied by this [`Preset`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<PresetVolume>,
+}
+
+/// Shareable (exportable and importable) specification of a
+/// [`state::PresetVolume`].
+///
+/// Refers [`Output`]s and [`Mixin`]s by their URLs, as their IDs are not
+/// preserved between exports and imports.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PresetVolume {
+    /// [`Output::dst`] of the [`Output`] to tune the volume of.
+    pub output: state::OutputDstUrl,
+
+    /// [`Mixin::src`] of the [`Mixin`] to tune the volume of, rather than of
+    /// the [`Output`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mixin: Option<state::MixinSrcUrl>,
+
+    /// Volume rate to be set.
+    pub volume: state::Volume,
 }
 
 /// Shareable (exportable and importable) specification of a [`state::Input`].
@@ -106,6 +204,13 @@ pub struct Input {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub src: Option<InputSrc>,
 
+    /// Rungs of an adaptive bitrate ladder to serve a live stream with via
+    /// [`state::InputEndpointKind::Hls`] endpoint of this [`Input`].
+    ///
+    /// If empty, then a single rendition is served.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hls_ladder: Vec<state::HlsRung>,
+
     /// Indicator whether this [`Input`] is enabled, so is allowed to receive a
     /// live stream from its upstream sources.
     #[serde(default, skip_serializing_if = "is_false")]
@@ -124,6 +229,8 @@ impl<'de> Deserialize<'de> for Input {
             #[serde(default)]
             src: Option<InputSrc>,
             #[serde(default)]
+            hls_ladder: Vec<state::HlsRung>,
+            #[serde(default)]
             enabled: bool,
         }
 
@@ -145,6 +252,22 @@ impl<'de> Deserialize<'de> for Input {
             )));
         }
 
+        let mut unique_heights = HashSet::with_capacity(raw.hls_ladder.len());
+        for rung in &raw.hls_ladder {
+            if rung.height == 0 || rung.bitrate_kbps == 0 {
+                return Err(D::Error::custom(
+                    "Input.hls_ladder rungs should have non-zero height and \
+                     bitrate",
+                ));
+            }
+            if !unique_heights.insert(rung.height) {
+                return Err(D::Error::custom(format!(
+                    "Duplicate HlsRung.height in Input.hls_ladder: {}",
+                    rung.height,
+                )));
+            }
+        }
+
         if let Some(src) = &raw.src {
             fn ensure_srcs_unique<'i>(
                 src: &'i InputSrc,
@@ -152,12 +275,15 @@ impl<'de> Deserialize<'de> for Input {
                 unique_keys: &mut HashSet<&'i state::InputKey>,
             ) -> Result<(), String> {
                 match src {
-                    InputSrc::RemoteUrl(url) => {
-                        if let Some(url) = unique_urls.replace(url) {
-                            return Err(format!(
-                                "Duplicate RemoteInputSrc.url in Input.src: {}",
-                                url,
-                            ));
+                    InputSrc::RemoteUrl(remote) => {
+                        for url in remote.urls() {
+                            if let Some(url) = unique_urls.replace(url) {
+                                return Err(format!(
+                                    "Duplicate RemoteInputSrc.url in \
+                                     Input.src: {}",
+                                    url,
+                                ));
+                            }
                         }
                     }
                     InputSrc::FailoverInputs(inputs) => {
@@ -192,6 +318,7 @@ impl<'de> Deserialize<'de> for Input {
             key: raw.key,
             endpoints: raw.endpoints,
             src: raw.src,
+            hls_ladder: raw.hls_ladder,
             enabled: raw.enabled,
         })
     }
@@ -203,6 +330,13 @@ impl<'de> Deserialize<'de> for Input {
 pub struct InputEndpoint {
     /// Kind of this [`InputEndpoint`].
     pub kind: state::InputEndpointKind,
+
+    /// Settings of transcoding a live stream for this [`InputEndpoint`].
+    ///
+    /// Has effect only for [`state::InputEndpointKind::Hls`]. If [`None`],
+    /// then the default [`state::HlsEncoding`] is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hls_encoding: Option<state::HlsEncoding>,
 }
 
 /// Shareable (exportable and importable) specification of a
@@ -210,13 +344,89 @@ pub struct InputEndpoint {
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum InputSrc {
-    /// Remote endpoint represented by its URL.
-    RemoteUrl(state::InputSrcUrl),
+    /// Remote endpoint represented by its URL, optionally with fallback URLs.
+    RemoteUrl(RemoteSrc),
 
     /// Multiple [`Input`]s forming a failover source.
     FailoverInputs(Vec<Input>),
 }
 
+/// Shareable (exportable and importable) specification of a
+/// [`state::RemoteInputSrc`].
+///
+/// Is represented as a plain URL string if there are no
+/// [`RemoteSrc::fallback_urls`], for backward compatibility.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "RawRemoteSrc", into = "RawRemoteSrc")]
+pub struct RemoteSrc {
+    /// URL to pull a live stream from.
+    pub url: state::InputSrcUrl,
+
+    /// URLs to pull a live stream from, in order, once pulling from the
+    /// previous URL fails.
+    pub fallback_urls: Vec<state::InputSrcUrl>,
+}
+
+impl RemoteSrc {
+    /// Iterates over all the URLs of this [`RemoteSrc`] in order.
+    pub fn urls(&self) -> impl Iterator<Item = &state::InputSrcUrl> {
+        iter::once(&self.url).chain(&self.fallback_urls)
+    }
+}
+
+impl From<state::InputSrcUrl> for RemoteSrc {
+    #[inline]
+    fn from(url: state::InputSrcUrl) -> Self {
+        Self {
+            url,
+            fallback_urls: vec![],
+        }
+    }
+}
+
+/// Serialized representation of a [`RemoteSrc`].
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawRemoteSrc {
+    /// [`RemoteSrc`] without any [`RemoteSrc::fallback_urls`].
+    Url(state::InputSrcUrl),
+
+    /// [`RemoteSrc`] with [`RemoteSrc::fallback_urls`].
+    WithFallbacks {
+        /// URL to pull a live stream from.
+        url: state::InputSrcUrl,
+
+        /// URLs to pull a live stream from once pulling from the previous URL
+        /// fails.
+        #[serde(default)]
+        fallback_urls: Vec<state::InputSrcUrl>,
+    },
+}
+
+impl From<RawRemoteSrc> for RemoteSrc {
+    fn from(raw: RawRemoteSrc) -> Self {
+        match raw {
+            RawRemoteSrc::Url(url) => url.into(),
+            RawRemoteSrc::WithFallbacks { url, fallback_urls } => {
+                Self { url, fallback_urls }
+            }
+        }
+    }
+}
+
+impl From<RemoteSrc> for RawRemoteSrc {
+    fn from(src: RemoteSrc) -> Self {
+        if src.fallback_urls.is_empty() {
+            Self::Url(src.url)
+        } else {
+            Self::WithFallbacks {
+                url: src.url,
+                fallback_urls: src.fallback_urls,
+            }
+        }
+    }
+}
+
 /// Shareable (exportable and importable) specification of a [`state::Output`].
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Output {
@@ -243,6 +453,88 @@ pub struct Output {
     )]
     pub mixins: Vec<Mixin>,
 
+    /// Profile to transcode a live stream with before re-streaming it to the
+    /// downstream destination.
+    ///
+    /// If [`None`], then no transcoding is performed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcoding: Option<state::TranscodingProfile>,
+
+    /// Interval between keyframes to transcode a live stream with.
+    ///
+    /// If [`None`], then the default [`state::KeyframeInterval`] is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyframe_interval: Option<state::KeyframeInterval>,
+
+    /// Delay to re-stream a live stream of this [`Output`] with.
+    ///
+    /// Has no effect when there are [`Output::mixins`].
+    #[serde(default, skip_serializing_if = "state::Delay::is_zero")]
+    pub delay: state::Delay,
+
+    /// Policy of how long a mixed live stream of this [`Output`] lasts.
+    ///
+    /// Has effect only when there are [`Output::mixins`].
+    #[serde(default, skip_serializing_if = "state::MixDuration::is_longest")]
+    pub mix_duration: state::MixDuration,
+
+    /// Latency (in milliseconds) of an [SRT] connection to the downstream
+    /// destination.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt_latency_ms: Option<u32>,
+
+    /// Duration (in seconds) of segments to split a DVR recording of this
+    /// [`Output`] into.
+    ///
+    /// Has effect only for `file:///` destinations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dvr_segment_secs: Option<u32>,
+
+    /// Passphrase to encrypt an [SRT] connection to the downstream destination
+    /// with.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt_passphrase: Option<state::SrtPassphrase>,
+
+    /// Additional [FFmpeg] arguments to be passed right before this
+    /// [`Output`]'s destination URL.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+
+    /// Indicator whether [FFmpeg] should reconnect to a remote HTTP source
+    /// this [`Output`] pulls a live stream from, on its failures.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub reconnect: bool,
+
+    /// Level of [FFmpeg] logs (`-loglevel`) to re-stream a live stream to
+    /// this [`Output`] with, overriding the default one.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loglevel: Option<state::FfmpegLogLevel>,
+
+    /// [FFmpeg] container format (`-f`) to re-stream a live stream to this
+    /// [`Output`] in, overriding the one chosen by its destination URL.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<state::OutputFormat>,
+
+    /// Indicator whether this [`Output`] feeds its sibling [`Output`]s, so
+    /// they pull a live stream from its local [SRS] destination rather than
+    /// from the [`Input`].
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub feeds_siblings: bool,
+
     /// Indicator whether this [`Output`]  is enabled, so is allowed to perform
     /// a live stream re-streaming to its downstream destination.
     #[serde(default, skip_serializing_if = "is_false")]
@@ -282,6 +574,34 @@ impl Output {
     }
 }
 
+#[cfg(test)]
+impl Output {
+    /// Creates a new enabled [`Output`] to be used in tests, re-streaming a
+    /// live stream "as is" to the given `dst`.
+    #[must_use]
+    pub fn with_dst(dst: &str) -> Self {
+        Self {
+            dst: state::OutputDstUrl::new(dst.parse().unwrap()).unwrap(),
+            label: None,
+            volume: state::Volume::ORIGIN,
+            mixins: vec![],
+            transcoding: None,
+            keyframe_interval: None,
+            delay: state::Delay::default(),
+            mix_duration: state::MixDuration::default(),
+            srt_latency_ms: None,
+            dvr_segment_secs: None,
+            srt_passphrase: None,
+            extra_args: vec![],
+            reconnect: false,
+            loglevel: None,
+            format: None,
+            feeds_siblings: false,
+            enabled: true,
+        }
+    }
+}
+
 /// Shareable (exportable and importable) specification of a [`state::Mixin`].
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Mixin {
@@ -296,4 +616,28 @@ pub struct Mixin {
     /// [`Output`].
     #[serde(default, skip_serializing_if = "state::Delay::is_zero")]
     pub delay: state::Delay,
+
+    /// Indicator whether an audio of an [`Output`] should be ducked under this
+    /// [`Mixin`]'s audio via sidechain compression.
+    ///
+    /// Has effect only for [TeamSpeak] [`Mixin`]s.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub sidechain: bool,
+}
+
+#[cfg(test)]
+impl Mixin {
+    /// Creates a new [`Mixin`] to be used in tests, mixing the given `src` with
+    /// the origin volume and no delay.
+    #[must_use]
+    pub fn with_src(src: &str) -> Self {
+        Self {
+            src: state::MixinSrcUrl::new(src.parse().unwrap()).unwrap(),
+            volume: state::Volume::ORIGIN,
+            delay: state::Delay::default(),
+            sidechain: false,
+        }
+    }
 }
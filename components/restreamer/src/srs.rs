@@ -31,6 +31,12 @@ pub struct Server {
     /// [SRS]: https://github.com/ossrs/srs
     conf_path: PathBuf,
 
+    /// Absolute path to the directory where [SRS] serves [HLS] files from.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    /// [SRS]: https://github.com/ossrs/srs
+    hls_dir: PathBuf,
+
     /// Handle to the actual spawned [SRS] process.
     ///
     /// [SRS]: https://github.com/ossrs/srs
@@ -74,6 +80,13 @@ impl Server {
                 e,
             )
         })?;
+        let hls_dir = fs::canonicalize(&hls_dir).await.map_err(|e| {
+            anyhow!(
+                "Failed to resolve HLS directory {} : {}",
+                hls_dir.display(),
+                e,
+            )
+        })?;
 
         // Set directory for dvr::Storage served by this SRS instance.
         let mut dvr_dir = http_dir.clone();
@@ -121,6 +134,7 @@ impl Server {
 
         let srv = Self {
             conf_path,
+            hls_dir,
             process: Arc::new(ServerProcess(abort_handle)),
         };
 
@@ -133,6 +147,17 @@ impl Server {
         Ok(srv)
     }
 
+    /// Returns absolute path to the directory where this [SRS] server serves
+    /// [HLS] files from.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    /// [SRS]: https://github.com/ossrs/srs
+    #[inline]
+    #[must_use]
+    pub fn hls_dir(&self) -> &Path {
+        &self.hls_dir
+    }
+
     /// Updates [SRS] configuration file and reloads the spawned [SRS] server
     /// to catch up the changes.
     ///
@@ -199,6 +224,35 @@ impl Borrow<u32> for ClientId {
     }
 }
 
+impl ClientId {
+    /// Kicks a client behind this [`ClientId`] from [SRS] server right away,
+    /// even if there are other copies of this [`ClientId`] left.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub fn kickoff(mut self) {
+        // The last copy is kicked on `Drop` anyway.
+        if Arc::get_mut(&mut self.0).is_none() {
+            Self::spawn_kickoff(*self);
+        }
+    }
+
+    /// Spawns kicking of a client with the given `client_id` from [SRS]
+    /// server.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    fn spawn_kickoff(client_id: u32) {
+        drop(tokio::spawn(
+            api::srs::Client::kickoff_client(client_id).map_err(move |e| {
+                log::warn!(
+                    "Failed to kickoff client {} from SRS: {}",
+                    client_id,
+                    e,
+                )
+            }),
+        ));
+    }
+}
+
 impl Drop for ClientId {
     /// Kicks a client behind this [`ClientId`] from [SRS] server it there are
     /// no more copies left.
@@ -206,15 +260,7 @@ impl Drop for ClientId {
     /// [SRS]: https://github.com/ossrs/srs
     fn drop(&mut self) {
         if let Some(&mut client_id) = Arc::get_mut(&mut self.0) {
-            drop(tokio::spawn(
-                api::srs::Client::kickoff_client(client_id).map_err(move |e| {
-                    log::warn!(
-                        "Failed to kickoff client {} from SRS: {}",
-                        client_id,
-                        e,
-                    )
-                }),
-            ));
+            Self::spawn_kickoff(client_id);
         }
     }
 }
@@ -235,6 +281,14 @@ pub struct Config {
     /// [SRS]: https://github.com/ossrs/srs
     pub http_server_dir: DisplayablePath,
 
+    /// Public host announced by [SRS] as [WebRTC] ICE candidate, so browsers
+    /// are able to publish via [WHIP].
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+    /// [WHIP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whip
+    pub rtc_candidate: String,
+
     /// Severity of [SRS] server logs.
     ///
     /// [SRS]: https://github.com/ossrs/srs
@@ -1,32 +1,51 @@
 //! Application state.
 
 use std::{
-    borrow::Cow, collections::HashSet, convert::TryInto, future::Future, mem,
-    panic::AssertUnwindSafe, path::Path, time::Duration,
+    borrow::Cow,
+    collections::{HashSet, VecDeque},
+    convert::TryInto,
+    fmt,
+    future::Future,
+    iter, mem,
+    panic::AssertUnwindSafe,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
 use derive_more::{Deref, Display, From, Into};
 use ephyr_log::log;
 use futures::{
-    future::TryFutureExt as _,
+    future::{self, TryFutureExt as _},
     sink,
-    stream::{StreamExt as _, TryStreamExt as _},
+    stream::{BoxStream, StreamExt as _, TryStreamExt as _},
 };
 use futures_signals::signal::{Mutable, SignalExt as _};
 use juniper::{
-    graphql_scalar, GraphQLEnum, GraphQLObject, GraphQLScalarValue,
-    GraphQLUnion, ParseScalarResult, ParseScalarValue, ScalarValue, Value,
+    graphql_object, graphql_scalar, GraphQLEnum, GraphQLInputObject,
+    GraphQLObject, GraphQLScalarValue, GraphQLUnion, ParseScalarResult,
+    ParseScalarValue, ScalarValue, Value,
 };
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use smart_default::SmartDefault;
-use tokio::{fs, io::AsyncReadExt as _};
-use url::Url;
+use tokio::{
+    fs,
+    io::{self, AsyncReadExt as _, AsyncWriteExt as _},
+    net::TcpStream,
+    time,
+};
+use url::{Host, Url};
 use uuid::Uuid;
 
-use crate::{display_panic, serde::is_false, spec, srs, Spec};
+use crate::{display_panic, dvr, serde::is_false, spec, srs, Spec};
 
 /// Reactive application's state.
 ///
@@ -37,17 +56,44 @@ pub struct State {
     /// public APIs.
     pub password_hash: Mutable<Option<String>>,
 
+    /// [`argon2`] hash of password which grants read-only access to this
+    /// application's public APIs.
+    ///
+    /// Has effect only if [`State::password_hash`] is set.
+    #[serde(default)]
+    pub viewer_password_hash: Mutable<Option<String>>,
+
     /// All [`Restream`]s performed by this application.
     pub restreams: Mutable<Vec<Restream>>,
+
+    /// Notice about an ongoing or upcoming maintenance to be shown to this
+    /// application's users.
+    ///
+    /// Doesn't affect re-streaming at all.
+    #[serde(default)]
+    pub maintenance_notice: Mutable<Option<String>>,
+
+    /// Recent [`Status`] transitions of all the [`Restream`]s' elements.
+    ///
+    /// Not persisted, so is reset on application restart.
+    #[serde(skip)]
+    pub status_events: StatusEvents,
+
+    /// [`Revision`] of this [`State`], bumped on each its persisted change.
+    #[serde(default)]
+    pub revision: Mutable<Revision>,
 }
 
 impl State {
     /// Instantiates a new [`State`] reading it from a `file` (if any) and
     /// performing all the required inner subscriptions.
     ///
+    /// If the `file` exists, but fails to be parsed, then it's backed up
+    /// aside and a fresh [`State`] is used.
+    ///
     /// # Errors
     ///
-    /// If [`State`] file exists, but fails to be parsed.
+    /// If [`State`] file fails to be opened, read or backed up.
     pub async fn try_new<P: AsRef<Path>>(
         file: P,
     ) -> Result<Self, anyhow::Error> {
@@ -72,25 +118,44 @@ impl State {
         let state = if contents.is_empty() {
             State::default()
         } else {
-            serde_json::from_slice(&contents).map_err(|e| {
-                anyhow!(
-                    "Failed to deserialize state from '{}' file: {}",
-                    file.display(),
-                    e,
-                )
-            })?
+            match serde_json::from_slice(&contents) {
+                Ok(state) => state,
+                Err(e) => {
+                    let backup = Self::backup_corrupted(file).await?;
+                    log::warn!(
+                        "Failed to deserialize state from '{}' file, so \
+                         starting with a fresh one, while the corrupted file \
+                         is backed up to '{}': {}",
+                        file.display(),
+                        backup.display(),
+                        e,
+                    );
+                    State::default()
+                }
+            }
         };
 
         let (file, persisted_state) = (file.to_owned(), state.clone());
+        // Persisting hooks run concurrently, so are serialized to never write
+        // the same file simultaneously, and the latest `State` is always the
+        // one written last.
+        let persist_lock = Arc::new(tokio::sync::Mutex::new(()));
         let persist_state1 = move || {
-            fs::write(
-                file.clone(),
-                serde_json::to_vec(&persisted_state)
-                    .expect("Failed to serialize server state"),
-            )
-            .map_err(|e| log::error!("Failed to persist server state: {}", e))
+            let (file, state) = (file.clone(), persisted_state.clone());
+            let lock = Arc::clone(&persist_lock);
+            state.bump_revision();
+            async move {
+                let _guard = lock.lock().await;
+                let contents = serde_json::to_vec(&state)
+                    .expect("Failed to serialize server state");
+                persist_atomically(&file, &contents).await.map_err(|e| {
+                    log::error!("Failed to persist server state: {}", e)
+                })
+            }
         };
         let persist_state2 = persist_state1.clone();
+        let persist_state3 = persist_state1.clone();
+        let persist_state4 = persist_state1.clone();
         Self::on_change("persist_restreams", &state.restreams, move |_| {
             persist_state1()
         });
@@ -99,17 +164,73 @@ impl State {
             &state.password_hash,
             move |_| persist_state2(),
         );
+        Self::on_change(
+            "persist_maintenance_notice",
+            &state.maintenance_notice,
+            move |_| persist_state3(),
+        );
+        Self::on_change(
+            "persist_viewer_password_hash",
+            &state.viewer_password_hash,
+            move |_| persist_state4(),
+        );
 
         Ok(state)
     }
 
+    /// Moves the given corrupted [`State`] `file` aside, so it can be inspected
+    /// later, and returns the path it has been moved to.
+    ///
+    /// # Errors
+    ///
+    /// If the `file` fails to be moved.
+    async fn backup_corrupted(file: &Path) -> Result<PathBuf, anyhow::Error> {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut name = file.file_name().unwrap_or_default().to_owned();
+        name.push(format!(".corrupted.{}", timestamp));
+        let backup = file.with_file_name(name);
+
+        fs::rename(file, &backup).await.map_err(|e| {
+            anyhow!(
+                "Failed to back up corrupted '{}' file to '{}': {}",
+                file.display(),
+                backup.display(),
+                e,
+            )
+        })?;
+        Ok(backup)
+    }
+
     /// Applies the given [`Spec`] to this [`State`].
     ///
     /// If `replace` is `true` then all the [`Restream`]s, [`Restream::outputs`]
     /// and [`Output::mixins`] will be replaced with new ones, otherwise new
     /// ones will be merged with already existing ones.
-    pub fn apply(&self, new: spec::v1::Spec, replace: bool) {
+    ///
+    /// # Errors
+    ///
+    /// If the given [`Spec`] is invalid (see [`Restream::validate_spec()`]).
+    /// Nothing is applied in such case.
+    pub fn apply(
+        &self,
+        new: spec::v1::Spec,
+        replace: bool,
+    ) -> anyhow::Result<()> {
         let mut restreams = self.restreams.lock_mut();
+
+        let news: Vec<_> =
+            new.restreams.iter().cloned().map(Restream::new).collect();
+        for r in &new.restreams {
+            let olds = restreams.iter().filter(|o| {
+                !replace && new.restreams.iter().all(|n| n.key != o.key)
+            });
+            let others = news.iter().filter(|n| n.key != r.key);
+            Restream::validate_spec(r, olds.chain(others))?;
+        }
+
         if replace {
             let mut olds = mem::replace(
                 &mut *restreams,
@@ -139,6 +260,7 @@ impl State {
                 }
             }
         }
+        Ok(())
     }
 
     /// Exports this [`State`] as a [`spec::v1::Spec`].
@@ -156,6 +278,117 @@ impl State {
         .into()
     }
 
+    /// Writes a snapshot of this [`State`] (as an exported [`Spec`]) into a new
+    /// timestamped `spec-<timestamp>.json` file in the given `dir`, keeping
+    /// only the latest `keep` snapshots there.
+    ///
+    /// Returns the name of the written snapshot file.
+    ///
+    /// # Errors
+    ///
+    /// If the snapshot fails to be written, or the outdated snapshots fail to
+    /// be removed.
+    pub async fn snapshot<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        keep: usize,
+    ) -> Result<String, anyhow::Error> {
+        let dir = dir.as_ref();
+
+        fs::create_dir_all(dir).await.map_err(|e| {
+            anyhow!("Failed to create '{}' dir: {}", dir.display(), e)
+        })?;
+
+        let name = format!(
+            "{}{}{}",
+            SNAPSHOT_PREFIX,
+            humantime::format_rfc3339_nanos(SystemTime::now())
+                .to_string()
+                .replace(':', "-"),
+            SNAPSHOT_SUFFIX,
+        );
+        let file = dir.join(&name);
+        let contents = serde_json::to_vec_pretty(&self.export())
+            .map_err(|e| anyhow!("Failed to serialize spec: {}", e))?;
+        persist_atomically(&file, &contents).await.map_err(|e| {
+            anyhow!("Failed to write '{}' file: {}", file.display(), e)
+        })?;
+
+        let mut snapshots = Self::list_snapshots(dir).await?;
+        snapshots.sort();
+        let outdated = snapshots.len().saturating_sub(keep);
+        for old in &snapshots[..outdated] {
+            let path = dir.join(old);
+            fs::remove_file(&path).await.map_err(|e| {
+                anyhow!("Failed to remove '{}' file: {}", path.display(), e)
+            })?;
+        }
+
+        Ok(name)
+    }
+
+    /// Restores this [`State`] from the snapshot `file` in the given `dir`,
+    /// previously written by [`State::snapshot()`].
+    ///
+    /// All the existing [`Restream`]s are replaced with the ones from the
+    /// snapshot.
+    ///
+    /// # Errors
+    ///
+    /// If the `file` name is not a valid snapshot name, or it fails to be read
+    /// or parsed.
+    pub async fn restore_snapshot<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        file: &str,
+    ) -> Result<(), anyhow::Error> {
+        if !Self::is_snapshot_name(file) {
+            return Err(anyhow!("Invalid snapshot file name: {}", file));
+        }
+        let path = dir.as_ref().join(file);
+
+        let contents = fs::read(&path).await.map_err(|e| {
+            anyhow!("Failed to read '{}' file: {}", path.display(), e)
+        })?;
+        let spec = serde_json::from_slice::<Spec>(&contents).map_err(|e| {
+            anyhow!("Failed to parse '{}' file: {}", path.display(), e)
+        })?;
+
+        self.apply(spec.into_v1(), true).map_err(|e| {
+            anyhow!("Failed to apply '{}' file: {}", path.display(), e)
+        })
+    }
+
+    /// Lists names of all the snapshot files in the given `dir`.
+    ///
+    /// # Errors
+    ///
+    /// If the `dir` fails to be read.
+    async fn list_snapshots(dir: &Path) -> Result<Vec<String>, anyhow::Error> {
+        let err = |e| anyhow!("Failed to read '{}' dir: {}", dir.display(), e);
+
+        let mut entries = fs::read_dir(dir).await.map_err(err)?;
+        let mut names = vec![];
+        while let Some(entry) = entries.next_entry().await.map_err(err)? {
+            if let Some(name) = entry.file_name().to_str() {
+                if Self::is_snapshot_name(name) {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// Checks whether the given file `name` represents a snapshot file written
+    /// by [`State::snapshot()`].
+    #[must_use]
+    fn is_snapshot_name(name: &str) -> bool {
+        name.starts_with(SNAPSHOT_PREFIX)
+            && name.ends_with(SNAPSHOT_SUFFIX)
+            && !name.contains('/')
+            && !name.contains("..")
+    }
+
     /// Subscribes the specified `hook` to changes of the [`Mutable`] `val`ue.
     ///
     /// `name` is just a convenience for describing the `hook` in logs.
@@ -182,22 +415,102 @@ impl State {
         ));
     }
 
+    /// Bumps [`State::revision`] to the next [`Revision`].
+    #[inline]
+    pub fn bump_revision(&self) {
+        self.revision.replace_with(|r| r.next());
+    }
+
+    /// Subscribes to updates of [`State::restreams`].
+    ///
+    /// If the given `since` [`Revision`] is the current one, then the current
+    /// [`State::restreams`] are not emitted (as the subscriber has them
+    /// already), but only the ones of the next [`Revision`]s. Otherwise, the
+    /// current [`State::restreams`] are emitted right away.
+    #[must_use]
+    pub fn restreams_since(
+        &self,
+        since: Option<Revision>,
+    ) -> BoxStream<'static, Vec<Restream>> {
+        let since = if let Some(rev) = since {
+            rev
+        } else {
+            return self
+                .restreams
+                .signal_cloned()
+                .dedupe_cloned()
+                .to_stream()
+                .boxed();
+        };
+
+        // Emitting on `State::revision` bumps rather than on
+        // `State::restreams` changes, so the skipped emission cannot race
+        // with a change not being accounted in the `State::revision` yet.
+        let restreams = self.restreams.clone();
+        self.revision
+            .signal()
+            .dedupe()
+            .to_stream()
+            .skip_while(move |rev| future::ready(*rev == since))
+            .map(move |_| restreams.get_cloned())
+            .boxed()
+    }
+
     /// Adds a new [`Restream`] by the given `spec` to this [`State`].
     ///
     /// # Errors
     ///
-    /// If this [`State`] has a [`Restream`] with such `key` already.
+    /// - If this [`State`] has a [`Restream`] with such `key` already.
+    /// - If the given `spec` is invalid (see [`Restream::validate_spec()`]).
     pub fn add_restream(&self, spec: spec::v1::Restream) -> anyhow::Result<()> {
         let mut restreams = self.restreams.lock_mut();
 
         if restreams.iter().any(|r| r.key == spec.key) {
             return Err(anyhow!("Restream.key '{}' is used already", spec.key));
         }
+        Restream::validate_spec(&spec, restreams.iter())?;
 
         restreams.push(Restream::new(spec));
         Ok(())
     }
 
+    /// Copies a [`Restream`] identified by the given `id` in this [`State`]
+    /// as a new [`Restream`] with the given `new_key`.
+    ///
+    /// The copy has all its IDs regenerated, and all its [`Output`]s disabled
+    /// to avoid publishing to the same destinations twice accidentally.
+    ///
+    /// Returns [`None`] if there is no [`Restream`] with such `id` in this
+    /// [`State`].
+    ///
+    /// # Errors
+    ///
+    /// If this [`State`] has a [`Restream`] with such `new_key` already.
+    pub fn copy_restream(
+        &self,
+        id: RestreamId,
+        new_key: RestreamKey,
+    ) -> anyhow::Result<Option<()>> {
+        let mut restreams = self.restreams.lock_mut();
+
+        if restreams.iter().any(|r| r.key == new_key) {
+            return Err(anyhow!("Restream.key '{}' is used already", new_key));
+        }
+
+        let mut spec = if let Some(r) = restreams.iter().find(|r| r.id == id) {
+            r.export()
+        } else {
+            return Ok(None);
+        };
+        spec.key = new_key;
+        for o in &mut spec.outputs {
+            o.enabled = false;
+        }
+
+        restreams.push(Restream::new(spec));
+        Ok(Some(()))
+    }
+
     /// Edits a [`Restream`] with the given `spec` identified by the given `id`
     /// in this [`State`].
     ///
@@ -206,7 +519,8 @@ impl State {
     ///
     /// # Errors
     ///
-    /// If this [`State`] has a [`Restream`] with such `key` already.
+    /// - If this [`State`] has a [`Restream`] with such `key` already.
+    /// - If the given `spec` is invalid (see [`Restream::validate_spec()`]).
     pub fn edit_restream(
         &self,
         id: RestreamId,
@@ -217,6 +531,8 @@ impl State {
         if restreams.iter().any(|r| r.key == spec.key && r.id != id) {
             return Err(anyhow!("Restream.key '{}' is used already", spec.key));
         }
+        let others = restreams.iter().filter(|r| r.id != id);
+        Restream::validate_spec(&spec, others)?;
 
         #[allow(clippy::find_map)] // due to consuming `spec`
         Ok(restreams
@@ -237,6 +553,65 @@ impl State {
         (restreams.len() != prev_len).then(|| ())
     }
 
+    /// Sets or unsets the [`State::maintenance_notice`].
+    ///
+    /// Blank `notice` is considered as unsetting. Surrounding whitespaces are
+    /// trimmed.
+    ///
+    /// Returns `true` if the notice has been changed, or `false` if it's the
+    /// same already.
+    pub fn set_maintenance_notice(&self, notice: Option<String>) -> bool {
+        let notice = notice
+            .map(|n| n.trim().to_owned())
+            .filter(|n| !n.is_empty());
+
+        let mut current = self.maintenance_notice.lock_mut();
+        if *current == notice {
+            return false;
+        }
+        *current = notice;
+        true
+    }
+
+    /// Reorders [`Restream`]s of this [`State`] to match the given `order` of
+    /// their IDs.
+    ///
+    /// Returns `true` if the order has been changed, or `false` if it's the
+    /// same already.
+    ///
+    /// # Errors
+    ///
+    /// If the given `order` is not a permutation of IDs of all the existing
+    /// [`Restream`]s in this [`State`].
+    pub fn set_restreams_order(
+        &self,
+        order: &[RestreamId],
+    ) -> anyhow::Result<bool> {
+        let mut restreams = self.restreams.lock_mut();
+
+        let unique = order.iter().collect::<HashSet<_>>();
+        if unique.len() != order.len() {
+            return Err(anyhow!("Restreams order contains duplicate IDs"));
+        }
+        if order.len() != restreams.len() {
+            return Err(anyhow!(
+                "Restreams order should contain exactly {} IDs, but {} given",
+                restreams.len(),
+                order.len(),
+            ));
+        }
+        if let Some(r) = restreams.iter().find(|r| !unique.contains(&r.id)) {
+            return Err(anyhow!("Restreams order misses Restream.id {}", r.id));
+        }
+
+        if restreams.iter().map(|r| &r.id).eq(order.iter()) {
+            return Ok(false);
+        }
+
+        restreams.sort_by_key(|r| order.iter().position(|id| *id == r.id));
+        Ok(true)
+    }
+
     /// Enables a [`Restream`] with the given `id` in this [`State`].
     ///
     /// Returns `true` if it has been enabled, or `false` if it already has been
@@ -261,6 +636,19 @@ impl State {
             .find_map(|r| (r.id == id).then(|| r.input.disable()))
     }
 
+    /// Restarts all the re-streaming processes of a [`Restream`] with the
+    /// given `id` in this [`State`], regardless of their current status.
+    ///
+    /// Returns [`None`] if there is no [`Restream`] with such `id` in this
+    /// [`State`].
+    #[must_use]
+    pub fn restart_restream(&self, id: RestreamId) -> Option<()> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams.iter_mut().find(|r| r.id == id)?;
+        restream.generation = restream.generation.wrapping_add(1);
+        Some(())
+    }
+
     /// Enables an [`Input`] with the given `id` in the specified [`Restream`]
     /// of this [`State`].
     ///
@@ -308,13 +696,19 @@ impl State {
     ///
     /// # Errors
     ///
-    /// If the [`Restream`] has an [`Output`] with such `dst` already.
+    /// - If the [`Restream`] has an [`Output`] with such `dst` already.
+    /// - If the given `spec` is invalid (see [`Output::validate_spec()`]).
+    /// - If the given `dst` loops back into this [`State`] (see
+    ///   [`Output::validate_dst()`]).
     pub fn add_output(
         &self,
         restream_id: RestreamId,
         spec: spec::v1::Output,
     ) -> anyhow::Result<Option<()>> {
+        Output::validate_spec(&spec)?;
+
         let mut restreams = self.restreams.lock_mut();
+        Output::validate_dst(&spec.dst, restreams.iter())?;
 
         let outputs = if let Some(r) =
             restreams.iter_mut().find(|r| r.id == restream_id)
@@ -332,6 +726,62 @@ impl State {
         Ok(Some(()))
     }
 
+    /// Adds multiple new [`Output`]s to the specified [`Restream`] of this
+    /// [`State`] at once.
+    ///
+    /// Either all the [`Output`]s are added, or none of them.
+    ///
+    /// Returns IDs of the added [`Output`]s, or [`None`] if there is no
+    /// [`Restream`] with such `id` in this [`State`].
+    ///
+    /// # Errors
+    ///
+    /// - If the [`Restream`] has an [`Output`] with any of the given `dst`s
+    ///   already, or the given `specs` contain duplicated `dst`s.
+    /// - If any of the given `specs` is invalid (see
+    ///   [`Output::validate_spec()`]).
+    /// - If any of the given `dst`s loops back into this [`State`] (see
+    ///   [`Output::validate_dst()`]).
+    pub fn add_outputs(
+        &self,
+        restream_id: RestreamId,
+        specs: Vec<spec::v1::Output>,
+    ) -> anyhow::Result<Option<Vec<OutputId>>> {
+        for spec in &specs {
+            Output::validate_spec(spec)?;
+        }
+
+        let mut restreams = self.restreams.lock_mut();
+        for spec in &specs {
+            Output::validate_dst(&spec.dst, restreams.iter())?;
+        }
+
+        let outputs = if let Some(r) =
+            restreams.iter_mut().find(|r| r.id == restream_id)
+        {
+            &mut r.outputs
+        } else {
+            return Ok(None);
+        };
+
+        let mut dsts = HashSet::with_capacity(specs.len());
+        for spec in &specs {
+            if outputs.iter().any(|o| o.dst == spec.dst)
+                || !dsts.insert(&spec.dst)
+            {
+                return Err(anyhow!(
+                    "Output.dst '{}' is used already",
+                    spec.dst,
+                ));
+            }
+        }
+
+        let new = specs.into_iter().map(Output::new).collect::<Vec<_>>();
+        let ids = new.iter().map(|o| o.id).collect();
+        outputs.extend(new);
+        Ok(Some(ids))
+    }
+
     /// Edits an [`Output`] with the given `spec` identified by the given `id`
     /// in the specified [`Restream`] of this [`State`].
     ///
@@ -340,14 +790,20 @@ impl State {
     ///
     /// # Errors
     ///
-    /// If the [`Restream`] has an [`Output`] with such `dst` already.
+    /// - If the [`Restream`] has an [`Output`] with such `dst` already.
+    /// - If the given `spec` is invalid (see [`Output::validate_spec()`]).
+    /// - If the given `dst` loops back into this [`State`] (see
+    ///   [`Output::validate_dst()`]).
     pub fn edit_output(
         &self,
         restream_id: RestreamId,
         id: OutputId,
         spec: spec::v1::Output,
     ) -> anyhow::Result<Option<()>> {
+        Output::validate_spec(&spec)?;
+
         let mut restreams = self.restreams.lock_mut();
+        Output::validate_dst(&spec.dst, restreams.iter())?;
 
         let outputs = if let Some(r) =
             restreams.iter_mut().find(|r| r.id == restream_id)
@@ -391,6 +847,9 @@ impl State {
     /// Enables an [`Output`] with the given `id` in the specified [`Restream`]
     /// of this [`State`].
     ///
+    /// The change is persisted right away, and takes precedence over the
+    /// [`Output::enabled`] value of later imported [`Spec`]s.
+    ///
     /// Returns `true` if it has been enabled, or `false` if it already has been
     /// enabled, or [`None`] if it doesn't exist.
     #[must_use]
@@ -418,6 +877,9 @@ impl State {
     /// Disables an [`Output`] with the given `id` in the specified [`Restream`]
     /// of this [`State`].
     ///
+    /// The change is persisted right away, and takes precedence over the
+    /// [`Output::enabled`] value of later imported [`Spec`]s.
+    ///
     /// Returns `true` if it has been disabled, or `false` if it already has
     /// been disabled, or [`None`] if it doesn't exist.
     #[must_use]
@@ -487,20 +949,74 @@ impl State {
         )
     }
 
-    /// Tunes a [`Volume`] rate of the specified [`Output`] or its [`Mixin`] in
-    /// this [`State`].
+    /// Sets a [`TranscodingProfile`] of the specified [`Output`] in this
+    /// [`State`].
     ///
-    /// Returns `true` if a [`Volume`] rate has been changed, or `false` if it
-    /// has the same value already.
+    /// [`None`] `profile` means re-streaming "as is", without transcoding.
     ///
-    /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
+    /// Returns `true` if a [`TranscodingProfile`] has been changed, or `false`
+    /// if it has the same value already.
+    ///
+    /// Returns [`None`] if no such [`Restream`]/[`Output`] exists.
+    ///
+    /// # Errors
+    ///
+    /// If the `profile` is given for an [`Output`] which cannot be transcoded
+    /// (see [`Output::effective_transcoding()`]).
+    pub fn set_output_transcoding(
+        &self,
+        restream_id: RestreamId,
+        output_id: OutputId,
+        profile: Option<TranscodingProfile>,
+    ) -> anyhow::Result<Option<bool>> {
+        let mut restreams = self.restreams.lock_mut();
+        let output = if let Some(o) = restreams
+            .iter_mut()
+            .find(|r| r.id == restream_id)
+            .and_then(|r| r.outputs.iter_mut().find(|o| o.id == output_id))
+        {
+            o
+        } else {
+            return Ok(None);
+        };
+
+        if profile.is_some() {
+            if !output.mixins.is_empty() {
+                return Err(anyhow!(
+                    "Transcoding is not supported for Output with Mixins"
+                ));
+            }
+            if !matches!(output.dst.scheme(), "rtmp" | "rtmps" | "srt") {
+                return Err(anyhow!(
+                    "Transcoding is supported for RTMP and SRT Output.dst \
+                     only, but '{}' is given",
+                    output.dst,
+                ));
+            }
+        }
+        if output.transcoding == profile {
+            return Ok(Some(false));
+        }
+
+        output.transcoding = profile;
+        Ok(Some(true))
+    }
+
+    /// Sets a [`KeyframeInterval`] of the specified [`Output`] in this
+    /// [`State`].
+    ///
+    /// [`None`] `interval` means using the default [`KeyframeInterval`].
+    ///
+    /// Returns `true` if a [`KeyframeInterval`] has been changed, or `false`
+    /// if it has the same value already.
+    ///
+    /// Returns [`None`] if no such [`Restream`]/[`Output`] exists.
     #[must_use]
-    pub fn tune_volume(
+    pub fn set_output_keyframe_interval(
         &self,
         restream_id: RestreamId,
         output_id: OutputId,
-        mixin_id: Option<MixinId>,
-        volume: Volume,
+        interval: Option<KeyframeInterval>,
     ) -> Option<bool> {
         let mut restreams = self.restreams.lock_mut();
         let output = restreams
@@ -510,78 +1026,414 @@ impl State {
             .iter_mut()
             .find(|o| o.id == output_id)?;
 
-        let curr_volume = if let Some(id) = mixin_id {
-            &mut output.mixins.iter_mut().find(|m| m.id == id)?.volume
-        } else {
-            &mut output.volume
-        };
-
-        if *curr_volume == volume {
+        if output.keyframe_interval == interval {
             return Some(false);
         }
 
-        *curr_volume = volume;
+        output.keyframe_interval = interval;
         Some(true)
     }
 
-    /// Tunes a [`Delay`] of the specified [`Mixin`] in this [`State`].
+    /// Tunes a [`Volume`] rate of the specified [`Output`] or its [`Mixin`] in
+    /// this [`State`].
     ///
-    /// Returns `true` if a [`Delay`] has been changed, or `false` if it has the
-    /// same value already.
+    /// Returns `true` if a [`Volume`] rate has been changed, or `false` if it
+    /// has the same value already.
     ///
     /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
     #[must_use]
-    pub fn tune_delay(
+    pub fn tune_volume(
         &self,
-        input_id: RestreamId,
+        restream_id: RestreamId,
         output_id: OutputId,
-        mixin_id: MixinId,
-        delay: Delay,
+        mixin_id: Option<MixinId>,
+        volume: Volume,
     ) -> Option<bool> {
-        let mut restreams = self.restreams.lock_mut();
-        let mixin = restreams
+        self.restreams
+            .lock_mut()
             .iter_mut()
-            .find(|r| r.id == input_id)?
+            .find(|r| r.id == restream_id)?
             .outputs
             .iter_mut()
             .find(|o| o.id == output_id)?
-            .mixins
-            .iter_mut()
-            .find(|m| m.id == mixin_id)?;
+            .tune_volume(mixin_id, volume)
+    }
 
-        if mixin.delay == delay {
-            return Some(false);
-        }
+    /// Tunes [`Volume`] rates of multiple [`Output`]s or their [`Mixin`]s in
+    /// the specified [`Restream`] of this [`State`] at once.
+    ///
+    /// All the `changes` are applied under a single lock, so are observed (and
+    /// propagated to [FFmpeg] processes) as a single [`State`] change.
+    ///
+    /// Returns results of each of the `changes` in the same order, as
+    /// [`State::tune_volume()`] does, or [`None`] if no such [`Restream`]
+    /// exists.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[must_use]
+    pub fn tune_volumes(
+        &self,
+        restream_id: RestreamId,
+        changes: &[VolumeChange],
+    ) -> Option<Vec<Option<bool>>> {
+        let mut restreams = self.restreams.lock_mut();
+        let outputs =
+            &mut restreams.iter_mut().find(|r| r.id == restream_id)?.outputs;
 
-        mixin.delay = delay;
-        Some(true)
+        Some(Self::tune_outputs_volumes(outputs, changes.iter().copied()))
     }
-}
 
-/// Re-stream of a live stream from one `Input` to many `Output`s.
-#[derive(
-    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
-)]
-pub struct Restream {
-    /// Unique ID of this `Input`.
+    /// Tunes [`Volume`] rates of the given [`Output`]s or their [`Mixin`]s
+    /// with the given `changes`.
     ///
-    /// Once assigned, it never changes.
-    pub id: RestreamId,
-
-    /// Unique key of this `Restream` identifying it, and used to form its
-    /// endpoints URLs.
-    pub key: RestreamKey,
+    /// Returns results of each of the `changes` in the same order, as
+    /// [`Output::tune_volume()`] does.
+    fn tune_outputs_volumes<I>(
+        outputs: &mut [Output],
+        changes: I,
+    ) -> Vec<Option<bool>>
+    where
+        I: IntoIterator<Item = VolumeChange>,
+    {
+        changes
+            .into_iter()
+            .map(|c| {
+                outputs
+                    .iter_mut()
+                    .find(|o| o.id == c.output_id)?
+                    .tune_volume(c.mixin_id, c.volume)
+            })
+            .collect()
+    }
+
+    /// Adds a new [`Preset`] with the given `name` and [`Volume`] rates to the
+    /// specified [`Restream`] of this [`State`].
+    ///
+    /// Returns ID of the added [`Preset`], or [`None`] if there is no
+    /// [`Restream`] with such `restream_id` in this [`State`].
+    ///
+    /// # Errors
+    ///
+    /// If the [`Restream`] has a [`Preset`] with the given `name` already.
+    pub fn add_preset(
+        &self,
+        restream_id: RestreamId,
+        name: Label,
+        volumes: &[VolumeChange],
+    ) -> anyhow::Result<Option<PresetId>> {
+        let mut restreams = self.restreams.lock_mut();
+
+        let presets = if let Some(r) =
+            restreams.iter_mut().find(|r| r.id == restream_id)
+        {
+            &mut r.presets
+        } else {
+            return Ok(None);
+        };
+
+        if presets.iter().any(|p| p.name == name) {
+            return Err(anyhow!("Preset.name '{}' is used already", name));
+        }
+
+        let preset = Preset {
+            id: PresetId::random(),
+            name,
+            volumes: volumes
+                .iter()
+                .map(|c| PresetVolume {
+                    output_id: c.output_id,
+                    mixin_id: c.mixin_id,
+                    volume: c.volume,
+                })
+                .collect(),
+        };
+        let id = preset.id;
+        presets.push(preset);
+        Ok(Some(id))
+    }
+
+    /// Applies [`Volume`] rates of the specified [`Preset`] to the [`Output`]s
+    /// and their [`Mixin`]s of its [`Restream`] at once, as
+    /// [`State::tune_volumes()`] does.
+    ///
+    /// Returns [`None`] if no such [`Restream`]/[`Preset`] exists.
+    #[must_use]
+    pub fn apply_preset(
+        &self,
+        restream_id: RestreamId,
+        id: PresetId,
+    ) -> Option<Vec<Option<bool>>> {
+        let mut restreams = self.restreams.lock_mut();
+        let restream = restreams.iter_mut().find(|r| r.id == restream_id)?;
+        let volumes = restream
+            .presets
+            .iter()
+            .find(|p| p.id == id)?
+            .volumes
+            .clone();
+
+        Some(Self::tune_outputs_volumes(
+            &mut restream.outputs,
+            volumes.into_iter().map(Into::into),
+        ))
+    }
+
+    /// Removes a [`Preset`] with the given `id` from the specified
+    /// [`Restream`] of this [`State`].
+    ///
+    /// Returns [`None`] if there is no [`Restream`] with such `restream_id` or
+    /// no [`Preset`] with such `id` in this [`State`].
+    #[allow(clippy::must_use_candidate)]
+    pub fn remove_preset(
+        &self,
+        id: PresetId,
+        restream_id: RestreamId,
+    ) -> Option<()> {
+        let mut restreams = self.restreams.lock_mut();
+        let presets =
+            &mut restreams.iter_mut().find(|r| r.id == restream_id)?.presets;
+
+        let prev_len = presets.len();
+        presets.retain(|p| p.id != id);
+        (presets.len() != prev_len).then(|| ())
+    }
+
+    /// Tunes a [`Delay`] of the specified [`Mixin`] in this [`State`].
+    ///
+    /// Returns `true` if a [`Delay`] has been changed, or `false` if it has the
+    /// same value already.
+    ///
+    /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
+    #[must_use]
+    pub fn tune_delay(
+        &self,
+        input_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: MixinId,
+        delay: Delay,
+    ) -> Option<bool> {
+        let mut restreams = self.restreams.lock_mut();
+        let mixin = restreams
+            .iter_mut()
+            .find(|r| r.id == input_id)?
+            .outputs
+            .iter_mut()
+            .find(|o| o.id == output_id)?
+            .mixins
+            .iter_mut()
+            .find(|m| m.id == mixin_id)?;
+
+        if mixin.delay == delay {
+            return Some(false);
+        }
+
+        mixin.delay = delay;
+        Some(true)
+    }
+
+    /// Tunes a [`Delay`] of the specified [`Output`] in this [`State`].
+    ///
+    /// Returns `true` if a [`Delay`] has been changed, or `false` if it has the
+    /// same value already.
+    ///
+    /// Returns [`None`] if no such [`Restream`]/[`Output`] exists.
+    #[must_use]
+    pub fn tune_output_delay(
+        &self,
+        input_id: RestreamId,
+        output_id: OutputId,
+        delay: Delay,
+    ) -> Option<bool> {
+        let mut restreams = self.restreams.lock_mut();
+        let output = restreams
+            .iter_mut()
+            .find(|r| r.id == input_id)?
+            .outputs
+            .iter_mut()
+            .find(|o| o.id == output_id)?;
+
+        if output.delay == delay {
+            return Some(false);
+        }
+
+        output.delay = delay;
+        Some(true)
+    }
+
+    /// Tunes a sidechain ducking of the specified [`Mixin`] in this [`State`].
+    ///
+    /// Returns `true` if a sidechain ducking has been changed, or `false` if
+    /// it has the same value already.
+    ///
+    /// Returns [`None`] if no such [`Restream`]/[`Output`]/[`Mixin`] exists.
+    ///
+    /// # Errors
+    ///
+    /// If the specified [`Mixin`] is not a [TeamSpeak] one.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub fn tune_sidechain(
+        &self,
+        input_id: RestreamId,
+        output_id: OutputId,
+        mixin_id: MixinId,
+        sidechain: bool,
+    ) -> anyhow::Result<Option<bool>> {
+        let mut restreams = self.restreams.lock_mut();
+        let mixin = if let Some(m) = restreams
+            .iter_mut()
+            .find(|r| r.id == input_id)
+            .and_then(|r| r.outputs.iter_mut().find(|o| o.id == output_id))
+            .and_then(|o| o.mixins.iter_mut().find(|m| m.id == mixin_id))
+        {
+            m
+        } else {
+            return Ok(None);
+        };
+
+        if mixin.src.scheme() != "ts" {
+            return Err(anyhow!(
+                "Sidechain is supported for TeamSpeak Mixin.src only, \
+                 but '{}' is given",
+                mixin.src,
+            ));
+        }
+        if mixin.sidechain == sidechain {
+            return Ok(Some(false));
+        }
+
+        mixin.sidechain = sidechain;
+        Ok(Some(true))
+    }
+}
+
+/// Prefix of a [`State::snapshot()`] file name.
+const SNAPSHOT_PREFIX: &str = "spec-";
+
+/// Suffix of a [`State::snapshot()`] file name.
+const SNAPSHOT_SUFFIX: &str = ".json";
+
+/// Writes the given `contents` into the `file` atomically.
+///
+/// The `contents` is written into a temporary file in the same directory first,
+/// and only then is renamed over the `file`, so the `file` is never left
+/// truncated if the process is killed in the middle of writing.
+///
+/// # Errors
+///
+/// If the temporary file fails to be written or renamed.
+async fn persist_atomically(file: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp = tmp_path(file);
+
+    let mut f = fs::File::create(&tmp).await?;
+    f.write_all(contents).await?;
+    f.sync_all().await?;
+    drop(f);
+
+    fs::rename(&tmp, file).await
+}
+
+/// Returns path of the temporary file used for persisting the given `file`
+/// atomically.
+#[must_use]
+fn tmp_path(file: &Path) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_owned();
+    name.push(".tmp");
+    file.with_file_name(name)
+}
+
+/// Monotonically increasing revision of a [`State`], allowing clients to
+/// detect whether they have its latest version already.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub struct Revision(u64);
+
+impl Revision {
+    /// Returns the [`Revision`] following this one.
+    #[inline]
+    #[must_use]
+    pub fn next(self) -> Self {
+        Self(self.0.wrapping_add(1))
+    }
+}
+
+/// Type of a revision of this server's state.
+///
+/// Represented as a decimal string, as may not fit into a GraphQL `Int`.
+#[graphql_scalar]
+impl<S> GraphQLScalar for Revision
+where
+    S: ScalarValue,
+{
+    fn resolve(&self) -> Value {
+        Value::scalar(self.0.to_string())
+    }
+
+    fn from_input_value(v: &InputValue) -> Option<Self> {
+        v.as_scalar()
+            .and_then(ScalarValue::as_str)
+            .and_then(|s| s.parse().ok())
+            .map(Self)
+    }
+
+    fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+}
+
+/// Re-stream of a live stream from one `Input` to many `Output`s.
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct Restream {
+    /// Unique ID of this `Input`.
+    ///
+    /// Once assigned, it never changes.
+    pub id: RestreamId,
+
+    /// Unique key of this `Restream` identifying it, and used to form its
+    /// endpoints URLs.
+    pub key: RestreamKey,
 
     /// Optional label of this `Restream`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub label: Option<Label>,
 
+    /// Optional [SRS] `vhost` to route this `Restream`'s live streams through.
+    ///
+    /// If `null`, then the default [SRS] `vhost` is used.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srs_vhost: Option<SrsVhost>,
+
     /// `Input` that a live stream is received from.
     pub input: Input,
 
     /// `Output`s that a live stream is re-streamed to.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub outputs: Vec<Output>,
+
+    /// `Preset`s of `Output`s' and their `Mixin`s' volumes, which may be
+    /// applied at once.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub presets: Vec<Preset>,
+
+    /// Generation of this `Restream`'s re-streaming processes, bumped to force
+    /// their restart.
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub generation: u64,
 }
 
 impl Restream {
@@ -589,23 +1441,60 @@ impl Restream {
     #[inline]
     #[must_use]
     pub fn new(spec: spec::v1::Restream) -> Self {
+        let outputs: Vec<_> =
+            spec.outputs.into_iter().map(Output::new).collect();
+        let presets = spec
+            .presets
+            .into_iter()
+            .map(|p| Preset::new(p, &outputs))
+            .collect();
         Self {
             id: RestreamId::random(),
             key: spec.key,
             label: spec.label,
+            srs_vhost: spec.srs_vhost,
             input: Input::new(spec.input),
-            outputs: spec.outputs.into_iter().map(Output::new).collect(),
+            outputs,
+            presets,
+            generation: 0,
+        }
+    }
+
+    /// Validates the given [`spec::v1::Restream`] before creating or applying
+    /// a [`Restream`] out of it, alongside with the given other `restreams`.
+    ///
+    /// # Errors
+    ///
+    /// - If any of its [`spec::v1::Output`]s is invalid (see
+    ///   [`Output::validate_spec()`]).
+    /// - If any of its [`spec::v1::Output`]s loops back into itself or the
+    ///   given `restreams` (see [`Output::validate_dst()`]).
+    pub fn validate_spec<'a, I>(
+        spec: &spec::v1::Restream,
+        restreams: I,
+    ) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = &'a Restream>,
+        I::IntoIter: Clone,
+    {
+        let this = Self::new(spec.clone());
+        let restreams = restreams.into_iter().chain(iter::once(&this));
+        for o in &spec.outputs {
+            Output::validate_spec(o)?;
+            Output::validate_dst(&o.dst, restreams.clone())?;
         }
+        Ok(())
     }
 
     /// Applies the given [`spec::v1::Restream`] to this [`Restream`].
     ///
-    /// If `replace` is `true` then all the [`Restream::outputs`] will be
-    /// replaced with new ones, otherwise new ones will be merged with already
-    /// existing [`Restream::outputs`].
+    /// If `replace` is `true` then all the [`Restream::outputs`] and
+    /// [`Restream::presets`] will be replaced with new ones, otherwise new ones
+    /// will be merged with already existing ones.
     pub fn apply(&mut self, new: spec::v1::Restream, replace: bool) {
         self.key = new.key;
         self.label = new.label;
+        self.srs_vhost = new.srs_vhost;
         self.input.apply(new.input);
         if replace {
             let mut olds = mem::replace(
@@ -636,6 +1525,22 @@ impl Restream {
                 }
             }
         }
+
+        // `Preset`s are resolved against the already applied `Output`s.
+        if replace {
+            self.presets
+                .retain(|old| new.presets.iter().any(|p| p.name == old.name));
+        }
+        for new in new.presets {
+            let preset = Preset::new(new, &self.outputs);
+            if let Some(old) =
+                self.presets.iter_mut().find(|p| p.name == preset.name)
+            {
+                old.volumes = preset.volumes;
+            } else {
+                self.presets.push(preset);
+            }
+        }
     }
 
     /// Exports this [`Restream`] as a [`spec::v1::Restream`].
@@ -645,9 +1550,58 @@ impl Restream {
         spec::v1::Restream {
             key: self.key.clone(),
             label: self.label.clone(),
+            srs_vhost: self.srs_vhost.clone(),
             input: self.input.export(),
             outputs: self.outputs.iter().map(Output::export).collect(),
+            presets: self
+                .presets
+                .iter()
+                .map(|p| p.export(&self.outputs))
+                .collect(),
+        }
+    }
+
+    /// Estimates latency added to a live stream by this [`Restream`] when
+    /// re-streaming it to the given [`Output`].
+    ///
+    /// As real end-to-end latency cannot be measured without cooperation of
+    /// the downstream side, the estimate is just a sum of the known buffers
+    /// and delays of the re-streaming pipeline:
+    /// - [SRS] merged-write latency for each hop a live stream passes through
+    ///   (one more hop for a failover [`Input`]);
+    /// - encoder latency if the [`Output`] is transcoded;
+    /// - the largest [`Mixin::delay`], as mixed audio tracks are synchronized
+    ///   by their timestamps.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[must_use]
+    pub fn estimate_latency(&self, output: &Output) -> Duration {
+        /// Latency added by [SRS] on each hop, being its default `mw_latency`.
+        ///
+        /// [SRS]: https://github.com/ossrs/srs
+        const SRS_HOP: Duration = Duration::from_millis(350);
+
+        /// Latency added by [FFmpeg] transcoding with `superfast` [x264]
+        /// preset, roughly.
+        ///
+        /// [FFmpeg]: https://ffmpeg.org
+        /// [x264]: https://trac.ffmpeg.org/wiki/Encode/H.264
+        const TRANSCODING: Duration = Duration::from_secs(1);
+
+        let mut latency = SRS_HOP;
+        if let Some(InputSrc::Failover(_)) = &self.input.src {
+            latency += SRS_HOP;
+        }
+        if output.effective_transcoding().is_some() {
+            latency += TRANSCODING;
         }
+        latency += output
+            .mixins
+            .iter()
+            .map(|m| m.delay.0)
+            .max()
+            .unwrap_or_default();
+        latency
     }
 
     /// Returns an URL on a local [SRS] server of the endpoint representing a
@@ -657,7 +1611,28 @@ impl Restream {
     #[must_use]
     pub fn main_input_rtmp_endpoint_url(&self) -> Url {
         let main = self.input.endpoints.iter().find(|e| e.is_rtmp()).unwrap();
-        main.kind.rtmp_url(&self.key, &self.input.key)
+        main.kind
+            .rtmp_url(&self.key, &self.input.key, self.srs_vhost.as_ref())
+    }
+
+    /// Returns the enabled [`Output`] of this [`Restream`] feeding its sibling
+    /// [`Output`]s (see [`Output::feeds_siblings`]), if any.
+    ///
+    /// Only the first such [`Output`] is considered.
+    #[must_use]
+    pub fn siblings_feeder(&self) -> Option<&Output> {
+        self.outputs.iter().find(|o| o.enabled && o.feeds_siblings)
+    }
+
+    /// Indicates whether the given `stream` on a local [SRS] server is the one
+    /// published by the [`Restream::siblings_feeder()`] of this [`Restream`].
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[must_use]
+    pub fn is_feeder_stream(&self, stream: &str) -> bool {
+        self.siblings_feeder()
+            .and_then(|o| o.dst.local_srs_stream())
+            .map_or(false, |(app, s)| self.key == *app && s == stream)
     }
 }
 
@@ -671,6 +1646,7 @@ impl Restream {
     Eq,
     From,
     GraphQLScalarValue,
+    Hash,
     Into,
     PartialEq,
     Serialize,
@@ -686,6 +1662,43 @@ impl RestreamId {
     }
 }
 
+/// Default maximum length of [`RestreamKey`]s and [`InputKey`]s.
+pub const DEFAULT_KEY_MAX_LEN: usize = 20;
+
+/// Maximum length of [`RestreamKey`]s and [`InputKey`]s used when creating
+/// them without an explicitly specified one.
+static KEY_MAX_LEN: AtomicUsize = AtomicUsize::new(DEFAULT_KEY_MAX_LEN);
+
+/// Returns the maximum length of [`RestreamKey`]s and [`InputKey`]s, which is
+/// used by [`RestreamKey::new()`] and [`InputKey::new()`].
+#[inline]
+#[must_use]
+pub fn key_max_len() -> usize {
+    KEY_MAX_LEN.load(Ordering::Relaxed)
+}
+
+/// Sets the maximum length of [`RestreamKey`]s and [`InputKey`]s, which is
+/// used by [`RestreamKey::new()`] and [`InputKey::new()`].
+///
+/// Should be called before any [`State`] is loaded.
+#[inline]
+pub fn set_key_max_len(len: usize) {
+    KEY_MAX_LEN.store(len, Ordering::Relaxed);
+}
+
+/// Checks whether the given value is a valid [`RestreamKey`] or [`InputKey`]
+/// of no longer than `max_len` characters.
+///
+/// Allowed characters are safe to be used in URL paths: `[a-z0-9_.-]`, while
+/// the first character cannot be a dot (to disallow `.` and `..` paths).
+#[must_use]
+fn is_valid_key(val: &str, max_len: usize) -> bool {
+    static REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new("^[a-z0-9_-][a-z0-9_.-]*$").unwrap());
+
+    !val.is_empty() && val.len() <= max_len && REGEX.is_match(val)
+}
+
 /// Key of a [`Restream`] identifying it, and used to form its endpoints URLs.
 #[derive(
     Clone, Debug, Deref, Display, Eq, Hash, Into, PartialEq, Serialize,
@@ -693,15 +1706,23 @@ impl RestreamId {
 pub struct RestreamKey(String);
 
 impl RestreamKey {
-    /// Creates a new [`RestreamKey`] if the given value meets its invariants.
+    /// Creates a new [`RestreamKey`] if the given value meets its invariants,
+    /// considering the configured [`key_max_len()`].
+    #[inline]
     #[must_use]
     pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Option<Self> {
-        static REGEX: Lazy<Regex> =
-            Lazy::new(|| Regex::new("^[a-z0-9_-]{1,20}$").unwrap());
+        Self::new_with_len(val, key_max_len())
+    }
 
+    /// Creates a new [`RestreamKey`] if the given value meets its invariants,
+    /// being no longer than the given `max_len`.
+    #[must_use]
+    pub fn new_with_len<'s, S: Into<Cow<'s, str>>>(
+        val: S,
+        max_len: usize,
+    ) -> Option<Self> {
         let val = val.into();
-        (!val.is_empty() && REGEX.is_match(&val))
-            .then(|| Self(val.into_owned()))
+        is_valid_key(&val, max_len).then(|| Self(val.into_owned()))
     }
 }
 
@@ -719,7 +1740,8 @@ impl<'de> Deserialize<'de> for RestreamKey {
 /// Type of `Restream`'s `key` identifying it, and used to form its endpoints
 /// URLs.
 ///
-/// It should meet `[a-z0-9_-]{1,20}` format.
+/// It should meet `[a-z0-9_-][a-z0-9_.-]*` format, and be no longer than
+/// the configured maximum length (20 characters by default).
 #[graphql_scalar]
 impl<S> GraphQLScalar for RestreamKey
 where
@@ -747,12 +1769,80 @@ impl PartialEq<str> for RestreamKey {
     }
 }
 
-/// Upstream source that a `Restream` receives a live stream from.
+/// [SRS] `vhost` ([virtual host][1]) to route a [`Restream`]'s live streams
+/// through.
+///
+/// [SRS]: https://github.com/ossrs/srs
+/// [1]: https://github.com/ossrs/srs/wiki/v3_EN_RtmpUrlVhost
 #[derive(
-    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+    Clone, Debug, Deref, Display, Eq, Hash, Into, PartialEq, Serialize,
 )]
-pub struct Input {
-    /// Unique ID of this `Input`.
+pub struct SrsVhost(String);
+
+impl SrsVhost {
+    /// Creates a new [`SrsVhost`] if the given value meets its invariants.
+    ///
+    /// The value should be a valid host name. `hls`, `whip` and
+    /// `__defaultVhost__` are reserved, so are not allowed.
+    #[must_use]
+    pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Option<Self> {
+        static REGEX: Lazy<Regex> = Lazy::new(|| {
+            Regex::new("^[a-z0-9]([a-z0-9.-]{0,61}[a-z0-9])?$").unwrap()
+        });
+
+        let val = val.into();
+        (REGEX.is_match(&val) && val != "hls" && val != "whip")
+            .then(|| Self(val.into_owned()))
+    }
+
+    /// Returns this [`SrsVhost`] as a string slice.
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'de> Deserialize<'de> for SrsVhost {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(<Cow<'_, str>>::deserialize(deserializer)?)
+            .ok_or_else(|| D::Error::custom("Not a valid Restream.srsVhost"))
+    }
+}
+
+/// Type of `Restream`'s [SRS] `vhost` to route its live streams through.
+///
+/// It should be a valid host name, except the reserved `hls` one.
+///
+/// [SRS]: https://github.com/ossrs/srs
+#[graphql_scalar]
+impl<S> GraphQLScalar for SrsVhost
+where
+    S: ScalarValue,
+{
+    fn resolve(&self) -> Value {
+        Value::scalar(self.0.as_str().to_owned())
+    }
+
+    fn from_input_value(v: &InputValue) -> Option<Self> {
+        v.as_scalar()
+            .and_then(ScalarValue::as_str)
+            .and_then(Self::new)
+    }
+
+    fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+}
+
+/// Upstream source that a `Restream` receives a live stream from.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Input {
+    /// Unique ID of this `Input`.
     ///
     /// Once assigned, it never changes.
     pub id: InputId,
@@ -773,10 +1863,100 @@ pub struct Input {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub src: Option<InputSrc>,
 
+    /// Rungs of an adaptive bitrate ladder to serve a live stream with via
+    /// [`InputEndpointKind::Hls`] endpoint of this [`Input`].
+    ///
+    /// If empty, then a single rendition is served.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hls_ladder: Vec<HlsRung>,
+
     /// Indicator whether this `Input` is enabled, so is allowed to receive a
     /// live stream from its upstream sources.
     #[serde(default, skip_serializing_if = "is_false")]
     pub enabled: bool,
+
+    /// Moment when any of `InputEndpoint`s of this `Input` has gone
+    /// `Status::Online` the last time.
+    ///
+    /// `null` if this `Input` hasn't received any live stream since the
+    /// server start. Not persisted.
+    #[serde(skip)]
+    pub last_streamed_at: Option<DateTime<Utc>>,
+}
+
+/// Upstream source that a `Restream` receives a live stream from.
+#[graphql_object(
+    description = "Upstream source that a `Restream` receives a live stream \
+                   from."
+)]
+impl Input {
+    /// Unique ID of this `Input`.
+    ///
+    /// Once assigned, it never changes.
+    fn id(&self) -> InputId {
+        self.id
+    }
+
+    /// Key of this `Input` to expose its `InputEndpoint`s with for accepting
+    /// and serving a live stream.
+    fn key(&self) -> &InputKey {
+        &self.key
+    }
+
+    /// Endpoints of this `Input` serving a live stream for `Output`s and
+    /// clients.
+    fn endpoints(&self) -> &[InputEndpoint] {
+        &self.endpoints
+    }
+
+    /// Source to pull a live stream from.
+    ///
+    /// If specified, then this `Input` will pull a live stream from it (pull
+    /// kind), otherwise this `Input` will await a live stream to be pushed
+    /// (push kind).
+    fn src(&self) -> Option<&InputSrc> {
+        self.src.as_ref()
+    }
+
+    /// Kind of this `Input`, whether it pulls a live stream from its `src` or
+    /// awaits it to be pushed.
+    ///
+    /// For an `Input` with a `FailoverInputSrc` the kind of its primary (the
+    /// first one) `Input` is reported.
+    fn kind(&self) -> InputKind {
+        match &self.src {
+            None => InputKind::Push,
+            Some(InputSrc::Remote(_)) => InputKind::Pull,
+            Some(InputSrc::Failover(s)) => {
+                s.inputs.first().map_or(InputKind::Push, Self::kind)
+            }
+        }
+    }
+
+    /// Indicator whether this `Input` is enabled, so is allowed to receive a
+    /// live stream from its upstream sources.
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Moment when any of `InputEndpoint`s of this `Input` has gone
+    /// `Status::Online` the last time.
+    ///
+    /// `null` if this `Input` hasn't received any live stream since the
+    /// server start.
+    fn last_streamed_at(&self) -> Option<DateTime<Utc>> {
+        self.last_streamed_at
+    }
+}
+
+/// Kind of an `Input`, describing how it receives a live stream.
+#[derive(Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq)]
+pub enum InputKind {
+    /// `Input` awaits a live stream to be pushed into it.
+    Push,
+
+    /// `Input` pulls a live stream from its `InputSrc`.
+    Pull,
 }
 
 impl Input {
@@ -792,11 +1972,20 @@ impl Input {
                 .map(InputEndpoint::new)
                 .collect(),
             src: spec.src.map(InputSrc::new),
+            hls_ladder: spec.hls_ladder,
             enabled: spec.enabled,
+            last_streamed_at: None,
         }
     }
 
     /// Applies the given [`spec::v1::Input`] to this [`Input`].
+    ///
+    /// Publishers and players orphaned by this change (if [`Input::key`] has
+    /// changed, for example) still stream to the old endpoints, so are
+    /// postponed to be kicked off from [SRS] (see
+    /// [`InputEndpoint::postpone_kickoff()`]).
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
     pub fn apply(&mut self, new: spec::v1::Input) {
         if self.key != new.key
             || !new.enabled
@@ -806,12 +1995,12 @@ impl Input {
             // SRS endpoints have changed, disabled, or push/pull type has been
             // switched, so we should kick the publisher and all the players.
             for e in &mut self.endpoints {
-                e.srs_publisher_id = None;
-                e.srs_player_ids.clear();
+                e.postpone_kickoff(&self.key);
             }
         }
 
         self.key = new.key;
+        self.hls_ladder = new.hls_ladder;
         // Temporary omit changing existing `enabled` value to avoid unexpected
         // breakages of ongoing re-streams.
         //self.enabled = new.enabled;
@@ -839,6 +2028,13 @@ impl Input {
             (None, Some(new)) => self.src = Some(InputSrc::new(new)),
             _ => self.src = None,
         }
+
+        if self.enabled {
+            // Cancel the kickoff if the previous `key` has been returned.
+            for e in &mut self.endpoints {
+                let _ = e.revive_kickoff(&self.key);
+            }
+        }
     }
 
     /// Exports this [`Input`] as a [`spec::v1::Input`].
@@ -852,6 +2048,7 @@ impl Input {
                 .map(InputEndpoint::export)
                 .collect(),
             src: self.src.as_ref().map(InputSrc::export),
+            hls_ladder: self.hls_ladder.clone(),
             enabled: self.enabled,
         }
     }
@@ -865,6 +2062,10 @@ impl Input {
 
         self.enabled = true;
 
+        for e in &mut self.endpoints {
+            let _ = e.revive_kickoff(&self.key);
+        }
+
         if let Some(InputSrc::Failover(s)) = self.src.as_mut() {
             for i in &mut s.inputs {
                 changed |= i.enable();
@@ -884,8 +2085,8 @@ impl Input {
         self.enabled = false;
 
         for e in &mut self.endpoints {
-            e.srs_publisher_id = None;
-            e.srs_player_ids.clear();
+            // Kicking clients right away worsens flapping ingests.
+            e.postpone_kickoff(&self.key);
             // Do not rely only on SRS to set status, as it sporadically races.
             e.status = Status::Offline;
         }
@@ -913,6 +2114,37 @@ impl Input {
         }
     }
 
+    /// Indicates whether this [`Input`] or any of its
+    /// [`FailoverInputSrc::inputs`] has the given `key`.
+    #[must_use]
+    pub fn has_key(&self, key: &str) -> bool {
+        if self.key == *key {
+            return true;
+        }
+        if let Some(InputSrc::Failover(s)) = &self.src {
+            s.inputs.iter().any(|i| i.has_key(key))
+        } else {
+            false
+        }
+    }
+
+    /// Lookups for an [`InputEndpoint`] with the given `id` inside this
+    /// [`Input`] or its [`FailoverInputSrc::inputs`].
+    #[must_use]
+    pub fn find_endpoint_mut(
+        &mut self,
+        id: EndpointId,
+    ) -> Option<&mut InputEndpoint> {
+        if let Some(e) = self.endpoints.iter_mut().find(|e| e.id == id) {
+            return Some(e);
+        }
+        if let Some(InputSrc::Failover(s)) = &mut self.src {
+            s.inputs.iter_mut().find_map(|i| i.find_endpoint_mut(id))
+        } else {
+            None
+        }
+    }
+
     /// Indicates whether this [`Input`] is ready to serve a live stream for
     /// [`Output`]s.
     #[must_use]
@@ -969,6 +2201,48 @@ pub struct InputEndpoint {
     #[graphql(skip)]
     #[serde(skip)]
     pub srs_player_ids: HashSet<srs::ClientId>,
+
+    /// [SRS] clients of this [`InputEndpoint`] awaiting to be kicked off once
+    /// the grace period passes, unless its [`Input`] comes back.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[graphql(skip)]
+    #[serde(skip)]
+    pub pending_kickoff: Option<PendingKickoff>,
+
+    /// Settings of transcoding a live stream for this [`InputEndpoint`].
+    ///
+    /// Has effect only for [`InputEndpointKind::Hls`]. If [`None`], then the
+    /// default [`HlsEncoding`] is used.
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hls_encoding: Option<HlsEncoding>,
+
+    /// Information about the live stream served by this `InputEndpoint`,
+    /// probed once it goes `Status::Online`.
+    ///
+    /// Is provided for `InputEndpointKind::Rtmp` only.
+    #[serde(skip)]
+    pub stream_info: Option<StreamInfo>,
+
+    /// [PID] of the [FFmpeg] process serving this `InputEndpoint` at the
+    /// moment (pulling a live stream into it, or transcoding it).
+    ///
+    /// `null` if no such process is running.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [PID]: https://en.wikipedia.org/wiki/Process_identifier
+    #[serde(skip)]
+    pub pid: Option<i32>,
+
+    /// Moment when the [FFmpeg] process serving this `InputEndpoint` has been
+    /// started.
+    ///
+    /// `null` if no such process is running.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(skip)]
+    pub started_at: Option<DateTime<Utc>>,
 }
 
 impl InputEndpoint {
@@ -983,6 +2257,11 @@ impl InputEndpoint {
             status: Status::Offline,
             srs_publisher_id: None,
             srs_player_ids: HashSet::new(),
+            pending_kickoff: None,
+            hls_encoding: spec.hls_encoding,
+            stream_info: None,
+            pid: None,
+            started_at: None,
         }
     }
 
@@ -990,13 +2269,17 @@ impl InputEndpoint {
     #[inline]
     pub fn apply(&mut self, new: spec::v1::InputEndpoint) {
         self.kind = new.kind;
+        self.hls_encoding = new.hls_encoding;
     }
 
     /// Exports this [`InputEndpoint`] as a [`spec::v1::InputEndpoint`].
     #[inline]
     #[must_use]
     pub fn export(&self) -> spec::v1::InputEndpoint {
-        spec::v1::InputEndpoint { kind: self.kind }
+        spec::v1::InputEndpoint {
+            kind: self.kind,
+            hls_encoding: self.hls_encoding,
+        }
     }
 
     /// Indicates whether this [`InputEndpoint`] is an
@@ -1006,8 +2289,150 @@ impl InputEndpoint {
     pub fn is_rtmp(&self) -> bool {
         matches!(self.kind, InputEndpointKind::Rtmp)
     }
+
+    /// Moves the [SRS] publisher and players of this [`InputEndpoint`],
+    /// streaming via the given [`InputKey`], to its
+    /// [`InputEndpoint::pending_kickoff`], so they're not kicked off right
+    /// away.
+    ///
+    /// Clients already awaiting a kickoff for another [`InputKey`] are kicked
+    /// off immediately.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub fn postpone_kickoff(&mut self, key: &InputKey) {
+        let publisher_id = self.srs_publisher_id.take();
+        let player_ids = mem::take(&mut self.srs_player_ids);
+        if publisher_id.is_none() && player_ids.is_empty() {
+            return;
+        }
+
+        match self.pending_kickoff.as_mut() {
+            Some(p) if p.key == *key => {
+                if publisher_id.is_some() {
+                    p.publisher_id = publisher_id;
+                }
+                p.player_ids.extend(player_ids);
+            }
+            _ => {
+                self.pending_kickoff = Some(PendingKickoff {
+                    key: key.clone(),
+                    publisher_id,
+                    player_ids,
+                    since: Instant::now(),
+                });
+            }
+        }
+    }
+
+    /// Returns the [SRS] clients of the [`InputEndpoint::pending_kickoff`]
+    /// back to this [`InputEndpoint`], if they have been streaming via the
+    /// given [`InputKey`], so cancels their kickoff.
+    ///
+    /// Returns `true` if any clients have been returned.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub fn revive_kickoff(&mut self, key: &InputKey) -> bool {
+        if self
+            .pending_kickoff
+            .as_ref()
+            .map_or(true, |p| p.key != *key)
+        {
+            return false;
+        }
+        let pending = self.pending_kickoff.take().unwrap();
+
+        if self.srs_publisher_id.is_none() && pending.publisher_id.is_some() {
+            self.srs_publisher_id = pending.publisher_id;
+            // SRS won't notify about the publisher again, as it has never
+            // stopped publishing.
+            self.status = Status::Online;
+        }
+        self.srs_player_ids.extend(pending.player_ids);
+        true
+    }
+
+    /// Removes the [SRS] client with the given `id` from the
+    /// [`InputEndpoint::pending_kickoff`], as it has gone by itself.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub fn forget_kickoff(&mut self, id: u32) {
+        if let Some(p) = self.pending_kickoff.as_mut() {
+            if p.publisher_id.as_ref().map(|c| **c) == Some(id) {
+                p.publisher_id = None;
+            }
+            let _ = p.player_ids.remove(&id);
+            if p.publisher_id.is_none() && p.player_ids.is_empty() {
+                self.pending_kickoff = None;
+            }
+        }
+    }
+
+    /// Takes the [`InputEndpoint::pending_kickoff`] postponed at the given
+    /// `since` moment, so its [SRS] clients can be kicked off.
+    ///
+    /// Returns [`None`] if the [`PendingKickoff`] has been cancelled (or
+    /// replaced with a newer one) meanwhile.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    #[must_use]
+    pub fn expire_kickoff(&mut self, since: Instant) -> Option<PendingKickoff> {
+        if self.pending_kickoff.as_ref()?.since != since {
+            return None;
+        }
+        self.pending_kickoff.take()
+    }
+}
+
+/// [SRS] clients of an [`InputEndpoint`] awaiting to be kicked off.
+///
+/// Being [`Drop`]ped, kicks off all its clients.
+///
+/// [SRS]: https://github.com/ossrs/srs
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingKickoff {
+    /// [`InputKey`] the clients have been streaming via.
+    pub key: InputKey,
+
+    /// ID of [SRS] client who has been publishing a live stream.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub publisher_id: Option<srs::ClientId>,
+
+    /// IDs of [SRS] clients who have been playing a live stream.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub player_ids: HashSet<srs::ClientId>,
+
+    /// Moment when the kickoff has been postponed.
+    pub since: Instant,
+}
+
+/// Information about a live stream served by an `InputEndpoint`, as probed
+/// by [ffprobe].
+///
+/// [ffprobe]: https://ffmpeg.org/ffprobe.html
+#[derive(Clone, Debug, Default, GraphQLObject, PartialEq)]
+pub struct StreamInfo {
+    /// Width (in pixels) of the video.
+    pub width: Option<i32>,
+
+    /// Height (in pixels) of the video.
+    pub height: Option<i32>,
+
+    /// Frame rate of the video.
+    pub fps: Option<f64>,
+
+    /// Codec (in FFmpeg notation) of the video.
+    pub video_codec: Option<String>,
+
+    /// Codec (in FFmpeg notation) of the audio.
+    pub audio_codec: Option<String>,
 }
 
+// `StreamInfo::fps` is never `NaN`, as is parsed from a rational number with a
+// non-zero denominator.
+impl Eq for StreamInfo {}
+
 /// Possible kinds of an `InputEndpoint`.
 #[derive(
     Clone,
@@ -1039,26 +2464,75 @@ pub enum InputEndpointKind {
     /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
     #[display(fmt = "HLS")]
     Hls,
+
+    /// [WHIP] endpoint.
+    ///
+    /// Accepts a [WebRTC] live stream published from a browser, which is
+    /// transcoded into the [`InputEndpointKind::Rtmp`] endpoint of the same
+    /// `Input`.
+    ///
+    /// [WebRTC]: https://en.wikipedia.org/wiki/WebRTC
+    /// [WHIP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whip
+    #[display(fmt = "WHIP")]
+    Whip,
 }
 
 impl InputEndpointKind {
     /// Returns RTMP URL on a local [SRS] server of this [`InputEndpointKind`]
     /// for the given `restream` and `input`.
     ///
+    /// The given [`SrsVhost`] is used for [`InputEndpointKind::Rtmp`] only, as
+    /// [`InputEndpointKind::Hls`] and [`InputEndpointKind::Whip`] are always
+    /// served via their own `vhost`s.
+    ///
     /// [SRS]: https://github.com/ossrs/srs
     #[must_use]
-    pub fn rtmp_url(self, restream: &RestreamKey, input: &InputKey) -> Url {
+    pub fn rtmp_url(
+        self,
+        restream: &RestreamKey,
+        input: &InputKey,
+        vhost: Option<&SrsVhost>,
+    ) -> Url {
+        let vhost = match self {
+            Self::Rtmp => vhost.map(SrsVhost::as_str),
+            Self::Hls => Some("hls"),
+            Self::Whip => Some("whip"),
+        };
         Url::parse(&format!(
             "rtmp://127.0.0.1:1935/{}{}/{}",
             restream,
-            match self {
-                Self::Rtmp => "",
-                Self::Hls => "?vhost=hls",
-            },
+            vhost.map(|v| format!("?vhost={}", v)).unwrap_or_default(),
             input,
         ))
         .unwrap()
     }
+
+    /// Returns [WHIP] ingest URL on a local [SRS] server of this
+    /// [`InputEndpointKind`] for the given `restream` and `input`.
+    ///
+    /// Returns [`None`] if this [`InputEndpointKind`] is not an
+    /// [`InputEndpointKind::Whip`].
+    ///
+    /// The returned URL points to the local [SRS] HTTP API, so is expected to
+    /// be proxied for browsers.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [WHIP]: https://datatracker.ietf.org/doc/draft-ietf-wish-whip
+    #[must_use]
+    pub fn whip_url(
+        self,
+        restream: &RestreamKey,
+        input: &InputKey,
+    ) -> Option<Url> {
+        matches!(self, Self::Whip).then(|| {
+            Url::parse(&format!(
+                "http://127.0.0.1:8002/rtc/v1/whip/\
+                 ?app={}&stream={}&vhost=whip",
+                restream, input,
+            ))
+            .unwrap()
+        })
+    }
 }
 
 /// ID of an `InputEndpoint`.
@@ -1105,8 +2579,11 @@ impl InputSrc {
     #[must_use]
     pub fn new(spec: spec::v1::InputSrc) -> Self {
         match spec {
-            spec::v1::InputSrc::RemoteUrl(url) => {
-                Self::Remote(RemoteInputSrc { url })
+            spec::v1::InputSrc::RemoteUrl(remote) => {
+                Self::Remote(RemoteInputSrc {
+                    url: remote.url,
+                    fallback_urls: remote.fallback_urls,
+                })
             }
             spec::v1::InputSrc::FailoverInputs(inputs) => {
                 Self::Failover(FailoverInputSrc {
@@ -1121,8 +2598,9 @@ impl InputSrc {
     /// Replaces all the [`FailoverInputSrc::inputs`] with new ones.
     pub fn apply(&mut self, new: spec::v1::InputSrc) {
         match (self, new) {
-            (Self::Remote(old), spec::v1::InputSrc::RemoteUrl(new_url)) => {
-                old.url = new_url
+            (Self::Remote(old), spec::v1::InputSrc::RemoteUrl(new)) => {
+                old.url = new.url;
+                old.fallback_urls = new.fallback_urls;
             }
             (Self::Failover(src), spec::v1::InputSrc::FailoverInputs(news)) => {
                 let mut olds = mem::replace(
@@ -1152,7 +2630,12 @@ impl InputSrc {
     #[must_use]
     pub fn export(&self) -> spec::v1::InputSrc {
         match self {
-            Self::Remote(i) => spec::v1::InputSrc::RemoteUrl(i.url.clone()),
+            Self::Remote(i) => {
+                spec::v1::InputSrc::RemoteUrl(spec::v1::RemoteSrc {
+                    url: i.url.clone(),
+                    fallback_urls: i.fallback_urls.clone(),
+                })
+            }
             Self::Failover(src) => spec::v1::InputSrc::FailoverInputs(
                 src.inputs.iter().map(Input::export).collect(),
             ),
@@ -1167,6 +2650,13 @@ impl InputSrc {
 pub struct RemoteInputSrc {
     /// URL of this `RemoteInputSrc`.
     pub url: InputSrcUrl,
+
+    /// Fallback URLs of this `RemoteInputSrc`.
+    ///
+    /// Once pulling a live stream from the `RemoteInputSrc.url` fails, the
+    /// next URL is tried, and so on, in a round-robin manner.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_urls: Vec<InputSrcUrl>,
 }
 
 /// Failover source of multiple `Input`s to pull a live stream by an `Input`
@@ -1215,15 +2705,23 @@ impl InputId {
 pub struct InputKey(String);
 
 impl InputKey {
-    /// Creates a new [`InputKey`] if the given value meets its invariants.
+    /// Creates a new [`InputKey`] if the given value meets its invariants,
+    /// considering the configured [`key_max_len()`].
+    #[inline]
     #[must_use]
     pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Option<Self> {
-        static REGEX: Lazy<Regex> =
-            Lazy::new(|| Regex::new("^[a-z0-9_-]{1,20}$").unwrap());
+        Self::new_with_len(val, key_max_len())
+    }
 
+    /// Creates a new [`InputKey`] if the given value meets its invariants,
+    /// being no longer than the given `max_len`.
+    #[must_use]
+    pub fn new_with_len<'s, S: Into<Cow<'s, str>>>(
+        val: S,
+        max_len: usize,
+    ) -> Option<Self> {
         let val = val.into();
-        (!val.is_empty() && REGEX.is_match(&val))
-            .then(|| Self(val.into_owned()))
+        is_valid_key(&val, max_len).then(|| Self(val.into_owned()))
     }
 }
 
@@ -1240,7 +2738,8 @@ impl<'de> Deserialize<'de> for InputKey {
 
 /// Type of `Input`'s `key` used to form its endpoint URL.
 ///
-/// It should meet `[a-z0-9_-]{1,20}` format.
+/// It should meet `[a-z0-9_-][a-z0-9_.-]*` format, and be no longer than
+/// the configured maximum length (20 characters by default).
 #[graphql_scalar]
 impl<S> GraphQLScalar for InputKey
 where
@@ -1394,42 +2893,235 @@ pub struct Output {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub mixins: Vec<Mixin>,
 
-    /// Indicator whether this `Output` is enabled, so is allowed to perform a
-    /// live stream re-streaming to its downstream destination.
-    #[serde(default, skip_serializing_if = "is_false")]
-    pub enabled: bool,
+    /// `TranscodingProfile` to transcode a live stream with before
+    /// re-streaming it to the downstream destination.
+    ///
+    /// If `null`, then a live stream is re-streamed "as is" (just copies
+    /// bytes). Has no effect when there are `Output.mixins`, or the
+    /// destination is not an [RTMP] or [SRT] one.
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcoding: Option<TranscodingProfile>,
 
-    /// `Status` of this `Output` indicating whether it actually re-streams a
-    /// live stream to its downstream destination.
-    #[serde(skip)]
-    pub status: Status,
-}
+    /// Interval between keyframes to transcode a live stream of this `Output`
+    /// with.
+    ///
+    /// Has effect only when this `Output` is transcoded. If `null`, then the
+    /// default `KeyframeInterval` is used, aligned with [HLS] segments.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyframe_interval: Option<KeyframeInterval>,
 
-impl Output {
-    /// Creates a new [`Output`] out of the given [`spec::v1::Output`].
-    #[inline]
-    #[must_use]
-    pub fn new(spec: spec::v1::Output) -> Self {
-        Self {
-            id: OutputId::random(),
-            dst: spec.dst,
-            label: spec.label,
-            volume: spec.volume,
-            mixins: spec.mixins.into_iter().map(Mixin::new).collect(),
-            enabled: spec.enabled,
-            status: Status::Offline,
-        }
-    }
+    /// `Delay` to re-stream a live stream of this `Output` with, so it can be
+    /// synchronized with some other partner live stream.
+    ///
+    /// Has no effect when there are `Output.mixins` (`Mixin.delay` should be
+    /// used instead). Offsets timestamps of the live stream only, without
+    /// holding it back in real time.
+    #[serde(default, skip_serializing_if = "Delay::is_zero")]
+    pub delay: Delay,
 
-    /// Applies the given [`spec::v1::Output`] to this [`Output`].
+    /// Policy of how long a mixed live stream of this `Output` lasts, relative
+    /// to its original live stream and `Output.mixins`.
     ///
-    /// If `replace` is `true` then all the [`Output::mixins`] will be replaced
+    /// Has effect only when there are `Output.mixins`.
+    #[serde(default, skip_serializing_if = "MixDuration::is_longest")]
+    pub mix_duration: MixDuration,
+
+    /// Latency (in milliseconds) of an [SRT] connection to the downstream
+    /// destination.
+    ///
+    /// Has effect only for [SRT] destinations.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt_latency_ms: Option<u32>,
+
+    /// Duration (in seconds) of segments to split a DVR recording of this
+    /// `Output` into, so timestamped files are written.
+    ///
+    /// Has effect only for `file:///` destinations. If not specified, then a
+    /// single file is recorded.
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dvr_segment_secs: Option<u32>,
+
+    /// Passphrase to encrypt an [SRT] connection to the downstream destination
+    /// with.
+    ///
+    /// Has effect only for [SRT] destinations.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[graphql(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub srt_passphrase: Option<SrtPassphrase>,
+
+    /// Additional [FFmpeg] arguments to be passed right before this `Output`'s
+    /// destination URL.
+    ///
+    /// These are passed "as is" (without any shell interpretation), so allow
+    /// to specify [FFmpeg] options not exposed otherwise.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+
+    /// Indicator whether [FFmpeg] should reconnect to a remote HTTP source
+    /// this `Output` pulls a live stream from, on its failures.
+    ///
+    /// Has no effect for RTMP sources.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub reconnect: bool,
+
+    /// Level of [FFmpeg] logs (`-loglevel`) to re-stream a live stream to this
+    /// `Output` with.
+    ///
+    /// If not specified, then the default one (configured via CLI) is used.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loglevel: Option<FfmpegLogLevel>,
+
+    /// [FFmpeg] container format (`-f`) to re-stream a live stream to this
+    /// `Output` in.
+    ///
+    /// If not specified, then the one is chosen by the `Output`'s destination
+    /// URL.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<OutputFormat>,
+
+    /// Indicator whether this `Output` feeds its sibling `Output`s, so they
+    /// pull a live stream from its `dst` on the local SRS server rather than
+    /// from the `Input`.
+    ///
+    /// Allows to transcode a messy `Input` once into a clean local stream,
+    /// rather than transcoding it for each `Output` separately. Only the first
+    /// enabled such `Output` of a `Restream` feeds its siblings, and its `dst`
+    /// should be `rtmp://127.0.0.1:1935/<restream-key>/<stream>`, where
+    /// `<stream>` is not a key of any `Input`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub feeds_siblings: bool,
+
+    /// Indicator whether this `Output` is enabled, so is allowed to perform a
+    /// live stream re-streaming to its downstream destination.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub enabled: bool,
+
+    /// `Status` of this `Output` indicating whether it actually re-streams a
+    /// live stream to its downstream destination.
+    #[serde(skip)]
+    pub status: Status,
+
+    /// Known reason of why this `Output` cannot reach `Status::Online`.
+    ///
+    /// `null` if everything is fine, or the reason is unknown.
+    #[serde(skip)]
+    pub status_reason: Option<StatusReason>,
+
+    /// Current bitrate (in kilobits per second) of the live stream being
+    /// re-streamed to this `Output`, as reported by [FFmpeg].
+    ///
+    /// `null` if unknown yet, or this `Output` is `Status::Offline`.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(skip)]
+    pub current_bitrate_kbps: Option<i32>,
+
+    /// Current frame rate (rounded to an integer) of the live stream being
+    /// re-streamed to this `Output`, as reported by [FFmpeg].
+    ///
+    /// `null` if unknown yet, or this `Output` is `Status::Offline`.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(skip)]
+    pub current_fps: Option<i32>,
+
+    /// [PID] of the [FFmpeg] process re-streaming to this `Output` at the
+    /// moment.
+    ///
+    /// `null` if no such process is running.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    /// [PID]: https://en.wikipedia.org/wiki/Process_identifier
+    #[serde(skip)]
+    pub pid: Option<i32>,
+
+    /// Moment when the [FFmpeg] process re-streaming to this `Output` has been
+    /// started.
+    ///
+    /// `null` if no such process is running.
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[serde(skip)]
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+impl Output {
+    /// Creates a new [`Output`] out of the given [`spec::v1::Output`].
+    #[inline]
+    #[must_use]
+    pub fn new(spec: spec::v1::Output) -> Self {
+        Self {
+            id: OutputId::random(),
+            dst: spec.dst,
+            label: spec.label,
+            volume: spec.volume,
+            mixins: spec.mixins.into_iter().map(Mixin::new).collect(),
+            transcoding: spec.transcoding,
+            keyframe_interval: spec.keyframe_interval,
+            delay: spec.delay,
+            mix_duration: spec.mix_duration,
+            srt_latency_ms: spec.srt_latency_ms,
+            dvr_segment_secs: spec.dvr_segment_secs,
+            srt_passphrase: spec.srt_passphrase,
+            extra_args: spec.extra_args,
+            reconnect: spec.reconnect,
+            loglevel: spec.loglevel,
+            format: spec.format,
+            feeds_siblings: spec.feeds_siblings,
+            enabled: spec.enabled,
+            status: Status::Offline,
+            status_reason: None,
+            current_bitrate_kbps: None,
+            current_fps: None,
+            pid: None,
+            started_at: None,
+        }
+    }
+
+    /// Applies the given [`spec::v1::Output`] to this [`Output`].
+    ///
+    /// If `replace` is `true` then all the [`Output::mixins`] will be replaced
     /// with new ones, otherwise new ones will be merged with already existing
     /// [`Output::mixins`].
+    ///
+    /// [`Output::enabled`] is not applied, as [`State::enable_output()`] and
+    /// [`State::disable_output()`] toggles are the source of truth for it, so
+    /// the [`Output::export()`] always reflects the live value.
     pub fn apply(&mut self, new: spec::v1::Output, replace: bool) {
         self.dst = new.dst;
         self.label = new.label;
         self.volume = new.volume;
+        self.transcoding = new.transcoding;
+        self.keyframe_interval = new.keyframe_interval;
+        self.delay = new.delay;
+        self.mix_duration = new.mix_duration;
+        self.srt_latency_ms = new.srt_latency_ms;
+        self.dvr_segment_secs = new.dvr_segment_secs;
+        self.srt_passphrase = new.srt_passphrase;
+        self.extra_args = new.extra_args;
+        self.reconnect = new.reconnect;
+        self.loglevel = new.loglevel;
+        self.format = new.format;
+        self.feeds_siblings = new.feeds_siblings;
         // Temporary omit changing existing `enabled` value to avoid unexpected
         // breakages of ongoing re-streams.
         //self.enabled = new.enabled;
@@ -1464,6 +3156,46 @@ impl Output {
         }
     }
 
+    /// Returns the [`TranscodingProfile`] this [`Output`] is actually
+    /// transcoded with, considering that [`Output::transcoding`] has effect
+    /// only for [RTMP] and [SRT] destinations without [`Output::mixins`].
+    ///
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[must_use]
+    pub fn effective_transcoding(&self) -> Option<TranscodingProfile> {
+        self.transcoding.filter(|_| {
+            self.mixins.is_empty()
+                && matches!(self.dst.scheme(), "rtmp" | "rtmps" | "srt")
+        })
+    }
+
+    /// Tunes a [`Volume`] rate of this [`Output`], or of its [`Mixin`] if the
+    /// `mixin_id` is specified.
+    ///
+    /// Returns `true` if a [`Volume`] rate has been changed, or `false` if it
+    /// has the same value already.
+    ///
+    /// Returns [`None`] if no such [`Mixin`] exists.
+    pub fn tune_volume(
+        &mut self,
+        mixin_id: Option<MixinId>,
+        volume: Volume,
+    ) -> Option<bool> {
+        let curr_volume = if let Some(id) = mixin_id {
+            &mut self.mixins.iter_mut().find(|m| m.id == id)?.volume
+        } else {
+            &mut self.volume
+        };
+
+        if *curr_volume == volume {
+            return Some(false);
+        }
+
+        *curr_volume = volume;
+        Some(true)
+    }
+
     /// Exports this [`Output`] as a [`spec::v1::Output`].
     #[inline]
     #[must_use]
@@ -1473,365 +3205,496 @@ impl Output {
             label: self.label.clone(),
             volume: self.volume,
             mixins: self.mixins.iter().map(Mixin::export).collect(),
+            transcoding: self.transcoding,
+            keyframe_interval: self.keyframe_interval,
+            delay: self.delay,
+            mix_duration: self.mix_duration,
+            srt_latency_ms: self.srt_latency_ms,
+            dvr_segment_secs: self.dvr_segment_secs,
+            srt_passphrase: self.srt_passphrase.clone(),
+            extra_args: self.extra_args.clone(),
+            reconnect: self.reconnect,
+            loglevel: self.loglevel,
+            format: self.format.clone(),
+            feeds_siblings: self.feeds_siblings,
             enabled: self.enabled,
         }
     }
-}
-
-/// ID of an `Output`.
-#[derive(
-    Clone,
-    Copy,
-    Debug,
-    Deserialize,
-    Display,
-    Eq,
-    From,
-    GraphQLScalarValue,
-    Into,
-    PartialEq,
-    Serialize,
-)]
-pub struct OutputId(Uuid);
 
-impl OutputId {
-    /// Generates a new random [`OutputId`].
-    #[inline]
-    #[must_use]
-    pub fn random() -> Self {
-        Self(Uuid::new_v4())
+    /// Validates the given [`Output::extra_args`] to be used along with the
+    /// given [`OutputDstUrl`].
+    ///
+    /// # Errors
+    ///
+    /// - If any of `args` is empty or blank;
+    /// - If any of `args` contains the `dst` URL itself, as it's always
+    ///   provided by the [`Output`] separately.
+    pub fn validate_extra_args(
+        dst: &OutputDstUrl,
+        args: &[String],
+    ) -> Result<(), String> {
+        for arg in args {
+            if arg.trim().is_empty() {
+                return Err(
+                    "Output.extraArgs cannot contain empty values".to_owned()
+                );
+            }
+            if arg.contains('\0') {
+                return Err(format!(
+                    "Output.extraArgs cannot contain NUL characters: {:?}",
+                    arg,
+                ));
+            }
+            if arg.contains(dst.as_str()) {
+                return Err(format!(
+                    "Output.extraArgs cannot contain Output.dst URL: {}",
+                    arg,
+                ));
+            }
+        }
+        Ok(())
     }
-}
-
-/// [`Url`] of an [`Output::dst`].
-///
-/// Only the following URLs are allowed at the moment:
-/// - [RTMP] URL (starting with `rtmp://` or `rtmps://` scheme and having a
-///   host);
-/// - [SRT] URL (starting with `srt://` scheme and having a host);
-/// - [Icecast] URL (starting with `icecast://` scheme and having a host);
-/// - [FLV] file URL (starting with `file:///` scheme, without host and
-///   subdirectories, and with `.flv` extension in its path).
-///
-/// [FLV]: https://en.wikipedia.org/wiki/Flash_Video
-/// [Icecast]: https://icecast.org
-/// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
-/// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
-#[derive(
-    Clone, Debug, Deref, Display, Eq, Hash, Into, PartialEq, Serialize,
-)]
-pub struct OutputDstUrl(Url);
 
-impl OutputDstUrl {
-    /// Creates a new [`OutputDstUrl`] if the given [`Url`] is suitable for
-    /// that.
+    /// Validates the given [`spec::v1::Output`] before creating or applying an
+    /// [`Output`] out of it.
     ///
     /// # Errors
     ///
-    /// Returns the given [`Url`] back if it doesn't represent a valid
-    /// [`OutputDstUrl`].
-    #[inline]
-    pub fn new(url: Url) -> Result<Self, Url> {
-        if Self::validate(&url) {
-            Ok(Self(url))
-        } else {
-            Err(url)
+    /// - If its mixins are invalid (see [`Output::validate_mixins()`]);
+    /// - If it [feeds siblings][1], but its `dst` is not on the local [SRS]
+    ///   server (see [`OutputDstUrl::local_srs_stream()`]).
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    /// [1]: spec::v1::Output::feeds_siblings
+    pub fn validate_spec(spec: &spec::v1::Output) -> anyhow::Result<()> {
+        Self::validate_mixins(spec.mixins.iter().map(|m| &m.src))
+            .map_err(|e| anyhow!("{}", e))?;
+        if spec.feeds_siblings && spec.dst.local_srs_stream().is_none() {
+            return Err(anyhow!(
+                "Output.dst '{}' should be on the local SRS server to feed \
+                 sibling Outputs",
+                spec.dst,
+            ));
         }
+        Ok(())
     }
 
-    /// Validates the given [`Url`] to represent a valid [`OutputDstUrl`].
-    #[must_use]
-    pub fn validate(url: &Url) -> bool {
-        match url.scheme() {
-            "icecast" | "rtmp" | "rtmps" | "srt" => url.has_host(),
-            "file" => {
-                let path = Path::new(url.path());
-                !url.has_host()
-                    && path.is_absolute()
-                    && path.extension() == Some("flv".as_ref())
-                    && path.parent() == Some("/".as_ref())
-                    && !url.path().contains("/../")
+    /// Validates the given [`Output::dst`] not to loop back into any of the
+    /// given [`Restream`]s, as re-streaming into a local [SRS] ingest of its
+    /// own [`Input`] pegs CPU with an endless feedback loop.
+    ///
+    /// # Errors
+    ///
+    /// If the `dst` points to an existing [`Input`] of the local [SRS] server.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
+    pub fn validate_dst<'a, I>(
+        dst: &OutputDstUrl,
+        restreams: I,
+    ) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = &'a Restream>,
+    {
+        if let Some((app, stream)) = dst.local_srs_stream() {
+            if restreams
+                .into_iter()
+                .any(|r| r.key == *app && r.input.has_key(stream))
+            {
+                return Err(anyhow!(
+                    "Output.dst '{}' loops back into the local SRS server",
+                    dst,
+                ));
             }
-            _ => false,
         }
+        Ok(())
     }
-}
 
-impl<'de> Deserialize<'de> for OutputDstUrl {
-    #[inline]
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    /// Validates the given sources of [`Output::mixins`].
+    ///
+    /// # Errors
+    ///
+    /// - If any of `srcs` is duplicated;
+    /// - If `srcs` contain more than one [TeamSpeak] URL.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub fn validate_mixins<'a, I>(srcs: I) -> Result<(), InvalidMixins>
     where
-        D: Deserializer<'de>,
+        I: IntoIterator<Item = &'a MixinSrcUrl>,
     {
-        Self::new(Url::deserialize(deserializer)?).map_err(|url| {
-            D::Error::custom(format!("Not a valid Output.src URL: {}", url))
-        })
+        let mut unique = HashSet::new();
+        let mut has_teamspeak = false;
+        for src in srcs {
+            if !unique.insert(src) {
+                return Err(InvalidMixins::Duplicate(src.clone()));
+            }
+            if src.scheme() == "ts" {
+                if has_teamspeak {
+                    return Err(InvalidMixins::TooMuchTeamspeak);
+                }
+                has_teamspeak = true;
+            }
+        }
+        Ok(())
     }
 }
 
-/// Type of an `Output.dst` URL.
+/// Reason of [`Output::mixins`] being invalid.
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum InvalidMixins {
+    /// The same [`MixinSrcUrl`] is used by several [`Mixin`]s.
+    #[display(fmt = "Duplicate Output.mixin.src: {}", _0)]
+    Duplicate(MixinSrcUrl),
+
+    /// More than one [TeamSpeak] URL is used.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[display(fmt = "Only one TeamSpeak URL is allowed")]
+    TooMuchTeamspeak,
+}
+
+/// Passphrase to encrypt an [SRT] connection with.
 ///
-/// Only the following URLs are allowed at the moment:
-/// - [RTMP] URL (starting with `rtmp://` or `rtmps://` scheme and having a
-///   host);
-/// - [SRT] URL (starting with `srt://` scheme and having a host);
-/// - [Icecast] URL (starting with `icecast://` scheme and having a host);
-/// - [FLV] file URL (starting with `file:///` scheme, without host and
-///   subdirectories, and with `.flv` extension in its path).
+/// Its [`Debug`] and [`Display`] representations are redacted, so it never
+/// leaks into logs.
 ///
-/// [FLV]: https://en.wikipedia.org/wiki/Flash_Video
-/// [Icecast]: https://icecast.org
-/// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+/// [`Display`]: std::fmt::Display
 /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
-#[graphql_scalar]
-impl<S> GraphQLScalar for OutputDstUrl
-where
-    S: ScalarValue,
-{
-    fn resolve(&self) -> Value {
-        Value::scalar(self.0.as_str().to_owned())
+#[derive(Clone, Eq, PartialEq, Serialize)]
+pub struct SrtPassphrase(String);
+
+impl SrtPassphrase {
+    /// Creates a new [`SrtPassphrase`] if the given value meets its
+    /// invariants.
+    ///
+    /// [SRT] requires passphrases to be from 10 to 79 characters long.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    #[must_use]
+    pub fn new<S: Into<String>>(val: S) -> Option<Self> {
+        let val = val.into();
+        (10..=79).contains(&val.chars().count()).then(|| Self(val))
     }
 
-    fn from_input_value(v: &InputValue) -> Option<Self> {
-        v.as_scalar()
-            .and_then(ScalarValue::as_str)
-            .and_then(|s| Url::parse(s).ok())
-            .and_then(|url| Self::new(url).ok())
+    /// Returns the actual (non-redacted) value of this [`SrtPassphrase`].
+    #[inline]
+    #[must_use]
+    pub fn expose(&self) -> &str {
+        &self.0
     }
+}
 
-    fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
-        <String as ParseScalarValue<S>>::from_str(value)
+impl fmt::Debug for SrtPassphrase {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SrtPassphrase(***)")
     }
 }
 
-/// Additional source for an `Output` to be mixed with before re-streaming to
-/// the destination.
-#[derive(
-    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
-)]
-pub struct Mixin {
-    /// Unique ID of this `Mixin`.
-    ///
-    /// Once assigned, it never changes.
-    pub id: MixinId,
+impl fmt::Display for SrtPassphrase {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
 
-    /// URL of the source to be mixed with an `Output`.
+impl<'de> Deserialize<'de> for SrtPassphrase {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(String::deserialize(deserializer)?).ok_or_else(|| {
+            D::Error::custom("SRT passphrase should be 10-79 characters long")
+        })
+    }
+}
+
+/// Profile of transcoding a live stream by an `Output`.
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLEnum, PartialEq, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscodingProfile {
+    /// [H.264] video encoded with [Baseline profile][1] and [AAC] audio.
     ///
-    /// At the moment, only [TeamSpeak] is supported.
+    /// The most compatible one, suitable for low-end devices.
     ///
-    /// [TeamSpeak]: https://teamspeak.com
-    pub src: MixinSrcUrl,
-
-    /// Volume rate of this `Mixin`'s audio tracks to mix them with.
-    #[serde(default, skip_serializing_if = "Volume::is_origin")]
-    pub volume: Volume,
+    /// [AAC]: https://en.wikipedia.org/wiki/Advanced_Audio_Coding
+    /// [H.264]: https://en.wikipedia.org/wiki/Advanced_Video_Coding
+    /// [1]: https://trac.ffmpeg.org/wiki/Encode/H.264#Profile
+    Baseline,
 
-    /// Delay that this `Mixin` should wait before being mixed with an `Output`.
+    /// [H.264] video encoded with [Main profile][1] and [AAC] audio.
     ///
-    /// Very useful to fix de-synchronization issues and correct timings between
-    /// a `Mixin` and its `Output`.
-    #[serde(default, skip_serializing_if = "Delay::is_zero")]
-    pub delay: Delay,
+    /// [AAC]: https://en.wikipedia.org/wiki/Advanced_Audio_Coding
+    /// [H.264]: https://en.wikipedia.org/wiki/Advanced_Video_Coding
+    /// [1]: https://trac.ffmpeg.org/wiki/Encode/H.264#Profile
+    Main,
 
-    /// `Status` of this `Mixin` indicating whether it provides an actual media
-    /// stream to be mixed with its `Output`.
-    #[serde(skip)]
-    pub status: Status,
+    /// [H.264] video encoded with [High profile][1] and [AAC] audio.
+    ///
+    /// [AAC]: https://en.wikipedia.org/wiki/Advanced_Audio_Coding
+    /// [H.264]: https://en.wikipedia.org/wiki/Advanced_Video_Coding
+    /// [1]: https://trac.ffmpeg.org/wiki/Encode/H.264#Profile
+    High,
 }
 
-impl Mixin {
-    /// Creates a new [`Mixin`] out of the given [`spec::v1::Mixin`].
-    #[inline]
-    #[must_use]
-    pub fn new(spec: spec::v1::Mixin) -> Self {
-        Self {
-            id: MixinId::random(),
-            src: spec.src,
-            volume: spec.volume,
-            delay: spec.delay,
-            status: Status::Offline,
-        }
-    }
-
-    /// Applies the given [`spec::v1::Mixin`] to this [`Mixin`].
-    #[inline]
-    pub fn apply(&mut self, new: spec::v1::Mixin) {
-        self.src = new.src;
-        self.volume = new.volume;
-        self.delay = new.delay;
-    }
-
-    /// Exports this [`Mixin`] as a [`spec::v1::Mixin`].
+impl TranscodingProfile {
+    /// Returns name of the [H.264 profile][1] represented by this
+    /// [`TranscodingProfile`].
+    ///
+    /// [1]: https://trac.ffmpeg.org/wiki/Encode/H.264#Profile
     #[inline]
     #[must_use]
-    pub fn export(&self) -> spec::v1::Mixin {
-        spec::v1::Mixin {
-            src: self.src.clone(),
-            volume: self.volume,
-            delay: self.delay,
+    pub fn h264_profile(self) -> &'static str {
+        match self {
+            Self::Baseline => "baseline",
+            Self::Main => "main",
+            Self::High => "high",
         }
     }
 }
 
-/// ID of a `Mixin`.
+/// Settings of transcoding a live stream by an `InputEndpoint` of
+/// [`InputEndpointKind::Hls`].
 #[derive(
-    Clone,
-    Copy,
-    Debug,
-    Deserialize,
-    Display,
-    Eq,
-    From,
-    GraphQLScalarValue,
-    Into,
-    PartialEq,
-    Serialize,
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
 )]
-pub struct MixinId(Uuid);
+pub struct HlsEncoding {
+    /// [H.264] profile to encode video with.
+    ///
+    /// If [`None`], then [`TranscodingProfile::Baseline`] is used.
+    ///
+    /// [H.264]: https://en.wikipedia.org/wiki/Advanced_Video_Coding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<TranscodingProfile>,
 
-impl MixinId {
-    /// Generates a new random [`MixinId`].
+    /// [x264] preset to encode video with.
+    ///
+    /// If [`None`], then [`X264Preset::Superfast`] is used.
+    ///
+    /// [x264]: https://trac.ffmpeg.org/wiki/Encode/H.264#Preset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<X264Preset>,
+
+    /// Interval between keyframes (GOP duration) to encode video with.
+    ///
+    /// If [`None`], then the default [`KeyframeInterval`] is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyframe_interval: Option<KeyframeInterval>,
+
+    /// Indicator whether a live stream should be packaged into [HLS] "as is"
+    /// (without re-encoding) if it's already encoded with [H.264] video and
+    /// [AAC] audio.
+    ///
+    /// Falls back to transcoding if the live stream is not compatible, or
+    /// hasn't been probed yet, or an adaptive bitrate ladder is used.
+    ///
+    /// [AAC]: https://en.wikipedia.org/wiki/Advanced_Audio_Coding
+    /// [H.264]: https://en.wikipedia.org/wiki/Advanced_Video_Coding
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    #[serde(default, skip_serializing_if = "crate::serde::is_false")]
+    pub copy_if_compatible: bool,
+}
+
+/// Rung of an adaptive bitrate ladder of an [`InputEndpointKind::Hls`]
+/// endpoint, representing a single rendition of a live stream.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct HlsRung {
+    /// Height (in pixels) to scale video of this rendition to, preserving its
+    /// aspect ratio.
+    pub height: u16,
+
+    /// Target bitrate (in kbps) to encode video of this rendition with.
+    pub bitrate_kbps: u32,
+}
+
+/// [Preset][1] of [x264] encoder, trading off encoding speed for compression
+/// efficiency.
+///
+/// [x264]: https://www.videolan.org/developers/x264.html
+/// [1]: https://trac.ffmpeg.org/wiki/Encode/H.264#Preset
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum X264Preset {
+    /// `ultrafast` preset.
+    Ultrafast,
+
+    /// `superfast` preset.
+    Superfast,
+
+    /// `veryfast` preset.
+    Veryfast,
+
+    /// `faster` preset.
+    Faster,
+
+    /// `fast` preset.
+    Fast,
+
+    /// `medium` preset.
+    Medium,
+
+    /// `slow` preset.
+    Slow,
+
+    /// `slower` preset.
+    Slower,
+
+    /// `veryslow` preset.
+    Veryslow,
+}
+
+impl X264Preset {
+    /// Returns name of the [x264] preset represented by this [`X264Preset`].
+    ///
+    /// [x264]: https://www.videolan.org/developers/x264.html
     #[inline]
     #[must_use]
-    pub fn random() -> Self {
-        Self(Uuid::new_v4())
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ultrafast => "ultrafast",
+            Self::Superfast => "superfast",
+            Self::Veryfast => "veryfast",
+            Self::Faster => "faster",
+            Self::Fast => "fast",
+            Self::Medium => "medium",
+            Self::Slow => "slow",
+            Self::Slower => "slower",
+            Self::Veryslow => "veryslow",
+        }
     }
 }
 
-/// [`Url`] of a [`Mixin::src`].
-///
-/// Only the following URLs are allowed at the moment:
-/// - [TeamSpeak] URL (starting with `ts://` scheme and having a host);
-/// - [MP3] HTTP URL (starting with `http://` or `https://` scheme, having a
-///   host and `.mp3` extension in its path).
+/// Level of [FFmpeg] logs, as accepted by its `-loglevel` option.
 ///
-/// [MP3]: https://en.wikipedia.org/wiki/MP3
-/// [TeamSpeak]: https://teamspeak.com
+/// [FFmpeg]: https://ffmpeg.org
 #[derive(
-    Clone, Debug, Deref, Display, Eq, Hash, Into, PartialEq, Serialize,
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLEnum, PartialEq, Serialize,
 )]
-pub struct MixinSrcUrl(Url);
+#[serde(rename_all = "lowercase")]
+pub enum FfmpegLogLevel {
+    /// Show nothing at all.
+    Quiet,
 
-impl MixinSrcUrl {
-    /// Creates a new [`MixinSrcUrl`] if the given [`Url`] is suitable for that.
-    ///
-    /// # Errors
+    /// Only show fatal errors which could lead the process to crash.
+    Panic,
+
+    /// Only show fatal errors.
+    Fatal,
+
+    /// Show all errors, including ones which can be recovered from.
+    Error,
+
+    /// Show all warnings and errors.
+    Warning,
+
+    /// Show informative messages during processing.
+    Info,
+
+    /// Same as `INFO`, except more verbose.
+    Verbose,
+
+    /// Show everything, including debugging information.
+    Debug,
+
+    /// Show everything, including tracing information.
+    Trace,
+}
+
+impl FfmpegLogLevel {
+    /// Returns value of the [FFmpeg] `-loglevel` option represented by this
+    /// [`FfmpegLogLevel`].
     ///
-    /// Returns the given [`Url`] back if it doesn't represent a valid
-    /// [`MixinSrcUrl`].
+    /// [FFmpeg]: https://ffmpeg.org
     #[inline]
-    pub fn new(url: Url) -> Result<Self, Url> {
-        if Self::validate(&url) {
-            Ok(Self(url))
-        } else {
-            Err(url)
-        }
-    }
-
-    /// Validates the given [`Url`] to represent a valid [`MixinSrcUrl`].
     #[must_use]
-    pub fn validate(url: &Url) -> bool {
-        url.has_host()
-            && match url.scheme() {
-                "ts" => true,
-                "http" | "https" => {
-                    Path::new(url.path()).extension() == Some("mp3".as_ref())
-                }
-                _ => false,
-            }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Quiet => "quiet",
+            Self::Panic => "panic",
+            Self::Fatal => "fatal",
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+            Self::Verbose => "verbose",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
     }
 }
 
-impl<'de> Deserialize<'de> for MixinSrcUrl {
-    #[inline]
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        Self::new(Url::deserialize(deserializer)?).map_err(|url| {
-            D::Error::custom(format!("Not a valid Mixin.src URL: {}", url))
+impl FromStr for FfmpegLogLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "quiet" => Self::Quiet,
+            "panic" => Self::Panic,
+            "fatal" => Self::Fatal,
+            "error" => Self::Error,
+            "warning" => Self::Warning,
+            "info" => Self::Info,
+            "verbose" => Self::Verbose,
+            "debug" => Self::Debug,
+            "trace" => Self::Trace,
+            _ => return Err(anyhow!("'{}' is invalid FFmpeg log level", s)),
         })
     }
 }
 
-/// Type of a `Mixin.src` URL.
-///
-/// Only the following URLs are allowed at the moment:
-/// - [TeamSpeak] URL (starting with `ts://` scheme and having a host);
-/// - [MP3] HTTP URL (starting with `http://` or `https://` scheme, having a
-///   host and `.mp3` extension in its path).
+/// [FFmpeg] container format (`-f`) of an [`Output`].
 ///
-/// [MP3]: https://en.wikipedia.org/wiki/MP3
-/// [TeamSpeak]: https://teamspeak.com
-#[graphql_scalar]
-impl<S> GraphQLScalar for MixinSrcUrl
-where
-    S: ScalarValue,
-{
-    fn resolve(&self) -> Value {
-        Value::scalar(self.0.as_str().to_owned())
-    }
-
-    fn from_input_value(v: &InputValue) -> Option<Self> {
-        v.as_scalar()
-            .and_then(ScalarValue::as_str)
-            .and_then(|s| Url::parse(s).ok())
-            .and_then(|url| Self::new(url).ok())
-    }
-
-    fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
-        <String as ParseScalarValue<S>>::from_str(value)
-    }
-}
-
-/// Status indicating availability of an `Input`, `Output`, or a `Mixin`.
-#[derive(Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq, SmartDefault)]
-pub enum Status {
-    /// Inactive, no operations are performed and no media traffic is flowed.
-    #[default]
-    Offline,
-
-    /// Initializing, media traffic doesn't yet flow as expected.
-    Initializing,
-
-    /// Active, all operations are performing successfully and media traffic
-    /// flows as expected.
-    Online,
-}
+/// [FFmpeg]: https://ffmpeg.org
+#[derive(
+    Clone, Debug, Deref, Display, Eq, Hash, Into, PartialEq, Serialize,
+)]
+pub struct OutputFormat(String);
 
-/// Label of a [`Restream`] or an [`Output`].
-#[derive(Clone, Debug, Deref, Display, Eq, Into, PartialEq, Serialize)]
-pub struct Label(String);
+impl OutputFormat {
+    /// [FFmpeg] container formats allowed to be used by an [`Output`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    pub const ALLOWED: &'static [&'static str] =
+        &["flv", "matroska", "mp3", "mp4", "mpegts"];
 
-impl Label {
-    /// Creates a new [`Label`] if the given value meets its invariants.
+    /// Creates a new [`OutputFormat`] if the given value is one of the
+    /// [`OutputFormat::ALLOWED`] ones.
     #[must_use]
     pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Option<Self> {
-        static REGEX: Lazy<Regex> =
-            Lazy::new(|| Regex::new(r"^[^,\n\t\r\f\v]{1,70}$").unwrap());
-
         let val = val.into();
-        (!val.is_empty() && REGEX.is_match(&val))
+        Self::ALLOWED
+            .contains(&val.as_ref())
             .then(|| Self(val.into_owned()))
     }
+
+    /// Returns value of the [FFmpeg] `-f` option represented by this
+    /// [`OutputFormat`].
+    ///
+    /// [FFmpeg]: https://ffmpeg.org
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
-impl<'de> Deserialize<'de> for Label {
+impl<'de> Deserialize<'de> for OutputFormat {
     #[inline]
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
         Self::new(<Cow<'_, str>>::deserialize(deserializer)?)
-            .ok_or_else(|| D::Error::custom("Not a valid Label"))
+            .ok_or_else(|| D::Error::custom("Not a valid OutputFormat"))
     }
 }
 
-/// Type of a `Restream` or an `Output` label.
+/// Type of an `Output`'s FFmpeg container format.
 ///
-/// It should meet `[^,\n\t\r\f\v]{1,70}` format.
+/// It should be one of: `flv`, `matroska`, `mp3`, `mp4` or `mpegts`.
 #[graphql_scalar]
-impl<S> GraphQLScalar for Label
+impl<S> GraphQLScalar for OutputFormat
 where
     S: ScalarValue,
 {
@@ -1850,80 +3713,254 @@ where
     }
 }
 
-/// Volume rate of an audio track in percents.
+/// Policy of how long a live stream mixed by an `Output` with its `Mixin`s
+/// lasts.
 #[derive(
     Clone,
     Copy,
     Debug,
     Deserialize,
     Eq,
-    Ord,
+    GraphQLEnum,
     PartialEq,
-    PartialOrd,
     Serialize,
     SmartDefault,
 )]
-pub struct Volume(#[default(Self::ORIGIN.0)] u16);
+#[serde(rename_all = "lowercase")]
+pub enum MixDuration {
+    /// Mixed live stream lasts until the longest of the original live stream
+    /// and `Mixin`s ends.
+    ///
+    /// So, a looping `Mixin` keeps the mixed live stream alive even after the
+    /// original one drops.
+    #[default]
+    Longest,
 
-impl Volume {
-    /// Maximum possible value of a [`Volume`] rate.
-    pub const MAX: Volume = Volume(1000);
+    /// Mixed live stream ends along with the original live stream.
+    First,
+}
 
-    /// Value of a [`Volume`] rate corresponding to the original one of an audio
-    /// track.
-    pub const ORIGIN: Volume = Volume(100);
+impl MixDuration {
+    /// Returns value of the [FFmpeg `amix` filter's `duration` option][1]
+    /// represented by this [`MixDuration`].
+    ///
+    /// [1]: https://ffmpeg.org/ffmpeg-filters.html#amix
+    #[inline]
+    #[must_use]
+    pub fn amix_duration(self) -> &'static str {
+        match self {
+            Self::Longest => "longest",
+            Self::First => "first",
+        }
+    }
 
-    /// Minimum possible value of a [`Volume`] rate. Actually, disables audio.
-    pub const OFF: Volume = Volume(0);
+    /// Indicates whether this [`MixDuration`] is [`MixDuration::Longest`].
+    #[allow(clippy::trivially_copy_pass_by_ref)] // required for `serde`
+    #[inline]
+    #[must_use]
+    pub fn is_longest(&self) -> bool {
+        matches!(self, Self::Longest)
+    }
+}
 
-    /// Creates a new [`Volume`] rate value if it satisfies the required
-    /// invariants:
-    /// - within [`Volume::OFF`] and [`Volume::MAX`] values.
+/// ID of an `Output`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Display,
+    Eq,
+    From,
+    GraphQLScalarValue,
+    Into,
+    PartialEq,
+    Serialize,
+)]
+pub struct OutputId(Uuid);
+
+impl OutputId {
+    /// Generates a new random [`OutputId`].
+    #[inline]
     #[must_use]
-    pub fn new<N: TryInto<u16>>(num: N) -> Option<Self> {
-        let num = num.try_into().ok()?;
-        if (Self::OFF.0..=Self::MAX.0).contains(&num) {
-            Some(Self(num))
+    pub fn random() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+/// [`Url`] of an [`Output::dst`].
+///
+/// Only the following URLs are allowed at the moment:
+/// - [RTMP] URL (starting with `rtmp://` or `rtmps://` scheme and having a
+///   host);
+/// - [SRT] URL (starting with `srt://` scheme and having a host);
+/// - [Icecast] URL (starting with `icecast://` scheme and having a host);
+/// - [FLV], [Matroska] or [MPEG-TS] file URL (starting with `file:///`
+///   scheme, without host and `..` path segments, and with `.flv`, `.mkv` or
+///   `.ts` extension in its path).
+///
+/// [FLV]: https://en.wikipedia.org/wiki/Flash_Video
+/// [Icecast]: https://icecast.org
+/// [Matroska]: https://en.wikipedia.org/wiki/Matroska
+/// [MPEG-TS]: https://en.wikipedia.org/wiki/MPEG_transport_stream
+/// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+/// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+#[derive(
+    Clone, Debug, Deref, Display, Eq, Hash, Into, PartialEq, Serialize,
+)]
+pub struct OutputDstUrl(Url);
+
+impl OutputDstUrl {
+    /// Creates a new [`OutputDstUrl`] if the given [`Url`] is suitable for
+    /// that.
+    ///
+    /// # Errors
+    ///
+    /// Returns the given [`Url`] back if it doesn't represent a valid
+    /// [`OutputDstUrl`].
+    #[inline]
+    pub fn new(url: Url) -> Result<Self, Url> {
+        if Self::validate(&url) {
+            Ok(Self(url))
         } else {
-            None
+            Err(url)
+        }
+    }
+
+    /// Validates the given [`Url`] to represent a valid [`OutputDstUrl`].
+    ///
+    /// `file:///` [`Url`]s may point into nested subdirectories, but never
+    /// traverse upwards via `..` path segments.
+    #[must_use]
+    pub fn validate(url: &Url) -> bool {
+        match url.scheme() {
+            "icecast" | "rtmp" | "rtmps" | "srt" => url.has_host(),
+            "file" => {
+                let path = Path::new(url.path());
+                !url.has_host()
+                    && path.is_absolute()
+                    && dvr::file_format(path).is_some()
+                    && path
+                        .components()
+                        .skip(1)
+                        .all(|c| matches!(c, Component::Normal(_)))
+                    && !url.path().contains("/../")
+            }
+            _ => false,
         }
     }
 
-    /// Displays this [`Volume`] as a fraction of `1`, i.e. `100%` as `1`, `50%`
-    /// as `0.50`, and so on.
+    /// Returns the `app` and `stream` names of this [`OutputDstUrl`], if it
+    /// points to the RTMP ingest of the local [SRS] server (a loopback host
+    /// with `1935` port).
+    ///
+    /// `vhost` query parameters (`rtmp://127.0.0.1/app?vhost=v/stream`) are
+    /// respected.
+    ///
+    /// [SRS]: https://github.com/ossrs/srs
     #[must_use]
-    pub fn display_as_fraction(self) -> String {
-        format!("{}.{:02}", self.0 / 100, self.0 % 100)
+    pub fn local_srs_stream(&self) -> Option<(&str, &str)> {
+        if self.scheme() != "rtmp" || self.port().unwrap_or(1935) != 1935 {
+            return None;
+        }
+        let is_loopback = match self.host()? {
+            Host::Domain(d) => d.eq_ignore_ascii_case("localhost"),
+            Host::Ipv4(ip) => ip.is_loopback() || ip.is_unspecified(),
+            Host::Ipv6(ip) => ip.is_loopback() || ip.is_unspecified(),
+        };
+        if !is_loopback {
+            return None;
+        }
+
+        let mut segments = self.path_segments()?.filter(|s| !s.is_empty());
+        let app = segments.next()?;
+        let stream = segments.last().or_else(|| {
+            self.query()
+                .and_then(|q| q.rfind('/').map(|i| &q[i + 1..]))
+                .filter(|s| !s.is_empty())
+        })?;
+        Some((app, stream))
     }
 
-    /// Indicates whether this [`Volume`] rate value corresponds is the
-    /// [`Volume::ORIGIN`]al one.
-    #[allow(clippy::trivially_copy_pass_by_ref)] // required for `serde`
+    /// Probes whether the destination of this [`OutputDstUrl`] accepts
+    /// connections, by opening a TCP connection to its host.
+    ///
+    /// [SRT] (being UDP-based) and `file:///` destinations are not probed and
+    /// are always considered accepting.
+    ///
+    /// # Errors
+    ///
+    /// If connecting fails or doesn't complete within 5 seconds.
+    ///
+    /// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
+    pub async fn probe(&self) -> anyhow::Result<()> {
+        let default_port = match self.scheme() {
+            "rtmp" => 1935,
+            "rtmps" => 443,
+            "icecast" => 8000,
+            _ => return Ok(()),
+        };
+        // Neither path nor query is used here, as they may contain secrets.
+        let addr = format!(
+            "{}:{}",
+            self.host_str().unwrap_or_default(),
+            self.port().unwrap_or(default_port),
+        );
+
+        let _ = time::timeout(
+            Duration::from_secs(5),
+            TcpStream::connect(addr.as_str()),
+        )
+        .await
+        .map_err(|_| anyhow!("Connecting to '{}' timed out", addr))?
+        .map_err(|e| anyhow!("Failed to connect to '{}': {}", addr, e))?;
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for OutputDstUrl {
     #[inline]
-    #[must_use]
-    pub fn is_origin(&self) -> bool {
-        *self == Self::ORIGIN
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(Url::deserialize(deserializer)?).map_err(|url| {
+            D::Error::custom(format!("Not a valid Output.src URL: {}", url))
+        })
     }
 }
 
-/// Type a volume rate of audio track in percents.
+/// Type of an `Output.dst` URL.
 ///
-/// It's values are always within range of `0` and `1000` (inclusively).
+/// Only the following URLs are allowed at the moment:
+/// - [RTMP] URL (starting with `rtmp://` or `rtmps://` scheme and having a
+///   host);
+/// - [SRT] URL (starting with `srt://` scheme and having a host);
+/// - [Icecast] URL (starting with `icecast://` scheme and having a host);
+/// - [FLV], [Matroska] or [MPEG-TS] file URL (starting with `file:///`
+///   scheme, without host and `..` path segments, and with `.flv`, `.mkv` or
+///   `.ts` extension in its path).
 ///
-/// `0` means disabled audio.
+/// [FLV]: https://en.wikipedia.org/wiki/Flash_Video
+/// [Icecast]: https://icecast.org
+/// [Matroska]: https://en.wikipedia.org/wiki/Matroska
+/// [MPEG-TS]: https://en.wikipedia.org/wiki/MPEG_transport_stream
+/// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+/// [SRT]: https://en.wikipedia.org/wiki/Secure_Reliable_Transport
 #[graphql_scalar]
-impl<S> GraphQLScalar for Volume
+impl<S> GraphQLScalar for OutputDstUrl
 where
     S: ScalarValue,
 {
     fn resolve(&self) -> Value {
-        Value::scalar(i32::from(self.0))
+        Value::scalar(self.0.as_str().to_owned())
     }
 
     fn from_input_value(v: &InputValue) -> Option<Self> {
         v.as_scalar()
-            .and_then(ScalarValue::as_int)
-            .and_then(Self::new)
+            .and_then(ScalarValue::as_str)
+            .and_then(|s| Url::parse(s).ok())
+            .and_then(|url| Self::new(url).ok())
     }
 
     fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
@@ -1931,63 +3968,460 @@ where
     }
 }
 
-/// Delay of a [`Mixin`] being mixed with an [`Output`].
+/// Additional source for an `Output` to be mixed with before re-streaming to
+/// the destination.
 #[derive(
-    Clone,
-    Copy,
-    Debug,
-    Deserialize,
-    Default,
-    Eq,
-    Ord,
-    PartialEq,
-    PartialOrd,
-    Serialize,
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
 )]
-pub struct Delay(#[serde(with = "serde_humantime")] Duration);
+pub struct Mixin {
+    /// Unique ID of this `Mixin`.
+    ///
+    /// Once assigned, it never changes.
+    pub id: MixinId,
 
-impl Delay {
-    /// Creates a new [`Delay`] out of the given milliseconds.
+    /// URL of the source to be mixed with an `Output`.
+    ///
+    /// At the moment, only [TeamSpeak] is supported.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub src: MixinSrcUrl,
+
+    /// Volume rate of this `Mixin`'s audio tracks to mix them with.
+    #[serde(default, skip_serializing_if = "Volume::is_origin")]
+    pub volume: Volume,
+
+    /// Delay that this `Mixin` should wait before being mixed with an `Output`.
+    ///
+    /// Very useful to fix de-synchronization issues and correct timings between
+    /// a `Mixin` and its `Output`.
+    #[serde(default, skip_serializing_if = "Delay::is_zero")]
+    pub delay: Delay,
+
+    /// Indicator whether an audio of the `Output` should be ducked under this
+    /// `Mixin`'s audio via sidechain compression (so the original audio
+    /// quietens while somebody speaks).
+    ///
+    /// Has effect only for [TeamSpeak] `Mixin`s.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub sidechain: bool,
+
+    /// `Status` of this `Mixin` indicating whether it provides an actual media
+    /// stream to be mixed with its `Output`.
+    #[serde(skip)]
+    pub status: Status,
+}
+
+impl Mixin {
+    /// Creates a new [`Mixin`] out of the given [`spec::v1::Mixin`].
     #[inline]
     #[must_use]
-    pub fn from_millis<N: TryInto<u64>>(millis: N) -> Option<Self> {
-        millis
-            .try_into()
-            .ok()
-            .map(|m| Self(Duration::from_millis(m)))
+    pub fn new(spec: spec::v1::Mixin) -> Self {
+        Self {
+            id: MixinId::random(),
+            src: spec.src,
+            volume: spec.volume,
+            delay: spec.delay,
+            sidechain: spec.sidechain,
+            status: Status::Offline,
+        }
     }
 
-    /// Returns milliseconds of this [`Delay`].
+    /// Applies the given [`spec::v1::Mixin`] to this [`Mixin`].
     #[inline]
-    #[must_use]
-    pub fn as_millis(&self) -> i32 {
-        self.0.as_millis().try_into().unwrap()
+    pub fn apply(&mut self, new: spec::v1::Mixin) {
+        self.src = new.src;
+        self.volume = new.volume;
+        self.delay = new.delay;
+        self.sidechain = new.sidechain;
     }
 
-    /// Indicates whether this [`Delay`] introduces no actual delay.
+    /// Exports this [`Mixin`] as a [`spec::v1::Mixin`].
     #[inline]
     #[must_use]
-    pub fn is_zero(&self) -> bool {
-        self.0 == Duration::default()
+    pub fn export(&self) -> spec::v1::Mixin {
+        spec::v1::Mixin {
+            src: self.src.clone(),
+            volume: self.volume,
+            delay: self.delay,
+            sidechain: self.sidechain,
+        }
     }
 }
 
-/// Type of a `Mixin` delay in milliseconds.
-///
-/// Negative values are not allowed.
-#[graphql_scalar]
-impl<S> GraphQLScalar for Delay
-where
-    S: ScalarValue,
-{
-    fn resolve(&self) -> Value {
-        Value::scalar(self.as_millis())
-    }
+/// ID of a `Mixin`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Display,
+    Eq,
+    From,
+    GraphQLScalarValue,
+    Hash,
+    Into,
+    PartialEq,
+    Serialize,
+)]
+pub struct MixinId(Uuid);
 
-    fn from_input_value(v: &InputValue) -> Option<Self> {
-        v.as_scalar()
-            .and_then(ScalarValue::as_int)
-            .and_then(Self::from_millis)
+impl MixinId {
+    /// Generates a new random [`MixinId`].
+    #[inline]
+    #[must_use]
+    pub fn random() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+/// [`Url`] of a [`Mixin::src`].
+///
+/// Only the following URLs are allowed at the moment:
+/// - [TeamSpeak] URL (starting with `ts://` scheme and having a host);
+/// - [MP3] HTTP URL (starting with `http://` or `https://` scheme, having a
+///   host and `.mp3` extension in its path);
+/// - [MP3] local file URL (starting with `file://` scheme, having an absolute
+///   path with `.mp3` extension and without `..` traversals).
+///
+/// [MP3]: https://en.wikipedia.org/wiki/MP3
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(
+    Clone, Debug, Deref, Display, Eq, Hash, Into, PartialEq, Serialize,
+)]
+pub struct MixinSrcUrl(Url);
+
+impl MixinSrcUrl {
+    /// Creates a new [`MixinSrcUrl`] if the given [`Url`] is suitable for that.
+    ///
+    /// # Errors
+    ///
+    /// Returns the given [`Url`] back if it doesn't represent a valid
+    /// [`MixinSrcUrl`].
+    #[inline]
+    pub fn new(url: Url) -> Result<Self, Url> {
+        if Self::validate(&url) {
+            Ok(Self(url))
+        } else {
+            Err(url)
+        }
+    }
+
+    /// Validates the given [`Url`] to represent a valid [`MixinSrcUrl`].
+    ///
+    /// Local `file://` URLs should point to an absolute `.mp3` file path
+    /// without any `..` traversals.
+    #[must_use]
+    pub fn validate(url: &Url) -> bool {
+        let is_mp3 = |p: &Path| p.extension() == Some("mp3".as_ref());
+        match url.scheme() {
+            "ts" => url.has_host(),
+            "http" | "https" => url.has_host() && is_mp3(Path::new(url.path())),
+            "file" => url.to_file_path().map_or(false, |p| {
+                p.is_absolute()
+                    && is_mp3(&p)
+                    && p.components().all(|c| {
+                        matches!(c, Component::RootDir | Component::Normal(_))
+                    })
+            }),
+            _ => false,
+        }
+    }
+
+    /// Checks whether this [`MixinSrcUrl`] is reachable and points to an
+    /// audio, by performing a `HEAD` request to it.
+    ///
+    /// [TeamSpeak] URLs are not checked and are always considered reachable.
+    /// Local `file://` URLs are checked to point to an existing file.
+    ///
+    /// # Errors
+    ///
+    /// If the request fails, or responds with a non-2xx status or a
+    /// non-audio `Content-Type`.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub async fn check_reachable(&self) -> anyhow::Result<()> {
+        match self.scheme() {
+            "ts" => return Ok(()),
+            "file" => {
+                let path = self
+                    .to_file_path()
+                    .map_err(|_| anyhow!("'{}' is not a local path", self))?;
+                let is_file = fs::metadata(&path)
+                    .await
+                    .map_err(|e| anyhow!("Failed to access '{}': {}", self, e))?
+                    .is_file();
+                if !is_file {
+                    return Err(anyhow!("'{}' doesn't point to a file", self));
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let resp = reqwest::Client::new()
+            .head(self.0.clone())
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to request '{}': {}", self, e))?;
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "'{}' responded with bad status: {}",
+                self,
+                resp.status(),
+            ));
+        }
+        let is_audio = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map_or(false, |ct| ct.starts_with("audio/"));
+        if !is_audio {
+            return Err(anyhow!("'{}' doesn't point to an audio", self));
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for MixinSrcUrl {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(Url::deserialize(deserializer)?).map_err(|url| {
+            D::Error::custom(format!("Not a valid Mixin.src URL: {}", url))
+        })
+    }
+}
+
+/// Type of a `Mixin.src` URL.
+///
+/// Only the following URLs are allowed at the moment:
+/// - [TeamSpeak] URL (starting with `ts://` scheme and having a host);
+/// - [MP3] HTTP URL (starting with `http://` or `https://` scheme, having a
+///   host and `.mp3` extension in its path);
+/// - [MP3] local file URL (starting with `file://` scheme, having an absolute
+///   path with `.mp3` extension and without `..` traversals).
+///
+/// [MP3]: https://en.wikipedia.org/wiki/MP3
+/// [TeamSpeak]: https://teamspeak.com
+#[graphql_scalar]
+impl<S> GraphQLScalar for MixinSrcUrl
+where
+    S: ScalarValue,
+{
+    fn resolve(&self) -> Value {
+        Value::scalar(self.0.as_str().to_owned())
+    }
+
+    fn from_input_value(v: &InputValue) -> Option<Self> {
+        v.as_scalar()
+            .and_then(ScalarValue::as_str)
+            .and_then(|s| Url::parse(s).ok())
+            .and_then(|url| Self::new(url).ok())
+    }
+
+    fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+}
+
+/// Status indicating availability of an `Input`, `Output`, or a `Mixin`.
+#[derive(Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq, SmartDefault)]
+pub enum Status {
+    /// Inactive, no operations are performed and no media traffic is flowed.
+    #[default]
+    Offline,
+
+    /// Initializing, media traffic doesn't yet flow as expected.
+    Initializing,
+
+    /// Active, all operations are performing successfully and media traffic
+    /// flows as expected.
+    Online,
+}
+
+/// Transition of an `Input` or an `Output` from one `Status` to another.
+#[derive(Clone, Debug, GraphQLObject, PartialEq)]
+pub struct StatusEvent {
+    /// Sequential number of this `StatusEvent`, used as a cursor for paging.
+    pub id: i32,
+
+    /// Moment when this `StatusEvent` has happened.
+    pub at: DateTime<Utc>,
+
+    /// Key of the `Restream` the transitioned element belongs to.
+    pub restream_key: RestreamKey,
+
+    /// ID of the transitioned `Output` or `Input` endpoint.
+    pub element_id: Uuid,
+
+    /// `Status` the element has been in before this transition.
+    pub from: Status,
+
+    /// `Status` the element has transitioned to.
+    pub to: Status,
+}
+
+/// In-memory ring buffer of the most recent [`StatusEvent`]s.
+///
+/// Once its capacity is exceeded, the oldest [`StatusEvent`]s are evicted.
+#[derive(Clone, Debug)]
+pub struct StatusEvents(Arc<Mutex<StatusEventsBuffer>>);
+
+/// Inner storage of [`StatusEvents`].
+#[derive(Debug)]
+struct StatusEventsBuffer {
+    /// Recorded [`StatusEvent`]s, from the oldest to the newest one.
+    events: VecDeque<StatusEvent>,
+
+    /// Maximum number of [`StatusEvent`]s to be kept.
+    capacity: usize,
+
+    /// [`StatusEvent::id`] of the next recorded [`StatusEvent`].
+    next_id: i32,
+}
+
+impl StatusEvents {
+    /// Default maximum number of [`StatusEvent`]s to be kept.
+    pub const DEFAULT_CAPACITY: usize = 1000;
+
+    /// Creates a new empty [`StatusEvents`] keeping at most `capacity` of the
+    /// most recent [`StatusEvent`]s.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(StatusEventsBuffer {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+            next_id: 1,
+        })))
+    }
+
+    /// Records a transition of the element with the given `id` of the
+    /// [`Restream`] with the given `key` from one [`Status`] to another,
+    /// evicting the oldest [`StatusEvent`] if the capacity is exceeded.
+    pub fn record<I: Into<Uuid>>(
+        &self,
+        key: &RestreamKey,
+        id: I,
+        from: Status,
+        to: Status,
+    ) {
+        let mut buf = self.0.lock().unwrap();
+        if buf.capacity == 0 {
+            return;
+        }
+        if buf.events.len() >= buf.capacity {
+            let _ = buf.events.pop_front();
+        }
+        let event = StatusEvent {
+            id: buf.next_id,
+            at: Utc::now(),
+            restream_key: key.clone(),
+            element_id: id.into(),
+            from,
+            to,
+        };
+        buf.next_id = buf.next_id.saturating_add(1);
+        buf.events.push_back(event);
+    }
+
+    /// Returns at most `limit` of the recorded [`StatusEvent`]s, from the
+    /// newest to the oldest one.
+    ///
+    /// If `before` is specified, then only [`StatusEvent`]s with smaller
+    /// [`StatusEvent::id`] are returned, so the `before` value may be used as
+    /// a cursor for paging.
+    #[must_use]
+    pub fn page(&self, limit: usize, before: Option<i32>) -> Vec<StatusEvent> {
+        self.0
+            .lock()
+            .unwrap()
+            .events
+            .iter()
+            .rev()
+            .filter(|e| before.map_or(true, |b| e.id < b))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for StatusEvents {
+    #[inline]
+    fn default() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+}
+
+/// Known reason of why an `Output` cannot reach `Status::Online`.
+#[derive(Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq)]
+pub enum StatusReason {
+    /// Codec of the live stream is not supported by the `Output`'s destination
+    /// container, so the live stream cannot be re-streamed "as is" there.
+    ///
+    /// For example, [HEVC] video cannot be copied into [FLV]/[RTMP] without
+    /// transcoding.
+    ///
+    /// [FLV]: https://en.wikipedia.org/wiki/Flash_Video
+    /// [HEVC]: https://en.wikipedia.org/wiki/High_Efficiency_Video_Coding
+    /// [RTMP]: https://en.wikipedia.org/wiki/Real-Time_Messaging_Protocol
+    IncompatibleCodec,
+}
+
+/// Label of a [`Restream`], an [`Output`] or a [`Preset`].
+#[derive(
+    Clone, Debug, Deref, Display, Eq, Hash, Into, PartialEq, Serialize,
+)]
+pub struct Label(String);
+
+impl Label {
+    /// Creates a new [`Label`] if the given value meets its invariants.
+    ///
+    /// The value should be 1-70 characters long and contain no control
+    /// characters (like newlines or tabs).
+    #[must_use]
+    pub fn new<'s, S: Into<Cow<'s, str>>>(val: S) -> Option<Self> {
+        static REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"^\P{Cc}{1,70}$").unwrap());
+
+        let val = val.into();
+        (!val.is_empty() && REGEX.is_match(&val))
+            .then(|| Self(val.into_owned()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Label {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Self::new(<Cow<'_, str>>::deserialize(deserializer)?)
+            .ok_or_else(|| D::Error::custom("Not a valid Label"))
+    }
+}
+
+/// Type of a `Restream`, an `Output` or a `Preset` label.
+///
+/// It should be 1-70 characters long and contain no control characters (like
+/// newlines or tabs).
+#[graphql_scalar]
+impl<S> GraphQLScalar for Label
+where
+    S: ScalarValue,
+{
+    fn resolve(&self) -> Value {
+        Value::scalar(self.0.as_str().to_owned())
+    }
+
+    fn from_input_value(v: &InputValue) -> Option<Self> {
+        v.as_scalar()
+            .and_then(ScalarValue::as_str)
+            .and_then(Self::new)
     }
 
     fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
@@ -1995,22 +4429,2143 @@ where
     }
 }
 
+/// Named set of [`Volume`] rates of [`Output`]s and their [`Mixin`]s in a
+/// [`Restream`], which may be applied at once.
+#[derive(
+    Clone, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct Preset {
+    /// Unique ID of this `Preset`.
+    ///
+    /// Once assigned, it never changes.
+    pub id: PresetId,
+
+    /// Unique (in its `Restream`) name of this `Preset`.
+    pub name: Label,
+
+    /// `Volume` rates to be applied by this `Preset`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<PresetVolume>,
+}
+
+impl Preset {
+    /// Creates a new [`Preset`] out of the given [`spec::v1::Preset`],
+    /// resolving it against the given [`Output`]s.
+    ///
+    /// [`spec::v1::PresetVolume`]s referring unknown [`Output`]s or
+    /// [`Mixin`]s are omitted.
+    #[must_use]
+    pub fn new(spec: spec::v1::Preset, outputs: &[Output]) -> Self {
+        Self {
+            id: PresetId::random(),
+            name: spec.name,
+            volumes: spec
+                .volumes
+                .into_iter()
+                .filter_map(|v| {
+                    let output = outputs.iter().find(|o| o.dst == v.output)?;
+                    let mixin_id = match &v.mixin {
+                        Some(src) => Some(
+                            output.mixins.iter().find(|m| m.src == *src)?.id,
+                        ),
+                        None => None,
+                    };
+                    Some(PresetVolume {
+                        output_id: output.id,
+                        mixin_id,
+                        volume: v.volume,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Exports this [`Preset`] as a [`spec::v1::Preset`], resolving it against
+    /// the given [`Output`]s.
+    ///
+    /// [`PresetVolume`]s referring already removed [`Output`]s or [`Mixin`]s
+    /// are omitted.
+    #[must_use]
+    pub fn export(&self, outputs: &[Output]) -> spec::v1::Preset {
+        spec::v1::Preset {
+            name: self.name.clone(),
+            volumes: self
+                .volumes
+                .iter()
+                .filter_map(|v| {
+                    let output =
+                        outputs.iter().find(|o| o.id == v.output_id)?;
+                    let mixin = match v.mixin_id {
+                        Some(id) => Some(
+                            output
+                                .mixins
+                                .iter()
+                                .find(|m| m.id == id)?
+                                .src
+                                .clone(),
+                        ),
+                        None => None,
+                    };
+                    Some(spec::v1::PresetVolume {
+                        output: output.dst.clone(),
+                        mixin,
+                        volume: v.volume,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// ID of a `Preset`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Display,
+    Eq,
+    From,
+    GraphQLScalarValue,
+    Hash,
+    Into,
+    PartialEq,
+    Serialize,
+)]
+pub struct PresetId(Uuid);
+
+impl PresetId {
+    /// Generates a new random [`PresetId`].
+    #[inline]
+    #[must_use]
+    pub fn random() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+/// [`Volume`] rate of an [`Output`] or one of its [`Mixin`]s stored in a
+/// [`Preset`].
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, GraphQLObject, PartialEq, Serialize,
+)]
+pub struct PresetVolume {
+    /// ID of the tuned `Output`.
+    pub output_id: OutputId,
+
+    /// Optional ID of the tuned `Mixin`.
+    ///
+    /// If set, then tunes the `Mixin` rather than the `Output`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mixin_id: Option<MixinId>,
+
+    /// Volume rate in percents to be set.
+    pub volume: Volume,
+}
+
+impl From<PresetVolume> for VolumeChange {
+    #[inline]
+    fn from(v: PresetVolume) -> Self {
+        Self {
+            output_id: v.output_id,
+            mixin_id: v.mixin_id,
+            volume: v.volume,
+        }
+    }
+}
+
+/// Change of a [`Volume`] rate of an [`Output`] or one of its [`Mixin`]s.
+#[derive(Clone, Copy, Debug, GraphQLInputObject)]
+pub struct VolumeChange {
+    /// ID of the tuned `Output`.
+    pub output_id: OutputId,
+
+    /// Optional ID of the tuned `Mixin`.
+    ///
+    /// If set, then tunes the `Mixin` rather than the `Output`.
+    pub mixin_id: Option<MixinId>,
+
+    /// Volume rate in percents to be set.
+    pub volume: Volume,
+}
+
+/// Volume rate of an audio track in percents.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+    SmartDefault,
+)]
+pub struct Volume(#[default(Self::ORIGIN.0)] u16);
+
+impl Volume {
+    /// Maximum possible value of a [`Volume`] rate.
+    pub const MAX: Volume = Volume(1000);
+
+    /// Value of a [`Volume`] rate corresponding to the original one of an audio
+    /// track.
+    pub const ORIGIN: Volume = Volume(100);
+
+    /// Minimum possible value of a [`Volume`] rate. Actually, disables audio.
+    pub const OFF: Volume = Volume(0);
+
+    /// Creates a new [`Volume`] rate value if it satisfies the required
+    /// invariants:
+    /// - within [`Volume::OFF`] and [`Volume::MAX`] values.
+    #[must_use]
+    pub fn new<N: TryInto<u16>>(num: N) -> Option<Self> {
+        let num = num.try_into().ok()?;
+        if (Self::OFF.0..=Self::MAX.0).contains(&num) {
+            Some(Self(num))
+        } else {
+            None
+        }
+    }
+
+    /// Displays this [`Volume`] as a fraction of `1`, i.e. `100%` as `1.00`,
+    /// `50%` as `0.50`, and so on.
+    ///
+    /// Precision of the fractional part is derived from the
+    /// [`Volume::ORIGIN`] value (which is expected to be a power of `10`), so
+    /// all the [`Volume`] rate values are displayed exactly.
+    #[must_use]
+    pub fn display_as_fraction(self) -> String {
+        let scale = Self::ORIGIN.0;
+        let mut precision = 0;
+        let mut n = scale;
+        while n > 1 {
+            n /= 10;
+            precision += 1;
+        }
+        format!(
+            "{}.{:0precision$}",
+            self.0 / scale,
+            self.0 % scale,
+            precision = precision,
+        )
+    }
+
+    /// Indicates whether this [`Volume`] rate value corresponds is the
+    /// [`Volume::ORIGIN`]al one.
+    #[allow(clippy::trivially_copy_pass_by_ref)] // required for `serde`
+    #[inline]
+    #[must_use]
+    pub fn is_origin(&self) -> bool {
+        *self == Self::ORIGIN
+    }
+}
+
+/// Type a volume rate of audio track in percents.
+///
+/// It's values are always within range of `0` and `1000` (inclusively).
+///
+/// `0` means disabled audio.
+#[graphql_scalar]
+impl<S> GraphQLScalar for Volume
+where
+    S: ScalarValue,
+{
+    fn resolve(&self) -> Value {
+        Value::scalar(i32::from(self.0))
+    }
+
+    fn from_input_value(v: &InputValue) -> Option<Self> {
+        v.as_scalar()
+            .and_then(ScalarValue::as_int)
+            .and_then(Self::new)
+    }
+
+    fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+}
+
+/// Interval between keyframes (GOP duration) of a transcoded [`Output`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyframeInterval(#[serde(with = "serde_humantime")] Duration);
+
+impl KeyframeInterval {
+    /// Maximum allowed [`KeyframeInterval`] in seconds.
+    pub const MAX_SECS: u64 = 60;
+
+    /// Creates a new [`KeyframeInterval`] out of the given seconds, if they're
+    /// in `1..=`[`KeyframeInterval::MAX_SECS`] range.
+    #[inline]
+    #[must_use]
+    pub fn from_secs<N: TryInto<u64>>(secs: N) -> Option<Self> {
+        secs.try_into()
+            .ok()
+            .filter(|s| (1..=Self::MAX_SECS).contains(s))
+            .map(|s| Self(Duration::from_secs(s)))
+    }
+
+    /// Returns seconds of this [`KeyframeInterval`].
+    #[inline]
+    #[must_use]
+    pub fn as_secs(&self) -> i32 {
+        self.0.as_secs().try_into().unwrap()
+    }
+}
+
+impl Default for KeyframeInterval {
+    /// Default [`KeyframeInterval`] of 2 seconds, which evenly divides the
+    /// default 10 seconds [HLS] fragment of [SRS], so keyframes are aligned
+    /// with segments boundaries.
+    ///
+    /// [HLS]: https://en.wikipedia.org/wiki/HTTP_Live_Streaming
+    /// [SRS]: https://github.com/ossrs/srs
+    #[inline]
+    fn default() -> Self {
+        Self(Duration::from_secs(2))
+    }
+}
+
+/// Type of an `Output` keyframe interval in seconds.
+///
+/// Only values in `1..=60` range are allowed.
+#[graphql_scalar]
+impl<S> GraphQLScalar for KeyframeInterval
+where
+    S: ScalarValue,
+{
+    fn resolve(&self) -> Value {
+        Value::scalar(self.as_secs())
+    }
+
+    fn from_input_value(v: &InputValue) -> Option<Self> {
+        v.as_scalar()
+            .and_then(ScalarValue::as_int)
+            .and_then(Self::from_secs)
+    }
+
+    fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+}
+
+/// Delay of a [`Mixin`] being mixed with an [`Output`].
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Default,
+    Eq,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+pub struct Delay(#[serde(with = "serde_humantime")] Duration);
+
+impl Delay {
+    /// Creates a new [`Delay`] out of the given milliseconds.
+    #[inline]
+    #[must_use]
+    pub fn from_millis<N: TryInto<u64>>(millis: N) -> Option<Self> {
+        millis
+            .try_into()
+            .ok()
+            .map(|m| Self(Duration::from_millis(m)))
+    }
+
+    /// Creates a new [`Delay`] out of the given fractional seconds, rounded to
+    /// whole milliseconds.
+    ///
+    /// Returns [`None`] if the given `secs` are negative, not finite, or don't
+    /// fit into [`i32`] milliseconds.
+    #[must_use]
+    pub fn from_secs_f64(secs: f64) -> Option<Self> {
+        let millis = (secs * 1000.0).round();
+        if !(0.0..=f64::from(i32::MAX)).contains(&millis) {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Some(Self(Duration::from_millis(millis as u64)))
+    }
+
+    /// Returns milliseconds of this [`Delay`].
+    #[inline]
+    #[must_use]
+    pub fn as_millis(&self) -> i32 {
+        self.0.as_millis().try_into().unwrap()
+    }
+
+    /// Indicates whether this [`Delay`] introduces no actual delay.
+    #[inline]
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.0 == Duration::default()
+    }
+}
+
+/// Type of a `Mixin` or an `Output` delay in milliseconds.
+///
+/// Negative values are not allowed.
+#[graphql_scalar]
+impl<S> GraphQLScalar for Delay
+where
+    S: ScalarValue,
+{
+    fn resolve(&self) -> Value {
+        Value::scalar(self.as_millis())
+    }
+
+    fn from_input_value(v: &InputValue) -> Option<Self> {
+        v.as_scalar()
+            .and_then(ScalarValue::as_int)
+            .and_then(Self::from_millis)
+    }
+
+    fn from_str(value: ScalarToken<'_>) -> ParseScalarResult<'_, S> {
+        <String as ParseScalarValue<S>>::from_str(value)
+    }
+}
+
+#[cfg(test)]
+impl State {
+    /// Creates a new [`State`] to be used in tests, populated with the given
+    /// [`spec::v1::Restream`]s.
+    #[must_use]
+    pub fn with_restreams(restreams: Vec<spec::v1::Restream>) -> Self {
+        let state = Self::default();
+        for r in restreams {
+            state.add_restream(r).unwrap();
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod volume_spec {
+    use super::Volume;
+
+    #[test]
+    fn displays_as_fraction() {
+        for (input, expected) in &[
+            (1, "0.01"),
+            (10, "0.10"),
+            (200, "2.00"),
+            (107, "1.07"),
+            (170, "1.70"),
+            (1000, "10.00"),
+            (0, "0.00"),
+            (999, "9.99"),
+        ] {
+            let actual = Volume::new(*input).unwrap().display_as_fraction();
+            assert_eq!(&actual, *expected);
+        }
+    }
+
+    #[test]
+    fn displays_as_fraction_exactly_in_full_range() {
+        let scale = f64::from(Volume::ORIGIN.0);
+        let precision = scale.log10().ceil() as usize;
+        for n in Volume::OFF.0..=Volume::MAX.0 {
+            assert_eq!(
+                Volume::new(n).unwrap().display_as_fraction(),
+                format!(
+                    "{:.precision$}",
+                    f64::from(n) / scale,
+                    precision = precision,
+                ),
+                "volume: {}",
+                n,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod delay_spec {
+    use super::Delay;
+
+    #[test]
+    fn converts_from_seconds() {
+        for (input, expected) in &[
+            (0.0, 0),
+            (0.001, 1),
+            (1.0, 1000),
+            (3.5, 3500),
+            (2.0004, 2000),
+            (2.0005, 2001),
+        ] {
+            assert_eq!(
+                Delay::from_secs_f64(*input).map(|d| d.as_millis()),
+                Some(*expected),
+                "input: {}",
+                input,
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_seconds() {
+        for input in &[-0.5, -3.5, f64::NAN, f64::INFINITY, 1e10] {
+            assert!(Delay::from_secs_f64(*input).is_none(), "input: {}", input);
+        }
+    }
+}
+
+#[cfg(test)]
+mod restreams_order_spec {
+    use crate::spec;
+
+    use super::{RestreamId, State};
+
+    fn ids(state: &State) -> Vec<RestreamId> {
+        state.restreams.lock_ref().iter().map(|r| r.id).collect()
+    }
+
+    #[test]
+    fn reorders_restreams() {
+        let state = State::with_restreams(vec![
+            spec::v1::Restream::with_key("first"),
+            spec::v1::Restream::with_key("second"),
+            spec::v1::Restream::with_key("third"),
+        ]);
+        let orig = ids(&state);
+        let new_order = vec![orig[2], orig[0], orig[1]];
+
+        assert!(state.set_restreams_order(&new_order).unwrap());
+        assert_eq!(ids(&state), new_order);
+
+        let keys = state
+            .restreams
+            .lock_ref()
+            .iter()
+            .map(|r| r.key.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, vec!["third", "first", "second"]);
+
+        assert!(!state.set_restreams_order(&new_order).unwrap());
+    }
+
+    #[test]
+    fn rejects_order_with_missing_id() {
+        let state = State::with_restreams(vec![
+            spec::v1::Restream::with_key("first"),
+            spec::v1::Restream::with_key("second"),
+            spec::v1::Restream::with_key("third"),
+        ]);
+        let orig = ids(&state);
+
+        assert!(state.set_restreams_order(&[orig[2], orig[0]]).is_err());
+        assert!(state
+            .set_restreams_order(&[orig[2], orig[0], RestreamId::random()])
+            .is_err());
+        assert!(state
+            .set_restreams_order(&[orig[2], orig[0], orig[0]])
+            .is_err());
+        assert_eq!(ids(&state), orig);
+    }
+}
+
+#[cfg(test)]
+mod latency_spec {
+    use std::time::Duration;
+
+    use url::Url;
+
+    use crate::spec;
+
+    use super::{
+        Delay, InputKey, MixinSrcUrl, Restream, TranscodingProfile, Volume,
+    };
+
+    fn restream(failover: bool) -> Restream {
+        let mut spec = spec::v1::Restream::with_key("test");
+        if failover {
+            let input = |key: &str| spec::v1::Input {
+                key: InputKey::new(key).unwrap(),
+                ..spec.input.clone()
+            };
+            let inputs = vec![input("main"), input("backup")];
+            spec.input.src = Some(spec::v1::InputSrc::FailoverInputs(inputs));
+        }
+        Restream::new(spec)
+    }
+
+    fn output(
+        transcoding: Option<TranscodingProfile>,
+        delays: &[u64],
+    ) -> spec::v1::Output {
+        spec::v1::Output {
+            mixins: delays
+                .iter()
+                .enumerate()
+                .map(|(n, d)| spec::v1::Mixin {
+                    src: MixinSrcUrl::new(
+                        Url::parse(&format!("https://example.com/{}.mp3", n))
+                            .unwrap(),
+                    )
+                    .unwrap(),
+                    volume: Volume::ORIGIN,
+                    delay: Delay::from_millis(*d).unwrap(),
+                    sidechain: false,
+                })
+                .collect(),
+            transcoding,
+            ..spec::v1::Output::with_dst("rtmp://example.com/live/stream")
+        }
+    }
+
+    #[test]
+    fn sums_known_buffers_and_delays() {
+        for (failover, transcoding, delays, expected) in vec![
+            (false, None, vec![], 350),
+            (true, None, vec![], 700),
+            (false, Some(TranscodingProfile::Main), vec![], 1350),
+            (false, None, vec![3500, 1000], 3850),
+            (true, None, vec![200], 900),
+            // Transcoding has no effect when mixing.
+            (false, Some(TranscodingProfile::High), vec![500], 850),
+        ] {
+            let mut r = restream(failover);
+            r.apply(
+                spec::v1::Restream {
+                    outputs: vec![output(transcoding, &delays)],
+                    ..r.export()
+                },
+                true,
+            );
+
+            assert_eq!(
+                r.estimate_latency(&r.outputs[0]),
+                Duration::from_millis(expected),
+                "failover: {}, transcoding: {:?}, delays: {:?}",
+                failover,
+                transcoding,
+                delays,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod snapshot_spec {
+    use std::env;
+
+    use tokio::fs;
+    use uuid::Uuid;
+
+    use crate::spec;
+
+    use super::State;
+
+    #[actix_rt::test]
+    async fn keeps_only_latest_snapshots() {
+        let dir = env::temp_dir().join(format!("ephyr-{}", Uuid::new_v4()));
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("first")]);
+
+        let mut names = vec![];
+        for _ in 0..3 {
+            names.push(state.snapshot(&dir, 2).await.unwrap());
+        }
+
+        let mut files = State::list_snapshots(&dir).await.unwrap();
+        files.sort();
+        assert_eq!(files, names[1..].to_vec());
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+
+    #[actix_rt::test]
+    async fn restores_snapshot() {
+        let dir = env::temp_dir().join(format!("ephyr-{}", Uuid::new_v4()));
+        let state = State::with_restreams(vec![
+            spec::v1::Restream::with_key("first"),
+            spec::v1::Restream::with_key("second"),
+        ]);
+
+        let name = state.snapshot(&dir, 5).await.unwrap();
+
+        let restored =
+            State::with_restreams(vec![spec::v1::Restream::with_key("third")]);
+        restored.restore_snapshot(&dir, &name).await.unwrap();
+
+        assert_eq!(restored.export().into_v1(), state.export().into_v1());
+
+        assert!(restored
+            .restore_snapshot(&dir, "../state.json")
+            .await
+            .is_err());
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+}
+
+#[cfg(test)]
+mod persistence_spec {
+    use std::{env, path::PathBuf, time::Duration};
+
+    use tokio::{fs, time};
+    use uuid::Uuid;
+
+    use super::{persist_atomically, tmp_path, State};
+
+    fn state_file() -> PathBuf {
+        let dir = env::temp_dir().join(format!("ephyr-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("state.json")
+    }
+
+    #[actix_rt::test]
+    async fn partially_written_tmp_file_doesnt_clobber_state() {
+        let file = state_file();
+        let valid = serde_json::to_vec(&State::default()).unwrap();
+        persist_atomically(&file, &valid).await.unwrap();
+        assert!(!tmp_path(&file).exists());
+
+        // Imitate the process being killed in the middle of writing.
+        fs::write(tmp_path(&file), &valid[..valid.len() / 2])
+            .await
+            .unwrap();
+
+        assert_eq!(fs::read(&file).await.unwrap(), valid);
+        assert!(State::try_new(&file).await.is_ok());
+
+        let _ = fs::remove_dir_all(file.parent().unwrap()).await;
+    }
+
+    #[actix_rt::test]
+    async fn backs_up_corrupted_state_file() {
+        let file = state_file();
+        fs::write(&file, b"{\"restreams\": [").await.unwrap();
+
+        let state = State::try_new(&file).await.unwrap();
+        assert!(state.restreams.lock_ref().is_empty());
+
+        let dir = file.parent().unwrap();
+        let backup = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .map_or(false, |n| n.starts_with("state.json.corrupted."))
+            })
+            .expect("corrupted state file should be backed up");
+        assert_eq!(fs::read(backup).await.unwrap(), b"{\"restreams\": [");
+
+        let _ = fs::remove_dir_all(dir).await;
+    }
+
+    #[actix_rt::test]
+    async fn persists_latest_state_on_concurrent_changes() {
+        let file = state_file();
+        let state = State::try_new(&file).await.unwrap();
+
+        for i in 0..20 {
+            state.maintenance_notice.set(Some(format!("notice {}", i)));
+            state.password_hash.set(Some(format!("hash {}", i)));
+        }
+        time::delay_for(Duration::from_millis(500)).await;
+
+        let persisted: State =
+            serde_json::from_slice(&fs::read(&file).await.unwrap()).unwrap();
+        assert_eq!(
+            persisted.maintenance_notice.get_cloned().as_deref(),
+            Some("notice 19"),
+        );
+        assert_eq!(
+            persisted.password_hash.get_cloned().as_deref(),
+            Some("hash 19"),
+        );
+
+        let _ = fs::remove_dir_all(file.parent().unwrap()).await;
+    }
+}
+
+#[cfg(test)]
+mod output_dst_url_spec {
+    use url::Url;
+
+    use super::OutputDstUrl;
+
+    fn is_valid(url: &str) -> bool {
+        OutputDstUrl::validate(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn accepts_root_flv_file() {
+        assert!(is_valid("file:///record.flv"));
+    }
+
+    #[test]
+    fn accepts_nested_flv_file() {
+        assert!(is_valid("file:///recordings/record.flv"));
+        assert!(is_valid("file:///recordings/show1/record.flv"));
+        assert!(is_valid("file:///a/b/c/d/record.flv"));
+    }
+
+    #[test]
+    fn never_traverses_upwards() {
+        for url in &[
+            "file:///../record.flv",
+            "file:///recordings/../record.flv",
+            "file:///recordings/%2E%2E/record.flv",
+            "file:///recordings/show1/../../../etc/record.flv",
+        ] {
+            // `Url` normalizes `..` segments on parsing, so they either are
+            // resolved within the root or the URL is rejected.
+            let url = Url::parse(url).unwrap();
+            if let Ok(dst) = OutputDstUrl::new(url) {
+                assert!(!dst.path().contains(".."), "accepted: {}", dst);
+            }
+        }
+    }
+
+    #[test]
+    fn accepts_mkv_and_ts_files() {
+        assert!(is_valid("file:///recordings/record.mkv"));
+        assert!(is_valid("file:///recordings/show1/record.ts"));
+    }
+
+    #[test]
+    fn rejects_unsupported_file() {
+        assert!(!is_valid("file:///recordings/record.mp4"));
+        assert!(!is_valid("file:///recordings/record.mkv.part"));
+        assert!(!is_valid("file:///recordings/show1/"));
+        assert!(!is_valid("file:///recordings/show1"));
+    }
+
+    #[test]
+    fn rejects_file_with_host() {
+        assert!(!is_valid("file://host/recordings/record.flv"));
+    }
+}
+
+#[cfg(test)]
+mod extra_args_spec {
+    use url::Url;
+
+    use super::{Output, OutputDstUrl};
+
+    fn dst() -> OutputDstUrl {
+        OutputDstUrl::new(Url::parse("rtmp://example.com/live/stream").unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn accepts_regular_args() {
+        let args = vec!["-g".to_owned(), "60".to_owned()];
+
+        assert!(Output::validate_extra_args(&dst(), &args).is_ok());
+        assert!(Output::validate_extra_args(&dst(), &[]).is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_args() {
+        for arg in &["", "  "] {
+            let args = vec!["-g".to_owned(), (*arg).to_owned()];
+
+            assert!(Output::validate_extra_args(&dst(), &args).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_args_with_dst_url() {
+        let args = vec![
+            "-f".to_owned(),
+            "tee".to_owned(),
+            "rtmp://example.com/live/stream".to_owned(),
+        ];
+
+        assert!(Output::validate_extra_args(&dst(), &args).is_err());
+    }
+}
+
+#[cfg(test)]
+mod maintenance_notice_spec {
+    use super::State;
+
+    #[test]
+    fn round_trips_notice() {
+        let state = State::default();
+        assert_eq!(state.maintenance_notice.get_cloned(), None);
+
+        assert!(state.set_maintenance_notice(Some(
+            "  Restart at 22:00 UTC  ".to_owned()
+        )));
+        assert_eq!(
+            state.maintenance_notice.get_cloned().as_deref(),
+            Some("Restart at 22:00 UTC"),
+        );
+        assert!(!state
+            .set_maintenance_notice(Some("Restart at 22:00 UTC".to_owned())));
+
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: State = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            restored.maintenance_notice.get_cloned().as_deref(),
+            Some("Restart at 22:00 UTC"),
+        );
+    }
+
+    #[test]
+    fn clears_notice() {
+        let state = State::default();
+        let _ = state.set_maintenance_notice(Some("Maintenance".to_owned()));
+
+        assert!(state.set_maintenance_notice(None));
+        assert_eq!(state.maintenance_notice.get_cloned(), None);
+        assert!(!state.set_maintenance_notice(None));
+
+        let _ = state.set_maintenance_notice(Some("Maintenance".to_owned()));
+        assert!(state.set_maintenance_notice(Some("   ".to_owned())));
+        assert_eq!(state.maintenance_notice.get_cloned(), None);
+    }
+
+    #[test]
+    fn reads_state_without_notice() {
+        let state: State =
+            serde_json::from_str(r#"{"password_hash":null,"restreams":[]}"#)
+                .unwrap();
+
+        assert_eq!(state.maintenance_notice.get_cloned(), None);
+    }
+}
+
+#[cfg(test)]
+mod key_spec {
+    use url::Url;
+
+    use super::{
+        InputEndpointKind, InputKey, RestreamKey, DEFAULT_KEY_MAX_LEN,
+    };
+
+    const LONG_KEY: &str = "legacy.stream-name_with.dots-0123456789a";
+
+    #[test]
+    fn rejects_long_key_under_default_limit() {
+        assert_eq!(LONG_KEY.len(), 40);
+
+        let max = DEFAULT_KEY_MAX_LEN;
+        assert!(RestreamKey::new_with_len(LONG_KEY, max).is_none());
+        assert!(InputKey::new_with_len(LONG_KEY, max).is_none());
+    }
+
+    #[test]
+    fn accepts_long_key_under_raised_limit() {
+        assert!(RestreamKey::new_with_len(LONG_KEY, 40).is_some());
+        assert!(InputKey::new_with_len(LONG_KEY, 40).is_some());
+        assert!(RestreamKey::new_with_len(LONG_KEY, 39).is_none());
+    }
+
+    #[test]
+    fn rejects_unsafe_keys() {
+        for key in &["", ".", "..", ".hidden", "with/slash", "Upper", "sp ace"]
+        {
+            assert!(
+                RestreamKey::new_with_len(*key, 40).is_none(),
+                "allows '{}' key",
+                key,
+            );
+        }
+    }
+
+    #[test]
+    fn builds_correct_rtmp_url_for_dotted_keys() {
+        let restream = RestreamKey::new_with_len(LONG_KEY, 40).unwrap();
+        let input = InputKey::new_with_len("main.backup", 40).unwrap();
+
+        assert_eq!(
+            InputEndpointKind::Rtmp.rtmp_url(&restream, &input, None),
+            Url::parse(&format!(
+                "rtmp://127.0.0.1:1935/{}/main.backup",
+                LONG_KEY,
+            ))
+            .unwrap(),
+        );
+        assert_eq!(
+            InputEndpointKind::Hls
+                .rtmp_url(&restream, &input, None)
+                .path(),
+            format!("/{}", LONG_KEY),
+        );
+    }
+}
+
+#[cfg(test)]
+mod srs_vhost_spec {
+    use crate::spec;
+
+    use super::{InputEndpointKind, Restream, SrsVhost};
+
+    fn restream(vhost: Option<&str>) -> Restream {
+        let mut spec = spec::v1::Restream::with_key("test");
+        spec.srs_vhost = vhost.map(|v| SrsVhost::new(v).unwrap());
+        spec.input.endpoints.push(spec::v1::InputEndpoint {
+            kind: InputEndpointKind::Hls,
+            hls_encoding: None,
+        });
+        Restream::new(spec)
+    }
+
+    #[test]
+    fn uses_configured_vhost_in_srs_url() {
+        let r = restream(Some("live.example.com"));
+
+        assert_eq!(
+            r.main_input_rtmp_endpoint_url().as_str(),
+            "rtmp://127.0.0.1:1935/test?vhost=live.example.com/origin",
+        );
+    }
+
+    #[test]
+    fn uses_default_vhost_if_none() {
+        let r = restream(None);
+
+        assert_eq!(
+            r.main_input_rtmp_endpoint_url().as_str(),
+            "rtmp://127.0.0.1:1935/test/origin",
+        );
+    }
+
+    #[test]
+    fn always_uses_whip_vhost_for_whip_endpoint() {
+        let r = restream(Some("live.example.com"));
+
+        assert_eq!(
+            InputEndpointKind::Whip
+                .rtmp_url(&r.key, &r.input.key, r.srs_vhost.as_ref())
+                .as_str(),
+            "rtmp://127.0.0.1:1935/test?vhost=whip/origin",
+        );
+    }
+
+    #[test]
+    fn builds_whip_url_for_whip_endpoint_only() {
+        let r = restream(Some("live.example.com"));
+
+        assert_eq!(
+            InputEndpointKind::Whip
+                .whip_url(&r.key, &r.input.key)
+                .unwrap()
+                .as_str(),
+            "http://127.0.0.1:8002/rtc/v1/whip/\
+             ?app=test&stream=origin&vhost=whip",
+        );
+        assert!(InputEndpointKind::Rtmp
+            .whip_url(&r.key, &r.input.key)
+            .is_none());
+        assert!(InputEndpointKind::Hls
+            .whip_url(&r.key, &r.input.key)
+            .is_none());
+    }
+
+    #[test]
+    fn always_uses_hls_vhost_for_hls_endpoint() {
+        let r = restream(Some("live.example.com"));
+
+        assert_eq!(
+            InputEndpointKind::Hls
+                .rtmp_url(&r.key, &r.input.key, r.srs_vhost.as_ref())
+                .as_str(),
+            "rtmp://127.0.0.1:1935/test?vhost=hls/origin",
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_vhosts() {
+        for vhost in
+            &["", "hls", "whip", "__defaultVhost__", "-bad", "with/slash"]
+        {
+            assert!(SrsVhost::new(*vhost).is_none(), "allows '{}'", vhost);
+        }
+        assert!(SrsVhost::new("live.example.com").is_some());
+    }
+}
+
+#[cfg(test)]
+mod add_outputs_spec {
+    use crate::spec;
+
+    use super::{RestreamKey, State};
+
+    #[test]
+    fn adds_all_outputs() {
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("test")]);
+        let id = state.restreams.lock_ref()[0].id;
+
+        let ids = state
+            .add_outputs(
+                id,
+                vec![
+                    spec::v1::Output::with_dst("rtmp://example.com/live/one"),
+                    spec::v1::Output::with_dst("rtmp://example.com/live/two"),
+                    spec::v1::Output::with_dst("rtmp://example.com/live/three"),
+                ],
+            )
+            .unwrap()
+            .unwrap();
+
+        let restreams = state.restreams.lock_ref();
+        let outputs = &restreams[0].outputs;
+        assert_eq!(ids.len(), 3);
+        assert_eq!(outputs.iter().map(|o| o.id).collect::<Vec<_>>(), ids);
+    }
+
+    #[test]
+    fn adds_nothing_on_conflict() {
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("test")]);
+        let id = state.restreams.lock_ref()[0].id;
+        state
+            .add_output(
+                id,
+                spec::v1::Output::with_dst("rtmp://example.com/live/two"),
+            )
+            .unwrap()
+            .unwrap();
+
+        let res = state.add_outputs(
+            id,
+            vec![
+                spec::v1::Output::with_dst("rtmp://example.com/live/one"),
+                spec::v1::Output::with_dst("rtmp://example.com/live/two"),
+                spec::v1::Output::with_dst("rtmp://example.com/live/three"),
+            ],
+        );
+
+        assert!(res.is_err());
+        assert_eq!(state.restreams.lock_ref()[0].outputs.len(), 1);
+    }
+
+    #[test]
+    fn adds_nothing_on_duplicates_in_batch() {
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("test")]);
+        let id = state.restreams.lock_ref()[0].id;
+
+        let res = state.add_outputs(
+            id,
+            vec![
+                spec::v1::Output::with_dst("rtmp://example.com/live/one"),
+                spec::v1::Output::with_dst("rtmp://example.com/live/one"),
+            ],
+        );
+
+        assert!(res.is_err());
+        assert!(state.restreams.lock_ref()[0].outputs.is_empty());
+    }
+
+    fn with_mixins(dst: &str, srcs: &[&str]) -> spec::v1::Output {
+        let mut spec = spec::v1::Output::with_dst(dst);
+        spec.mixins = srcs
+            .iter()
+            .map(|src| spec::v1::Mixin::with_src(src))
+            .collect();
+        spec
+    }
+
+    #[test]
+    fn rejects_invalid_mixins() {
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("test")]);
+        let id = state.restreams.lock_ref()[0].id;
+
+        for srcs in &[
+            &["http://example.com/a.mp3", "http://example.com/a.mp3"][..],
+            &["ts://example.com/one", "ts://example.com/two"][..],
+        ] {
+            let spec = with_mixins("rtmp://example.com/live/one", srcs);
+            assert!(state.add_output(id, spec.clone()).is_err());
+            assert!(state.add_outputs(id, vec![spec]).is_err());
+        }
+        assert!(state.restreams.lock_ref()[0].outputs.is_empty());
+
+        let spec = with_mixins(
+            "rtmp://example.com/live/one",
+            &["http://example.com/a.mp3", "ts://example.com/one"],
+        );
+        assert!(state.add_output(id, spec).unwrap().is_some());
+    }
+
+    #[test]
+    fn applies_nothing_on_invalid_mixins() {
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("test")]);
+        let before = state.export().into_v1();
+
+        let mut new = before.clone();
+        new.restreams[0]
+            .outputs
+            .push(spec::v1::Output::with_dst("rtmp://example.com/a"));
+        new.restreams[0].outputs.push(with_mixins(
+            "rtmp://example.com/b",
+            &["http://example.com/a.mp3", "http://example.com/a.mp3"],
+        ));
+
+        assert!(state.apply(new.clone(), false).is_err());
+        assert!(state.apply(new.clone(), true).is_err());
+        assert_eq!(state.export().into_v1(), before);
+
+        let mut edited = new.restreams.remove(0);
+        edited.outputs.remove(0);
+        let id = state.restreams.lock_ref()[0].id;
+        assert!(state.edit_restream(id, edited).is_err());
+        assert_eq!(state.export().into_v1(), before);
+    }
+
+    #[test]
+    fn rejects_loopback_dst() {
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("test")]);
+        let id = state.restreams.lock_ref()[0].id;
+
+        for dst in &[
+            "rtmp://127.0.0.1/test/origin",
+            "rtmp://127.0.0.1:1935/test/origin",
+            "rtmp://localhost:1935/test?vhost=live/origin",
+        ] {
+            assert!(
+                state
+                    .add_output(id, spec::v1::Output::with_dst(dst))
+                    .is_err(),
+                "loopback {} is accepted",
+                dst,
+            );
+        }
+        assert!(state.restreams.lock_ref()[0].outputs.is_empty());
+
+        state
+            .add_output(
+                id,
+                spec::v1::Output::with_dst("rtmp://example.com/test/origin"),
+            )
+            .unwrap()
+            .unwrap();
+        let output_id = state.restreams.lock_ref()[0].outputs[0].id;
+        assert!(state
+            .edit_output(
+                id,
+                output_id,
+                spec::v1::Output::with_dst("rtmp://127.0.0.1:1935/test/origin"),
+            )
+            .is_err());
+
+        for dst in &[
+            "rtmp://127.0.0.1:1935/test/other",
+            "rtmp://127.0.0.1:1936/test/origin",
+        ] {
+            state
+                .add_output(id, spec::v1::Output::with_dst(dst))
+                .unwrap()
+                .unwrap();
+        }
+        assert_eq!(state.restreams.lock_ref()[0].outputs.len(), 3);
+    }
+
+    #[test]
+    fn rejects_loopback_dst_of_restream_specs() {
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("test")]);
+        let before = state.export().into_v1();
+
+        let looped = |key: &str, dst: &str| spec::v1::Restream {
+            outputs: vec![spec::v1::Output::with_dst(dst)],
+            ..spec::v1::Restream::with_key(key)
+        };
+
+        for replace in &[false, true] {
+            let mut new = before.clone();
+            new.restreams[0] =
+                looped("test", "rtmp://127.0.0.1:1935/test/origin");
+            assert!(state.apply(new, *replace).is_err());
+
+            let mut new = before.clone();
+            new.restreams
+                .push(looped("other", "rtmp://127.0.0.1:1935/test/origin"));
+            assert!(state.apply(new, *replace).is_err());
+        }
+        assert_eq!(state.export().into_v1(), before);
+
+        assert!(state
+            .add_restream(looped("other", "rtmp://127.0.0.1/other/origin"))
+            .is_err());
+        assert!(state
+            .add_restream(looped("other", "rtmp://127.0.0.1/test/origin"))
+            .is_err());
+        assert_eq!(state.export().into_v1(), before);
+
+        let mut new = before;
+        new.restreams[0].key = RestreamKey::new("renamed").unwrap();
+        new.restreams
+            .push(looped("other", "rtmp://127.0.0.1:1935/test/origin"));
+        state.apply(new, true).unwrap();
+        assert_eq!(state.restreams.lock_ref().len(), 2);
+    }
+
+    #[test]
+    fn feeds_siblings_only_from_local_srs() {
+        let state =
+            State::with_restreams(vec![spec::v1::Restream::with_key("test")]);
+        let id = state.restreams.lock_ref()[0].id;
+
+        let mut remote =
+            spec::v1::Output::with_dst("rtmp://example.com/live/clean");
+        remote.feeds_siblings = true;
+        assert!(state.add_output(id, remote).is_err());
+
+        let mut local =
+            spec::v1::Output::with_dst("rtmp://127.0.0.1:1935/test/clean");
+        local.feeds_siblings = true;
+        state.add_output(id, local).unwrap().unwrap();
+
+        let restreams = state.restreams.lock_ref();
+        assert!(restreams[0].is_feeder_stream("clean"));
+        assert!(!restreams[0].is_feeder_stream("origin"));
+    }
+}
+
 #[cfg(test)]
-mod volume_spec {
-    use super::Volume;
+mod mixin_reachability_spec {
+    use std::env;
+
+    use actix_web::{test, web, App, HttpResponse};
+    use tokio::fs;
+    use url::Url;
+    use uuid::Uuid;
+
+    use super::MixinSrcUrl;
+
+    fn mock_server() -> test::TestServer {
+        test::start(|| {
+            App::new().route(
+                "/track.mp3",
+                web::head().to(|| {
+                    HttpResponse::Ok().content_type("audio/mpeg").finish()
+                }),
+            )
+        })
+    }
+
+    fn mixin(url: &str) -> MixinSrcUrl {
+        MixinSrcUrl::new(Url::parse(url).unwrap()).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn accepts_reachable_url() {
+        let srv = mock_server();
+
+        let res = mixin(&srv.url("/track.mp3")).check_reachable().await;
+
+        assert!(res.is_ok(), "{:?}", res);
+    }
+
+    #[actix_rt::test]
+    async fn rejects_not_found_url() {
+        let srv = mock_server();
+
+        let res = mixin(&srv.url("/missing.mp3")).check_reachable().await;
+
+        assert!(res.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn skips_teamspeak_url() {
+        let res = mixin("ts://127.0.0.1:1/channel").check_reachable().await;
+
+        assert!(res.is_ok());
+    }
 
     #[test]
-    fn displays_as_fraction() {
-        for (input, expected) in &[
-            (1, "0.01"),
-            (10, "0.10"),
-            (200, "2.00"),
-            (107, "1.07"),
-            (170, "1.70"),
-            (1000, "10.00"),
+    fn validates_local_mp3_file() {
+        for url in &["file:///srv/jingles/intro.mp3", "file:///intro.mp3"] {
+            assert!(
+                MixinSrcUrl::validate(&Url::parse(url).unwrap()),
+                "rejects '{}'",
+                url,
+            );
+        }
+        for url in &[
+            "file:///srv/jingles/intro.wav",
+            "file:///srv/jingles/",
+            "file://remote.host/srv/jingles/intro.mp3",
         ] {
-            let actual = Volume::new(*input).unwrap().display_as_fraction();
-            assert_eq!(&actual, *expected);
+            assert!(
+                !MixinSrcUrl::validate(&Url::parse(url).unwrap()),
+                "allows '{}'",
+                url,
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn checks_local_file_exists() {
+        let dir = env::temp_dir().join(format!("ephyr-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("jingle.mp3");
+        fs::write(&path, b"ID3").await.unwrap();
+
+        let res = MixinSrcUrl::new(Url::from_file_path(&path).unwrap())
+            .unwrap()
+            .check_reachable()
+            .await;
+        assert!(res.is_ok(), "{:?}", res);
+
+        let res = MixinSrcUrl::new(
+            Url::from_file_path(dir.join("missing.mp3")).unwrap(),
+        )
+        .unwrap()
+        .check_reachable()
+        .await;
+        assert!(res.is_err());
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+}
+
+#[cfg(test)]
+mod copy_restream_spec {
+    use crate::spec;
+
+    use super::{RestreamKey, State};
+
+    #[test]
+    fn copies_with_fresh_ids_and_disabled_outputs() {
+        let state = State::with_restreams(vec![spec::v1::Restream {
+            outputs: vec![spec::v1::Output::with_dst(
+                "rtmp://example.com/live/stream",
+            )],
+            ..spec::v1::Restream::with_key("origin")
+        }]);
+        let id = state.restreams.lock_ref()[0].id;
+
+        assert_eq!(
+            state
+                .copy_restream(id, RestreamKey::new("copy").unwrap())
+                .unwrap(),
+            Some(()),
+        );
+
+        let restreams = state.restreams.lock_ref();
+        let (orig, copy) = (&restreams[0], &restreams[1]);
+        assert_eq!(copy.key, RestreamKey::new("copy").unwrap());
+        assert_ne!(copy.id, orig.id);
+        assert_ne!(copy.input.id, orig.input.id);
+        assert_eq!(copy.outputs.len(), 1);
+        assert_ne!(copy.outputs[0].id, orig.outputs[0].id);
+        assert_eq!(copy.outputs[0].dst, orig.outputs[0].dst);
+        assert!(!copy.outputs[0].enabled);
+        assert!(orig.outputs[0].enabled);
+    }
+
+    #[test]
+    fn errors_on_taken_key() {
+        let state = State::with_restreams(vec![spec::v1::Restream {
+            outputs: vec![spec::v1::Output::with_dst(
+                "rtmp://example.com/live/stream",
+            )],
+            ..spec::v1::Restream::with_key("origin")
+        }]);
+        let id = state.restreams.lock_ref()[0].id;
+
+        let res = state.copy_restream(id, RestreamKey::new("origin").unwrap());
+
+        assert!(res.is_err());
+        assert_eq!(state.restreams.lock_ref().len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod output_toggle_spec {
+    use crate::spec;
+
+    use super::State;
+
+    fn exported_enabled(state: &State) -> bool {
+        state.export().into_v1().restreams[0].outputs[0].enabled
+    }
+
+    #[test]
+    fn export_reflects_toggles() {
+        let state = State::with_restreams(vec![spec::v1::Restream {
+            outputs: vec![spec::v1::Output {
+                enabled: false,
+                ..spec::v1::Output::with_dst("rtmp://example.com/live/stream")
+            }],
+            ..spec::v1::Restream::with_key("origin")
+        }]);
+        let (restream_id, output_id) = {
+            let restreams = state.restreams.lock_ref();
+            (restreams[0].id, restreams[0].outputs[0].id)
+        };
+
+        assert_eq!(state.enable_output(output_id, restream_id), Some(true));
+        assert!(state.restreams.lock_ref()[0].outputs[0].enabled);
+        assert!(exported_enabled(&state));
+
+        assert_eq!(state.disable_output(output_id, restream_id), Some(true));
+        assert!(!state.restreams.lock_ref()[0].outputs[0].enabled);
+        assert!(!exported_enabled(&state));
+    }
+
+    #[test]
+    fn toggles_take_precedence_over_imports() {
+        let state = State::with_restreams(vec![spec::v1::Restream {
+            outputs: vec![spec::v1::Output {
+                enabled: false,
+                ..spec::v1::Output::with_dst("rtmp://example.com/live/stream")
+            }],
+            ..spec::v1::Restream::with_key("origin")
+        }]);
+        let (restream_id, output_id) = {
+            let restreams = state.restreams.lock_ref();
+            (restreams[0].id, restreams[0].outputs[0].id)
+        };
+        let stale = state.export().into_v1();
+
+        assert_eq!(state.enable_output(output_id, restream_id), Some(true));
+        state.apply(stale, false).unwrap();
+
+        assert!(state.restreams.lock_ref()[0].outputs[0].enabled);
+        assert!(exported_enabled(&state));
+    }
+}
+
+#[cfg(test)]
+mod output_transcoding_spec {
+    use crate::spec;
+
+    use super::{State, TranscodingProfile};
+
+    #[test]
+    fn sets_profile_of_rtmp_and_srt_outputs() {
+        for dst in &["rtmp://example.com/live/stream", "srt://example.com:9000"]
+        {
+            let state = State::with_restreams(vec![spec::v1::Restream {
+                outputs: vec![spec::v1::Output::with_dst(dst)],
+                ..spec::v1::Restream::with_key("test")
+            }]);
+            let (r_id, o_id) = {
+                let restreams = state.restreams.lock_ref();
+                (restreams[0].id, restreams[0].outputs[0].id)
+            };
+            let profile = Some(TranscodingProfile::Main);
+
+            assert_eq!(
+                state.set_output_transcoding(r_id, o_id, profile).unwrap(),
+                Some(true),
+                "transcoding of {} is not set",
+                dst,
+            );
+            assert_eq!(
+                state.set_output_transcoding(r_id, o_id, profile).unwrap(),
+                Some(false),
+            );
+            assert_eq!(
+                state.restreams.lock_ref()[0].outputs[0].transcoding,
+                profile,
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_profile_of_output_with_mixins() {
+        let state = State::with_restreams(vec![spec::v1::Restream {
+            outputs: vec![spec::v1::Output {
+                mixins: vec![spec::v1::Mixin::with_src(
+                    "https://example.com/music.mp3",
+                )],
+                ..spec::v1::Output::with_dst("rtmp://example.com/live/stream")
+            }],
+            ..spec::v1::Restream::with_key("test")
+        }]);
+        let (r_id, o_id) = {
+            let restreams = state.restreams.lock_ref();
+            (restreams[0].id, restreams[0].outputs[0].id)
+        };
+
+        let res = state.set_output_transcoding(
+            r_id,
+            o_id,
+            Some(TranscodingProfile::Main),
+        );
+
+        assert!(res.is_err());
+        assert_eq!(state.restreams.lock_ref()[0].outputs[0].transcoding, None);
+    }
+
+    #[test]
+    fn rejects_profile_of_non_rtmp_output() {
+        let state = State::with_restreams(vec![spec::v1::Restream {
+            outputs: vec![spec::v1::Output::with_dst("file:///record.flv")],
+            ..spec::v1::Restream::with_key("test")
+        }]);
+        let (r_id, o_id) = {
+            let restreams = state.restreams.lock_ref();
+            (restreams[0].id, restreams[0].outputs[0].id)
+        };
+
+        let res = state.set_output_transcoding(
+            r_id,
+            o_id,
+            Some(TranscodingProfile::Main),
+        );
+
+        assert!(res.is_err());
+        assert_eq!(state.restreams.lock_ref()[0].outputs[0].transcoding, None);
+        assert_eq!(
+            state.set_output_transcoding(r_id, o_id, None).unwrap(),
+            Some(false),
+        );
+    }
+}
+
+#[cfg(test)]
+mod label_spec {
+    use super::Label;
+
+    #[test]
+    fn accepts_commas() {
+        assert_eq!(
+            Label::new("Day 2, Finals").as_deref().map(String::as_str),
+            Some("Day 2, Finals"),
+        );
+    }
+
+    #[test]
+    fn rejects_control_characters() {
+        for label in &["Day 2\nFinals", "Day\t2", "Day 2\r", "\u{7}"] {
+            assert!(Label::new(*label).is_none(), "allows {:?}", label);
+        }
+    }
+
+    #[test]
+    fn limits_length() {
+        assert!(Label::new("").is_none());
+        assert!(Label::new("a".repeat(70)).is_some());
+        assert!(Label::new("a".repeat(71)).is_none());
+    }
+}
+
+#[cfg(test)]
+mod orphaned_sessions_spec {
+    use std::collections::HashSet;
+
+    use crate::spec;
+
+    use super::{Input, InputEndpointKind, InputKey, Status};
+
+    fn input_spec(key: &str) -> spec::v1::Input {
+        spec::v1::Input {
+            key: InputKey::new(key).unwrap(),
+            endpoints: vec![spec::v1::InputEndpoint {
+                kind: InputEndpointKind::Rtmp,
+                hls_encoding: None,
+            }],
+            src: None,
+            hls_ladder: vec![],
+            enabled: true,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn postpones_kicking_old_clients_on_key_change() {
+        let mut input = Input::new(input_spec("origin"));
+        input.endpoints[0].srs_publisher_id = Some(1.into());
+        let _ = input.endpoints[0].srs_player_ids.insert(2.into());
+        let _ = input.endpoints[0].srs_player_ids.insert(3.into());
+
+        input.apply(input_spec("renamed"));
+
+        assert!(input.endpoints[0].srs_publisher_id.is_none());
+        assert!(input.endpoints[0].srs_player_ids.is_empty());
+
+        let pending = input.endpoints[0].pending_kickoff.as_ref().unwrap();
+        assert_eq!(pending.key, InputKey::new("origin").unwrap());
+        assert_eq!(pending.publisher_id.as_ref().map(|id| **id), Some(1));
+        assert_eq!(
+            pending
+                .player_ids
+                .iter()
+                .map(|id| **id)
+                .collect::<HashSet<_>>(),
+            vec![2, 3].into_iter().collect(),
+        );
+    }
+
+    #[actix_rt::test]
+    async fn keeps_clients_if_key_unchanged() {
+        let mut input = Input::new(input_spec("origin"));
+        input.endpoints[0].srs_publisher_id = Some(1.into());
+        let _ = input.endpoints[0].srs_player_ids.insert(2.into());
+
+        input.apply(input_spec("origin"));
+
+        assert!(input.endpoints[0].pending_kickoff.is_none());
+        assert_eq!(
+            input.endpoints[0].srs_publisher_id.as_ref().map(|id| **id),
+            Some(1),
+        );
+        assert_eq!(input.endpoints[0].srs_player_ids.len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn skips_kickoff_if_input_comes_back() {
+        let mut input = Input::new(input_spec("origin"));
+        input.endpoints[0].srs_publisher_id = Some(1.into());
+        input.endpoints[0].status = Status::Online;
+
+        assert!(input.disable());
+        assert_eq!(input.endpoints[0].status, Status::Offline);
+        let since = input.endpoints[0].pending_kickoff.as_ref().unwrap().since;
+
+        assert!(input.enable());
+
+        assert!(input.endpoints[0].expire_kickoff(since).is_none());
+        assert_eq!(
+            input.endpoints[0].srs_publisher_id.as_ref().map(|id| **id),
+            Some(1),
+        );
+        assert_eq!(input.endpoints[0].status, Status::Online);
+    }
+
+    #[actix_rt::test]
+    async fn kicks_off_if_input_stays_disabled() {
+        let mut input = Input::new(input_spec("origin"));
+        input.endpoints[0].srs_publisher_id = Some(1.into());
+
+        assert!(input.disable());
+        let since = input.endpoints[0].pending_kickoff.as_ref().unwrap().since;
+
+        let expired = input.endpoints[0].expire_kickoff(since).unwrap();
+        assert_eq!(expired.publisher_id.as_ref().map(|id| **id), Some(1));
+        assert!(input.endpoints[0].pending_kickoff.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn revives_clients_if_key_returned() {
+        let mut input = Input::new(input_spec("origin"));
+        let _ = input.endpoints[0].srs_player_ids.insert(2.into());
+
+        input.apply(input_spec("renamed"));
+        input.apply(input_spec("origin"));
+
+        assert!(input.endpoints[0].pending_kickoff.is_none());
+        assert_eq!(input.endpoints[0].srs_player_ids.len(), 1);
+    }
+}
+
+#[cfg(test)]
+mod status_events_spec {
+    use uuid::Uuid;
+
+    use super::{RestreamKey, Status, StatusEvents};
+
+    fn events(capacity: usize, count: usize) -> StatusEvents {
+        let events = StatusEvents::with_capacity(capacity);
+        let key = RestreamKey::new("test").unwrap();
+        for _ in 0..count {
+            events.record(
+                &key,
+                Uuid::new_v4(),
+                Status::Offline,
+                Status::Online,
+            );
+        }
+        events
+    }
+
+    fn ids(
+        events: &StatusEvents,
+        limit: usize,
+        before: Option<i32>,
+    ) -> Vec<i32> {
+        events.page(limit, before).iter().map(|e| e.id).collect()
+    }
+
+    #[test]
+    fn evicts_oldest_on_wraparound() {
+        let events = events(3, 5);
+
+        assert_eq!(ids(&events, 10, None), vec![5, 4, 3]);
+    }
+
+    #[test]
+    fn pages_by_before_cursor() {
+        let events = events(10, 7);
+
+        assert_eq!(ids(&events, 3, None), vec![7, 6, 5]);
+        assert_eq!(ids(&events, 3, Some(5)), vec![4, 3, 2]);
+        assert_eq!(ids(&events, 3, Some(2)), vec![1]);
+        assert!(ids(&events, 3, Some(1)).is_empty());
+    }
+
+    #[test]
+    fn pages_after_wraparound() {
+        let events = events(4, 10);
+
+        assert_eq!(ids(&events, 2, Some(9)), vec![8, 7]);
+        assert!(ids(&events, 2, Some(7)).is_empty());
+    }
+
+    #[test]
+    fn records_transition() {
+        let events = StatusEvents::default();
+        let id = Uuid::new_v4();
+        events.record(
+            &RestreamKey::new("test").unwrap(),
+            id,
+            Status::Initializing,
+            Status::Online,
+        );
+
+        let page = events.page(10, None);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].restream_key.as_str(), "test");
+        assert_eq!(page[0].element_id, id);
+        assert_eq!(page[0].from, Status::Initializing);
+        assert_eq!(page[0].to, Status::Online);
+    }
+}
+
+#[cfg(test)]
+mod input_kind_spec {
+    use juniper::{
+        graphql_object, EmptyMutation, EmptySubscription, RootNode, Variables,
+    };
+
+    use crate::spec;
+
+    use super::{Input, InputEndpointKind, InputKey, InputSrcUrl};
+
+    struct Query(Vec<Input>);
+
+    #[graphql_object]
+    impl Query {
+        fn inputs(&self) -> &[Input] {
+            &self.0
+        }
+    }
+
+    fn input(key: &str, src: Option<spec::v1::InputSrc>) -> spec::v1::Input {
+        spec::v1::Input {
+            key: InputKey::new(key).unwrap(),
+            endpoints: vec![spec::v1::InputEndpoint {
+                kind: InputEndpointKind::Rtmp,
+                hls_encoding: None,
+            }],
+            src,
+            hls_ladder: vec![],
+            enabled: true,
+        }
+    }
+
+    fn remote(url: &str) -> spec::v1::InputSrc {
+        spec::v1::InputSrc::RemoteUrl(spec::v1::RemoteSrc {
+            url: InputSrcUrl::new(url.parse().unwrap()).unwrap(),
+            fallback_urls: vec![],
+        })
+    }
+
+    async fn resolve_kinds(inputs: Vec<spec::v1::Input>) -> Vec<String> {
+        let schema = RootNode::new(
+            Query(inputs.into_iter().map(Input::new).collect()),
+            EmptyMutation::<()>::new(),
+            EmptySubscription::<()>::new(),
+        );
+        let (res, errs) = juniper::execute(
+            "{ inputs { kind } }",
+            None,
+            &schema,
+            &Variables::new(),
+            &(),
+        )
+        .await
+        .unwrap();
+        assert!(errs.is_empty(), "errors: {:?}", errs);
+
+        res.as_object_value()
+            .and_then(|o| o.get_field_value("inputs"))
+            .and_then(|v| v.as_list_value())
+            .unwrap()
+            .iter()
+            .map(|i| {
+                i.as_object_value()
+                    .and_then(|o| o.get_field_value("kind"))
+                    .and_then(|v| v.as_string_value())
+                    .unwrap()
+                    .to_owned()
+            })
+            .collect()
+    }
+
+    #[actix_rt::test]
+    async fn resolves_push_and_pull_inputs() {
+        let kinds = resolve_kinds(vec![
+            input("push", None),
+            input("pull", Some(remote("rtmp://example.com/live/stream"))),
+        ])
+        .await;
+
+        assert_eq!(kinds, vec!["PUSH", "PULL"]);
+    }
+
+    #[actix_rt::test]
+    async fn resolves_failover_inputs_by_primary() {
+        let failover = |main| {
+            spec::v1::InputSrc::FailoverInputs(vec![
+                input("main", main),
+                input("backup", Some(remote("rtmp://example.com/live/bak"))),
+            ])
+        };
+        let kinds = resolve_kinds(vec![
+            input("push", Some(failover(None))),
+            input(
+                "pull",
+                Some(failover(Some(remote("rtmp://example.com/live/main")))),
+            ),
+        ])
+        .await;
+
+        assert_eq!(kinds, vec!["PUSH", "PULL"]);
+    }
+}
+
+#[cfg(test)]
+mod tune_volumes_spec {
+    use crate::spec;
+
+    use super::{MixinId, OutputId, RestreamId, State, Volume, VolumeChange};
+
+    #[test]
+    fn applies_all_changes_with_per_change_results() {
+        let state = State::with_restreams(vec![spec::v1::Restream {
+            outputs: vec![spec::v1::Output {
+                mixins: vec![spec::v1::Mixin::with_src(
+                    "https://example.com/music.mp3",
+                )],
+                ..spec::v1::Output::with_dst("rtmp://example.com/live/stream")
+            }],
+            ..spec::v1::Restream::with_key("test")
+        }]);
+        let id = state.restreams.lock_ref()[0].id;
+        let (output_id, mixin_id) = {
+            let restreams = state.restreams.lock_ref();
+            let output = &restreams[0].outputs[0];
+            (output.id, output.mixins[0].id)
+        };
+
+        let res = state.tune_volumes(
+            id,
+            &[
+                VolumeChange {
+                    output_id,
+                    mixin_id: None,
+                    volume: Volume::new(50).unwrap(),
+                },
+                VolumeChange {
+                    output_id,
+                    mixin_id: Some(mixin_id),
+                    volume: Volume::new(150).unwrap(),
+                },
+                VolumeChange {
+                    output_id,
+                    mixin_id: Some(mixin_id),
+                    volume: Volume::new(150).unwrap(),
+                },
+                VolumeChange {
+                    output_id,
+                    mixin_id: Some(MixinId::random()),
+                    volume: Volume::OFF,
+                },
+                VolumeChange {
+                    output_id: OutputId::random(),
+                    mixin_id: None,
+                    volume: Volume::OFF,
+                },
+            ],
+        );
+
+        assert_eq!(
+            res,
+            Some(vec![Some(true), Some(true), Some(false), None, None]),
+        );
+        let restreams = state.restreams.lock_ref();
+        let output = &restreams[0].outputs[0];
+        assert_eq!(output.volume, Volume::new(50).unwrap());
+        assert_eq!(output.mixins[0].volume, Volume::new(150).unwrap());
+    }
+
+    #[test]
+    fn returns_none_for_unknown_restream() {
+        let state = State::default();
+
+        assert_eq!(state.tune_volumes(RestreamId::random(), &[]), None);
+    }
+}
+
+#[cfg(test)]
+mod presets_spec {
+    use crate::spec;
+
+    use super::{Label, PresetId, State, Volume, VolumeChange};
+
+    fn volume_changes(state: &State) -> Vec<VolumeChange> {
+        let restreams = state.restreams.lock_ref();
+        let output = &restreams[0].outputs[0];
+        vec![
+            VolumeChange {
+                output_id: output.id,
+                mixin_id: None,
+                volume: Volume::new(30).unwrap(),
+            },
+            VolumeChange {
+                output_id: output.id,
+                mixin_id: Some(output.mixins[0].id),
+                volume: Volume::new(170).unwrap(),
+            },
+        ]
+    }
+
+    #[test]
+    fn adds_applies_and_removes() {
+        let state = State::with_restreams(vec![spec::v1::Restream {
+            outputs: vec![spec::v1::Output {
+                mixins: vec![spec::v1::Mixin::with_src(
+                    "https://example.com/music.mp3",
+                )],
+                ..spec::v1::Output::with_dst("rtmp://example.com/live/stream")
+            }],
+            ..spec::v1::Restream::with_key("test")
+        }]);
+        let id = state.restreams.lock_ref()[0].id;
+        let name = Label::new("Quiet").unwrap();
+
+        let preset_id = state
+            .add_preset(id, name.clone(), &volume_changes(&state))
+            .unwrap()
+            .unwrap();
+        assert!(
+            state.add_preset(id, name, &[]).is_err(),
+            "allows duplicate name",
+        );
+
+        assert_eq!(
+            state.apply_preset(id, preset_id),
+            Some(vec![Some(true), Some(true)]),
+        );
+        {
+            let restreams = state.restreams.lock_ref();
+            let output = &restreams[0].outputs[0];
+            assert_eq!(output.volume, Volume::new(30).unwrap());
+            assert_eq!(output.mixins[0].volume, Volume::new(170).unwrap());
+        }
+        assert_eq!(
+            state.apply_preset(id, preset_id),
+            Some(vec![Some(false), Some(false)]),
+        );
+        assert_eq!(state.apply_preset(id, PresetId::random()), None);
+
+        assert_eq!(state.remove_preset(preset_id, id), Some(()));
+        assert_eq!(state.remove_preset(preset_id, id), None);
+        assert!(state.restreams.lock_ref()[0].presets.is_empty());
+    }
+
+    #[test]
+    fn persists_via_export() {
+        let state = State::with_restreams(vec![spec::v1::Restream {
+            outputs: vec![spec::v1::Output {
+                mixins: vec![spec::v1::Mixin::with_src(
+                    "https://example.com/music.mp3",
+                )],
+                ..spec::v1::Output::with_dst("rtmp://example.com/live/stream")
+            }],
+            ..spec::v1::Restream::with_key("test")
+        }]);
+        let id = state.restreams.lock_ref()[0].id;
+        let _ = state
+            .add_preset(
+                id,
+                Label::new("Quiet").unwrap(),
+                &volume_changes(&state),
+            )
+            .unwrap()
+            .unwrap();
+
+        let spec = state.restreams.lock_ref()[0].export();
+        assert_eq!(spec.presets.len(), 1);
+        assert_eq!(spec.presets[0].volumes.len(), 2);
+
+        let imported = State::default();
+        imported.add_restream(spec.clone()).unwrap();
+        let restreams = imported.restreams.lock_ref();
+        let restream = &restreams[0];
+        assert_eq!(restream.presets.len(), 1);
+        let volumes = &restream.presets[0].volumes;
+        assert_eq!(volumes[0].output_id, restream.outputs[0].id);
+        assert_eq!(volumes[0].mixin_id, None);
+        assert_eq!(volumes[1].mixin_id, Some(restream.outputs[0].mixins[0].id));
+        assert_eq!(restream.export(), spec);
+    }
+}
+
+#[cfg(test)]
+mod revision_spec {
+    use std::{env, time::Duration};
+
+    use futures::{FutureExt as _, StreamExt as _};
+    use tokio::{fs, time};
+    use uuid::Uuid;
+
+    use super::{Revision, State};
+
+    #[actix_rt::test]
+    async fn increments_on_persisted_change() {
+        let dir = env::temp_dir().join(format!("ephyr-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).await.unwrap();
+        let file = dir.join("state.json");
+
+        let state = State::try_new(&file).await.unwrap();
+        time::delay_for(Duration::from_millis(100)).await;
+        let initial = state.revision.get();
+
+        assert!(state.set_maintenance_notice(Some("Restart".to_owned())));
+        time::delay_for(Duration::from_millis(100)).await;
+        let changed = state.revision.get();
+        assert!(changed > initial, "{:?} <= {:?}", changed, initial);
+
+        let persisted: State =
+            serde_json::from_slice(&fs::read(&file).await.unwrap()).unwrap();
+        assert_eq!(persisted.revision.get(), changed);
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+
+    #[test]
+    fn skips_initial_emission_of_known_revision() {
+        let state = State::default();
+        let mut updates = state.restreams_since(Some(state.revision.get()));
+
+        assert!(updates.next().now_or_never().is_none());
+
+        state.bump_revision();
+        assert_eq!(updates.next().now_or_never(), Some(Some(vec![])));
+    }
+
+    #[test]
+    fn emits_initially_on_unknown_revision() {
+        let state = State::default();
+
+        for since in &[None, Some(Revision::default().next())] {
+            let mut updates = state.restreams_since(*since);
+            assert_eq!(
+                updates.next().now_or_never(),
+                Some(Some(vec![])),
+                "since {:?}",
+                since,
+            );
         }
     }
 }
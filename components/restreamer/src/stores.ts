@@ -54,6 +54,13 @@ export class RestreamModalState {
    */
   prev_label: string | null = null;
 
+  /**
+   * [SRS] `vhost` of the edited `Restream`, preserved "as is" on editing.
+   *
+   * [SRS]: https://github.com/ossrs/srs
+   */
+  srs_vhost: string | null = null;
+
   /**
    * Indicator whether the `Restream` should pull a live stream from a remote
    * endpoint.
@@ -198,6 +205,7 @@ export class RestreamModal implements Writable<RestreamModalState> {
    *                    editing.
    * @param with_hls    Indicator the `Restream` has had HLS endpoint before
    *                    editing.
+   * @param srs_vhost   Current SRS `vhost` of the `Restream` before editing.
    */
   openEdit(
     id: string,
@@ -205,7 +213,8 @@ export class RestreamModal implements Writable<RestreamModalState> {
     label: string | null,
     pull_url: string | null,
     backup: string | boolean | null,
-    with_hls: boolean
+    with_hls: boolean,
+    srs_vhost: string | null
   ) {
     this.update((v) => {
       v.edit_id = id;
@@ -237,6 +246,8 @@ export class RestreamModal implements Writable<RestreamModalState> {
       v.prev_with_hls = with_hls;
       v.with_hls = v.prev_with_hls;
 
+      v.srs_vhost = srs_vhost;
+
       v.visible = true;
       return v;
     });
@@ -265,6 +276,8 @@ export class RestreamModal implements Writable<RestreamModalState> {
       v.label = '';
       v.prev_label = null;
 
+      v.srs_vhost = null;
+
       if (v.prev_is_pull !== null) {
         v.is_pull = false;
       }
@@ -351,6 +364,17 @@ export class OutputModalState {
    */
   prev_mix_urls: string[] | null = null;
 
+  /**
+   * Policy of how long a mixed live stream of the `Output` lasts.
+   */
+  mix_duration: string = 'LONGEST';
+
+  /**
+   * Previous value of `Output`'s mix duration policy before it has been edited
+   * in the [[`OutputModal`]].
+   */
+  prev_mix_duration: string | null = null;
+
   /**
    * List of multiple labels and RTMP URLs to be added in a comma-separated
    * format.
@@ -423,13 +447,16 @@ export class OutputModal implements Writable<OutputModalState> {
    * @param dst_url        Current destination URL of the `Output` before
    *                       editing.
    * @param mix_urls       Current mixing URLs of the `Output` before editing.
+   * @param mix_duration   Current mix duration policy of the `Output` before
+   *                       editing.
    */
   openEdit(
     restream_id: string,
     id: string,
     label: string | null,
     dst_url: string,
-    mix_urls: string[]
+    mix_urls: string[],
+    mix_duration: string
   ) {
     this.update((v) => {
       v.restream_id = restream_id;
@@ -444,6 +471,9 @@ export class OutputModal implements Writable<OutputModalState> {
       v.prev_mix_urls = mix_urls.map(sanitizeUrl);
       v.mix_urls = v.prev_mix_urls.map(identity);
 
+      v.prev_mix_duration = mix_duration;
+      v.mix_duration = mix_duration;
+
       v.multi = false;
       v.visible = true;
       return v;
@@ -517,6 +547,9 @@ export class OutputModal implements Writable<OutputModalState> {
       v.mix_urls = [];
       v.prev_mix_urls = null;
 
+      v.mix_duration = 'LONGEST';
+      v.prev_mix_duration = null;
+
       v.list = '';
       v.visible = false;
       return v;
@@ -10,14 +10,17 @@ use std::{
     pin::Pin,
     str,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc, Mutex,
     },
     task::{Context, Poll},
     time::Duration,
 };
 
-use backoff::{future::FutureOperation as _, ExponentialBackoff};
+use anyhow::anyhow;
+use backoff::{
+    backoff::Backoff, future::FutureOperation as _, ExponentialBackoff,
+};
 use byteorder::{BigEndian, ByteOrder as _};
 use derive_more::{Display, Error};
 use ephyr_log::log;
@@ -25,7 +28,8 @@ use futures::{
     future, ready, sink, FutureExt as _, Stream, StreamExt as _,
     TryFutureExt as _,
 };
-use once_cell::sync::Lazy;
+use juniper::GraphQLEnum;
+use once_cell::sync::{Lazy, OnceCell};
 use rand::Rng as _;
 use tokio::{
     io::{self, AsyncRead},
@@ -95,6 +99,9 @@ pub struct Input {
     /// Indicator whether the spawned [`AudioCapture`] is unable to recover from
     /// its last error, and so this [`Input`] should return an error too.
     is_conn_unrecoverable: Arc<AtomicBool>,
+
+    /// [`Diagnostics`] of the [`Connection`] established by this [`Input`].
+    diagnostics: Arc<Mutex<Diagnostics>>,
 }
 
 impl Input {
@@ -113,8 +120,13 @@ impl Input {
         Self::SAMPLE_RATE / 1000 * Self::FREQUENCY_MILLIS * Self::CHANNELS;
 
     /// Creates a new [`Input`] with the provided [`Config`].
+    ///
+    /// The given [`Diagnostics`] describe the [TeamSpeak] server and channel
+    /// the [`Config`] is built for, as [`Config`] doesn't expose them.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
     #[must_use]
-    pub fn new<C: Into<Config>>(cfg: C) -> Self {
+    pub fn new<C: Into<Config>>(cfg: C, mut diagnostics: Diagnostics) -> Self {
         let cfg = {
             use ephyr_log::Drain as _;
 
@@ -138,6 +150,7 @@ impl Input {
             }
             cfg
         };
+        diagnostics.name = cfg.get_name().to_owned();
 
         let lgr = ephyr_log::logger();
         Self {
@@ -150,37 +163,85 @@ impl Input {
             audio: Arc::new(Mutex::new(AudioHandler::new(lgr))),
             conn: None,
             is_conn_unrecoverable: Arc::new(AtomicBool::default()),
+            diagnostics: Arc::new(Mutex::new(diagnostics)),
         }
     }
 
+    /// Returns a shared handle to the [`Diagnostics`] of this [`Input`], which
+    /// remains up-to-date while this [`Input`] is alive.
+    #[inline]
+    #[must_use]
+    pub fn diagnostics(&self) -> Arc<Mutex<Diagnostics>> {
+        self.diagnostics.clone()
+    }
+
     /// Spawns an [`AudioCapture`] associated with this [`Input`], retrying it
     /// endlessly with an [`ExponentialBackoff`] if it fails in a recoverable
     /// way.
+    ///
+    /// The [`ExponentialBackoff`] starts over each time the [`Connection`] is
+    /// established, so a dropped [`Connection`] is re-established quickly.
     fn spawn_audio_capturing(&mut self) {
         let cfg = self.cfg.clone();
         let audio = self.audio.clone();
         let is_conn_unrecoverable = self.is_conn_unrecoverable.clone();
+        let diagnostics = self.diagnostics.clone();
+        let diagnostics_on_fail = self.diagnostics.clone();
+        let backoff = ReconnectBackoff::new(ExponentialBackoff {
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        });
+        let was_connected = backoff.was_connected.clone();
 
         let capturing = (move || {
-            AudioCapture::run(cfg.clone(), audio.clone())
-                .map_err(AudioCaptureError::into_backoff)
+            let (cfg, audio) = (cfg.clone(), audio.clone());
+            let diagnostics = diagnostics.clone();
+            let was_connected = was_connected.clone();
+            async move {
+                {
+                    let mut diagnostics = diagnostics.lock().unwrap();
+                    if diagnostics.begin_attempt() {
+                        log::info!(
+                            "Reconnecting to TeamSpeak server {} \
+                             (attempt {})...",
+                            diagnostics.host,
+                            diagnostics.reconnects,
+                        );
+                    }
+                }
+                let res = match AudioCapture::connect(cfg, audio) {
+                    Ok(capture) => {
+                        let diagnostics = diagnostics.clone();
+                        capture
+                            .on_connected(move || {
+                                diagnostics.lock().unwrap().state =
+                                    ConnectionState::Connected;
+                                was_connected.store(true, Ordering::SeqCst);
+                            })
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+                diagnostics.lock().unwrap().state = match &res {
+                    Err(AudioCaptureError::TooManyConnections(_)) => {
+                        ConnectionState::Queued
+                    }
+                    _ => ConnectionState::Disconnected,
+                };
+                res.map_err(AudioCaptureError::into_backoff)
+            }
         })
-        .retry_notify(
-            ExponentialBackoff {
-                max_elapsed_time: None,
-                ..ExponentialBackoff::default()
-            },
-            |err, dur| {
-                log::error!(
-                    "Backoff TeamSpeak server audio capturing for {} due to \
+        .retry_notify(backoff, |err, dur| {
+            log::error!(
+                "Backoff TeamSpeak server audio capturing for {} due to \
                      error: {}",
-                    humantime::format_duration(dur),
-                    err,
-                )
-            },
-        )
+                humantime::format_duration(dur),
+                err,
+            )
+        })
         .map_err(move |e| {
             log::error!("Cannot capture audio from TeamSpeak server: {}", e);
+            diagnostics_on_fail.lock().unwrap().state = ConnectionState::Failed;
             is_conn_unrecoverable.store(true, Ordering::SeqCst)
         });
 
@@ -262,6 +323,7 @@ impl fmt::Debug for Input {
             .field("audio", &"Arc<Mutex<AudioHandler>>")
             .field("conn", &self.conn)
             .field("is_conn_unrecoverable", &self.is_conn_unrecoverable)
+            .field("diagnostics", &self.diagnostics)
             .finish()
     }
 }
@@ -283,6 +345,94 @@ impl Drop for Input {
     }
 }
 
+/// Diagnostics of a [`Connection`] to [TeamSpeak] server established by an
+/// [`Input`].
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostics {
+    /// Host (with optional port) of the [TeamSpeak] server being connected to.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub host: String,
+
+    /// [TeamSpeak] channel being joined.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub channel: String,
+
+    /// Nickname the [`Input`] is represented with in the [TeamSpeak] channel.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub name: String,
+
+    /// Current [`ConnectionState`].
+    pub state: ConnectionState,
+
+    /// Number of reconnection attempts performed so far.
+    pub reconnects: u32,
+}
+
+impl Diagnostics {
+    /// Creates new [`Diagnostics`] of a not yet established [`Connection`] to
+    /// the given [TeamSpeak] `host` and `channel`.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[inline]
+    #[must_use]
+    pub fn new<H: Into<String>, C: Into<String>>(host: H, channel: C) -> Self {
+        Self {
+            host: host.into(),
+            channel: channel.into(),
+            name: String::new(),
+            state: ConnectionState::Idle,
+            reconnects: 0,
+        }
+    }
+
+    /// Marks a new connection attempt being started, counting it as a
+    /// reconnect if it's not the first one.
+    ///
+    /// Attempts waiting for [`ConnectionsLimit`] are not counted as reconnects.
+    ///
+    /// Returns `true` if the started attempt is a reconnect.
+    fn begin_attempt(&mut self) -> bool {
+        let is_reconnect = !matches!(
+            self.state,
+            ConnectionState::Idle | ConnectionState::Queued
+        );
+        if is_reconnect {
+            self.reconnects = self.reconnects.saturating_add(1);
+        }
+        self.state = ConnectionState::Connecting;
+        is_reconnect
+    }
+}
+
+/// State of a [`Connection`] to [TeamSpeak] server.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Clone, Copy, Debug, Eq, GraphQLEnum, PartialEq)]
+pub enum ConnectionState {
+    /// Connection hasn't been started yet, as no audio was requested.
+    Idle,
+
+    /// Connection waits for the [`ConnectionsLimit`] to allow it.
+    Queued,
+
+    /// Connection is being established.
+    Connecting,
+
+    /// Connection is established and audio is being captured.
+    Connected,
+
+    /// Connection is lost and is going to be re-established.
+    Disconnected,
+
+    /// Connection failed permanently and won't be re-established.
+    Failed,
+}
+
 /// Possible errors of reading [`Input`].
 #[derive(Debug, Display, Error)]
 pub enum InputError {
@@ -324,21 +474,48 @@ pub struct AudioCapture {
     ///
     /// [TeamSpeak]: https://teamspeak.com
     audio: Arc<Mutex<AudioHandler>>,
+
+    /// [`ConnectionPermit`] occupied by the [`AudioCapture::conn`] until it's
+    /// fully disconnected.
+    permit: Option<ConnectionPermit>,
+
+    /// Callback to be invoked once the [`AudioCapture::conn`] completes its
+    /// handshake with [TeamSpeak] server.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    on_connected: Option<Box<dyn FnOnce() + Send>>,
 }
 
 impl AudioCapture {
     /// Creates new [`AudioCapture`] from the given [`Connection`] and for
-    /// the given [`AudioHandler`].
+    /// the given [`AudioHandler`], occupying the given [`ConnectionPermit`].
     #[inline]
     #[must_use]
-    pub fn new(conn: Connection, audio: Arc<Mutex<AudioHandler>>) -> Self {
+    pub fn new(
+        conn: Connection,
+        audio: Arc<Mutex<AudioHandler>>,
+        permit: ConnectionPermit,
+    ) -> Self {
         audio.lock().unwrap().reset();
         Self {
             conn: ManuallyDrop::new(conn),
             audio,
+            permit: Some(permit),
+            on_connected: None,
         }
     }
 
+    /// Sets the given callback to be invoked once the [`Connection`] of this
+    /// [`AudioCapture`] completes its handshake with [TeamSpeak] server.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[inline]
+    #[must_use]
+    pub fn on_connected<F: FnOnce() + Send + 'static>(mut self, f: F) -> Self {
+        self.on_connected = Some(Box::new(f));
+        self
+    }
+
     /// Generates a new random HWID (hardware identification string).
     #[must_use]
     pub fn new_hwid() -> String {
@@ -371,8 +548,12 @@ impl AudioCapture {
     /// Generates new HWID (hardware identification string) to uniquely
     /// distinguish this [`AudioCapture`] for [TeamSpeak] server.
     ///
+    /// Doesn't connect to [TeamSpeak] server if the global
+    /// [`ConnectionsLimit`] is reached already.
+    ///
     /// # Errors
     ///
+    /// - If the global [`ConnectionsLimit`] is reached;
     /// - If receiving audio from [TeamSpeak] server fails;
     /// - If processing received audio packets with [`AudioHandler`] fails.
     ///
@@ -383,12 +564,37 @@ impl AudioCapture {
         cfg: Config,
         audio: Arc<Mutex<AudioHandler>>,
     ) -> Result<(), AudioCaptureError> {
+        Self::connect(cfg, audio)?.await
+    }
+
+    /// Initiates a new [`Connection`] to [TeamSpeak] server using the given
+    /// [`Config`], and creates a new [`AudioCapture`] on top of it for the
+    /// given [`AudioHandler`].
+    ///
+    /// Generates new HWID (hardware identification string) to uniquely
+    /// distinguish this [`AudioCapture`] for [TeamSpeak] server.
+    ///
+    /// # Errors
+    ///
+    /// - If the global [`ConnectionsLimit`] is reached;
+    /// - If initializing the [`Connection`] fails.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub fn connect(
+        cfg: Config,
+        audio: Arc<Mutex<AudioHandler>>,
+    ) -> Result<Self, AudioCaptureError> {
+        let limit = ConnectionsLimit::global();
+        let permit = limit
+            .try_acquire()
+            .ok_or_else(|| AudioCaptureError::TooManyConnections(limit.max))?;
+
         log::debug!("Connecting to TeamSpeak server...");
         let conn = cfg
             .hardware_id(Self::new_hwid())
             .connect()
             .map_err(AudioCaptureError::InitializationFailed)?;
-        AudioCapture::new(conn, audio).await
+        Ok(AudioCapture::new(conn, audio, permit))
     }
 }
 
@@ -405,14 +611,21 @@ impl Future for AudioCapture {
         use AudioCaptureError as E;
 
         loop {
-            let audio_packet =
-                match ready!(Pin::new(&mut self.conn.events()).poll_next(cx))
-                    .ok_or_else(|| E::UnexpectedFinish)?
-                    .map_err(E::ConnectionFailed)?
-                {
-                    StreamItem::Audio(packet) => packet,
-                    _ => continue,
-                };
+            let item = ready!(Pin::new(&mut self.conn.events()).poll_next(cx))
+                .ok_or_else(|| E::UnexpectedFinish)?
+                .map_err(E::ConnectionFailed)?;
+
+            // `Connection::get_state()` fails until the handshake is done.
+            if self.conn.get_state().is_ok() {
+                if let Some(on_connected) = self.on_connected.take() {
+                    on_connected();
+                }
+            }
+
+            let audio_packet = match item {
+                StreamItem::Audio(packet) => packet,
+                _ => continue,
+            };
 
             let member_id = match audio_packet.data().data() {
                 AudioData::S2C { from, .. }
@@ -451,7 +664,54 @@ impl Drop for AudioCapture {
         // This is totally safe, because `self.conn` field is guaranteed to be
         // never used again later, so `ManuallyDrop` won't be touched again.
         #[allow(unsafe_code)]
-        spawn_disconnect(unsafe { ManuallyDrop::take(&mut self.conn) });
+        spawn_disconnect(
+            unsafe { ManuallyDrop::take(&mut self.conn) },
+            self.permit.take(),
+        );
+    }
+}
+
+/// [`Backoff`] of reconnecting [`AudioCapture`] to [TeamSpeak] server, which
+/// starts over once a [`Connection`] has been established successfully.
+///
+/// Without this, a long-living [`Connection`] dropped by [TeamSpeak] server
+/// (on its restart, for example) would be reconnected only after the maximum
+/// interval accumulated by all the previous failures.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Debug)]
+struct ReconnectBackoff<B> {
+    /// Underlying [`Backoff`] providing the actual intervals.
+    inner: B,
+
+    /// Indicator whether a [`Connection`] has been established since the
+    /// last interval was given.
+    was_connected: Arc<AtomicBool>,
+}
+
+impl<B: Backoff> ReconnectBackoff<B> {
+    /// Wraps the given [`Backoff`] into a new [`ReconnectBackoff`].
+    #[inline]
+    #[must_use]
+    fn new(inner: B) -> Self {
+        Self {
+            inner,
+            was_connected: Arc::new(AtomicBool::default()),
+        }
+    }
+}
+
+impl<B: Backoff> Backoff for ReconnectBackoff<B> {
+    #[inline]
+    fn reset(&mut self) {
+        self.inner.reset()
+    }
+
+    fn next_backoff(&mut self) -> Option<Duration> {
+        if self.was_connected.swap(false, Ordering::SeqCst) {
+            self.inner.reset();
+        }
+        self.inner.next_backoff()
     }
 }
 
@@ -469,6 +729,16 @@ pub enum AudioCaptureError {
     )]
     InitializationFailed(tsclientlib::Error),
 
+    /// Global [`ConnectionsLimit`] of concurrent [TeamSpeak] connections is
+    /// reached, so no more connections can be established at the moment.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[display(
+        fmt = "Too many concurrent TeamSpeak connections (limit is {})",
+        _0
+    )]
+    TooManyConnections(#[error(not(source))] usize),
+
     /// Establishing connection with [TeamSpeak] server failed.
     ///
     /// [TeamSpeak]: https://teamspeak.com
@@ -508,7 +778,8 @@ impl AudioCaptureError {
 
         let is_permanent = match &self {
             Self::InitializationFailed(_) => true,
-            Self::ConnectionFailed(_)
+            Self::TooManyConnections(_)
+            | Self::ConnectionFailed(_)
             | Self::UnexpectedFinish
             | Self::UnexpectedC2sPacket => false,
             Self::DecodingFailed(err) => {
@@ -523,6 +794,178 @@ impl AudioCaptureError {
     }
 }
 
+/// Default prefix of [TeamSpeak] client names, used if no other has been set
+/// via [`set_name_prefix()`].
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[allow(clippy::non_ascii_literal)]
+pub const DEFAULT_NAME_PREFIX: &str = "🤖";
+
+/// Global prefix of [TeamSpeak] client names used by this application.
+///
+/// [TeamSpeak]: https://teamspeak.com
+static NAME_PREFIX: OnceCell<String> = OnceCell::new();
+
+/// Returns the global prefix of [TeamSpeak] client names used by this
+/// application.
+///
+/// If it hasn't been set via [`set_name_prefix()`], then
+/// [`DEFAULT_NAME_PREFIX`] is used.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[must_use]
+pub fn name_prefix() -> &'static str {
+    NAME_PREFIX
+        .get_or_init(|| DEFAULT_NAME_PREFIX.to_owned())
+        .as_str()
+}
+
+/// Sets the global prefix of [TeamSpeak] client names used by this
+/// application.
+///
+/// # Errors
+///
+/// If the global prefix has been set or used already.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[inline]
+pub fn set_name_prefix(prefix: String) -> anyhow::Result<()> {
+    NAME_PREFIX.set(prefix).map_err(|_| {
+        anyhow!("teamspeak::NAME_PREFIX has been initialized already")
+    })
+}
+
+/// Global [`ConnectionsLimit`] of concurrent [TeamSpeak] connections used by
+/// this application.
+///
+/// [TeamSpeak]: https://teamspeak.com
+static CONNECTIONS_LIMIT: OnceCell<Arc<ConnectionsLimit>> = OnceCell::new();
+
+/// Limit of concurrent [TeamSpeak] connections.
+///
+/// Once the limit is reached, new [`AudioCapture`]s are not connected to
+/// [TeamSpeak] server, but are queued with an [`ExponentialBackoff`] until some
+/// [`ConnectionPermit`] is released.
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Debug)]
+pub struct ConnectionsLimit {
+    /// Maximum number of concurrent [TeamSpeak] connections.
+    ///
+    /// Zero value means no limit at all.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    max: usize,
+
+    /// Number of [TeamSpeak] connections being active at the moment.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    active: AtomicUsize,
+}
+
+impl ConnectionsLimit {
+    /// Default maximum number of concurrent [TeamSpeak] connections.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    pub const DEFAULT_MAX: usize = 100;
+
+    /// Creates a new [`ConnectionsLimit`] allowing the given `max` number of
+    /// concurrent [TeamSpeak] connections.
+    ///
+    /// Zero `max` means no limit at all.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[inline]
+    #[must_use]
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the global instance of [`ConnectionsLimit`].
+    ///
+    /// If it hasn't been set via [`ConnectionsLimit::set_global()`], then
+    /// [`ConnectionsLimit::DEFAULT_MAX`] is used.
+    #[must_use]
+    pub fn global() -> &'static Arc<Self> {
+        CONNECTIONS_LIMIT.get_or_init(|| Arc::new(Self::new(Self::DEFAULT_MAX)))
+    }
+
+    /// Sets the global instance of [`ConnectionsLimit`].
+    ///
+    /// # Errors
+    ///
+    /// If the global instance has been set or used already.
+    #[inline]
+    pub fn set_global(self) -> anyhow::Result<()> {
+        CONNECTIONS_LIMIT.set(Arc::new(self)).map_err(|_| {
+            anyhow!("teamspeak::ConnectionsLimit has been initialized already")
+        })
+    }
+
+    /// Returns maximum number of concurrent [TeamSpeak] connections allowed
+    /// by this [`ConnectionsLimit`].
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[inline]
+    #[must_use]
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Returns number of [TeamSpeak] connections being active at the moment.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[inline]
+    #[must_use]
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Tries to occupy a [`ConnectionPermit`] for a new [TeamSpeak]
+    /// connection.
+    ///
+    /// Returns [`None`] if this [`ConnectionsLimit`] is reached already.
+    ///
+    /// [TeamSpeak]: https://teamspeak.com
+    #[must_use]
+    pub fn try_acquire(self: &Arc<Self>) -> Option<ConnectionPermit> {
+        let mut active = self.active.load(Ordering::SeqCst);
+        loop {
+            if self.max != 0 && active >= self.max {
+                return None;
+            }
+            match self.active.compare_exchange_weak(
+                active,
+                active + 1,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return Some(ConnectionPermit(self.clone())),
+                Err(actual) => active = actual,
+            }
+        }
+    }
+}
+
+/// Permit of a single [TeamSpeak] connection occupied in a
+/// [`ConnectionsLimit`].
+///
+/// Releases itself back to the [`ConnectionsLimit`] on [`Drop`].
+///
+/// [TeamSpeak]: https://teamspeak.com
+#[derive(Debug)]
+pub struct ConnectionPermit(Arc<ConnectionsLimit>);
+
+impl Drop for ConnectionPermit {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = self.0.active.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 /// Collection of [`JoinHandle`]s being awaited for completion at the moment.
 ///
 /// See [`finish_all_disconnects`]'s documentation for details.
@@ -551,9 +994,12 @@ fn spawn_waiter(waiter: JoinHandle<()>) {
 /// [`tokio::spawn`]s disconnection of the given [`Connection`] and tracks its
 /// completion via [`IN_PROGRESS_DISCONNECTS`].
 ///
+/// The given [`ConnectionPermit`] is released only once the disconnection
+/// completes.
+///
 /// All disconnects can be awaited for completion via
 /// [`finish_all_disconnects()`].
-fn spawn_disconnect(mut conn: Connection) {
+fn spawn_disconnect(mut conn: Connection, permit: Option<ConnectionPermit>) {
     let mut disconnects = IN_PROGRESS_DISCONNECTS.lock().unwrap();
 
     let id = loop {
@@ -588,6 +1034,7 @@ fn spawn_disconnect(mut conn: Connection) {
                         drop(
                             IN_PROGRESS_DISCONNECTS.lock().unwrap().remove(&id),
                         );
+                        drop(permit);
                     },
                 ),
             ),
@@ -596,7 +1043,10 @@ fn spawn_disconnect(mut conn: Connection) {
 }
 
 /// Awaits for all disconnections from [TeamSpeak] servers happening at the
-/// moment to be completed.
+/// moment to be completed, but no longer than the given `timeout`.
+///
+/// Disconnections not completed within the `timeout` are abandoned and logged,
+/// so an unresponsive [TeamSpeak] server cannot hang the shutdown.
 ///
 /// Call this function __before__ shutting down the [`tokio::runtime`],
 /// otherwise disconnects won't proceed normally.
@@ -606,7 +1056,7 @@ fn spawn_disconnect(mut conn: Connection) {
 ///
 /// [TeamSpeak]: https://teamspeak.com
 /// [1]: https://github.com/tokio-rs/tokio/issues/2053
-pub async fn finish_all_disconnects() {
+pub async fn finish_all_disconnects(timeout: Duration) {
     let disconnects = {
         IN_PROGRESS_DISCONNECTS
             .lock()
@@ -616,5 +1066,186 @@ pub async fn finish_all_disconnects() {
             .collect::<Vec<_>>()
     };
 
-    drop(future::join_all(disconnects).await);
+    let unfinished = future::join_all(
+        disconnects
+            .into_iter()
+            .map(|hndl| time::timeout(timeout, hndl)),
+    )
+    .await
+    .into_iter()
+    .filter(Result::is_err)
+    .count();
+    if unfinished > 0 {
+        log::warn!(
+            "{} TeamSpeak connection(s) didn't disconnect cleanly within {}",
+            unfinished,
+            humantime::format_duration(timeout),
+        );
+    }
+}
+
+#[cfg(test)]
+mod disconnects_spec {
+    use std::time::{Duration, Instant};
+
+    use futures::future;
+
+    use super::{finish_all_disconnects, spawn_waiter};
+
+    #[actix_rt::test]
+    async fn completes_within_timeout_on_hanging_disconnect() {
+        spawn_waiter(tokio::spawn(future::pending()));
+        spawn_waiter(tokio::spawn(future::ready(())));
+
+        let timeout = Duration::from_millis(200);
+        let started = Instant::now();
+        finish_all_disconnects(timeout).await;
+        let elapsed = started.elapsed();
+
+        assert!(elapsed >= timeout, "finished too early: {:?}", elapsed);
+        assert!(
+            elapsed < timeout + Duration::from_secs(1),
+            "finished too late: {:?}",
+            elapsed,
+        );
+    }
+}
+
+#[cfg(test)]
+mod connections_limit_spec {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Barrier,
+        },
+        thread,
+    };
+
+    use super::ConnectionsLimit;
+
+    #[test]
+    fn rejects_permits_beyond_limit() {
+        let limit = Arc::new(ConnectionsLimit::new(2));
+
+        let first = limit.try_acquire();
+        let second = limit.try_acquire();
+        assert!(first.is_some());
+        assert!(second.is_some());
+        assert!(limit.try_acquire().is_none());
+        assert_eq!(limit.active(), 2);
+
+        drop(first);
+        assert_eq!(limit.active(), 1);
+        assert!(limit.try_acquire().is_some());
+        assert_eq!(limit.active(), 1);
+    }
+
+    #[test]
+    fn never_exceeds_limit_concurrently() {
+        const MAX: usize = 3;
+        const THREADS: usize = 16;
+
+        let limit = Arc::new(ConnectionsLimit::new(MAX));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(THREADS));
+
+        let handles = (0..THREADS)
+            .map(|_| {
+                let (limit, peak, barrier) =
+                    (limit.clone(), peak.clone(), barrier.clone());
+                thread::spawn(move || {
+                    let _ = barrier.wait();
+                    for _ in 0..1000 {
+                        if let Some(permit) = limit.try_acquire() {
+                            let _ = peak
+                                .fetch_max(limit.active(), Ordering::SeqCst);
+                            drop(permit);
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        assert!(peak.load(Ordering::SeqCst) <= MAX);
+        assert!(peak.load(Ordering::SeqCst) > 0);
+        assert_eq!(limit.active(), 0);
+    }
+
+    #[test]
+    fn zero_max_means_no_limit() {
+        let limit = Arc::new(ConnectionsLimit::new(0));
+
+        let permits = (0..500)
+            .map(|_| limit.try_acquire())
+            .collect::<Option<Vec<_>>>();
+
+        assert!(permits.is_some());
+        assert_eq!(limit.active(), 500);
+    }
+}
+
+#[cfg(test)]
+mod reconnect_spec {
+    use std::{sync::atomic::Ordering, time::Duration};
+
+    use backoff::{backoff::Backoff as _, ExponentialBackoff};
+
+    use super::{ConnectionState, Diagnostics, ReconnectBackoff};
+
+    fn backoff() -> ReconnectBackoff<ExponentialBackoff> {
+        ReconnectBackoff::new(ExponentialBackoff {
+            initial_interval: Duration::from_millis(100),
+            randomization_factor: 0.0,
+            multiplier: 2.0,
+            max_interval: Duration::from_secs(1),
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        })
+    }
+
+    #[test]
+    fn grows_interval_while_failing() {
+        let mut backoff = backoff();
+
+        let intervals: Vec<_> =
+            (0..5).map(|_| backoff.next_backoff().unwrap()).collect();
+        assert_eq!(
+            intervals,
+            [100, 200, 400, 800, 1000]
+                .iter()
+                .copied()
+                .map(Duration::from_millis)
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn starts_over_once_connected() {
+        let mut backoff = backoff();
+        for _ in 0..5 {
+            let _ = backoff.next_backoff();
+        }
+
+        backoff.was_connected.store(true, Ordering::SeqCst);
+        assert_eq!(backoff.next_backoff(), Some(Duration::from_millis(100)));
+        assert_eq!(backoff.next_backoff(), Some(Duration::from_millis(200)));
+    }
+
+    #[test]
+    fn counts_reconnects_only_after_being_connected() {
+        let mut diagnostics = Diagnostics::new("ts.example.com", "channel");
+
+        assert!(!diagnostics.begin_attempt(), "first attempt is reconnect");
+        diagnostics.state = ConnectionState::Queued;
+        assert!(!diagnostics.begin_attempt(), "queued attempt is reconnect");
+        diagnostics.state = ConnectionState::Connected;
+        assert!(diagnostics.begin_attempt());
+        diagnostics.state = ConnectionState::Disconnected;
+        assert!(diagnostics.begin_attempt());
+
+        assert_eq!(diagnostics.reconnects, 2);
+    }
 }
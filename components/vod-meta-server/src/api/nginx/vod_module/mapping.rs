@@ -60,6 +60,18 @@ pub struct Set {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub segment_duration: Option<MillisDuration>,
 
+    /// Absolute time of the first segment of this [`Set`], in milliseconds
+    /// [since the epoch][1].
+    ///
+    /// Used by [`nginx-vod-module`][2] to align segments of continuous live
+    /// streams (when [`Set::discontinuity`] is `false`). Must not change
+    /// during the whole stream.
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/Unix_time
+    /// [2]: https://github.com/kaltura/nginx-vod-module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_base_time: Option<MillisDateTime>,
+
     /// Index of the first [`Clip`] in the playlist of this [`Set`].
     ///
     /// Mandatory for non-continuous live streams that mix videos having
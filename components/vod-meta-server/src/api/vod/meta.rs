@@ -3,17 +3,23 @@
 //! [VOD]: https://en.wikipedia.org/wiki/Video_on_demand
 
 use std::{
+    borrow::Cow,
     collections::{HashMap, HashSet},
     time::Duration,
 };
 
-use chrono::{FixedOffset as TimeZone, Weekday};
+use chrono::{DateTime, FixedOffset as TimeZone, Utc, Weekday};
 use ephyr_serde::{timelike, timezone};
 use isolang::Language;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use url::Url;
 
-pub use crate::vod::meta::state::{PlaylistSlug, Resolution, SegmentDuration};
+pub use crate::{
+    api::nginx::vod_module::mapping::PlaylistType,
+    vod::meta::state::{
+        PlaylistSlug, Resolution, ResolutionFallback, SegmentDuration,
+    },
+};
 
 /// Set of [`Playlist`]s to be provided th the server.
 pub type Request = HashMap<PlaylistSlug, Playlist>;
@@ -53,6 +59,59 @@ pub struct Playlist {
     #[serde(default, skip_serializing_if = "HashSet::is_empty")]
     pub resolutions: HashSet<Resolution>,
 
+    /// Whitelist of [`Clip`]'s [`Resolution`]s allowed to be provided by this
+    /// [`Playlist`].
+    ///
+    /// Unlike [`Playlist::resolutions`], [`Clip`]s are not required to have
+    /// all of them, and [`Playlist::resolution_fallback`] defines what happens
+    /// to the [`Clip`]s missing some.
+    ///
+    /// If not specified or empty then all available [`Clip`]'s [`Resolution`]s
+    /// are allowed.
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub allowed_resolutions: HashSet<Resolution>,
+
+    /// Policy of what to do with [`Clip`]s missing some of the
+    /// [`Playlist::allowed_resolutions`].
+    ///
+    /// If not specified then [`ResolutionFallback::ReduceQuality`] is used.
+    #[serde(default)]
+    pub resolution_fallback: ResolutionFallback,
+
+    /// Absolute time to align segments of this [`Playlist`] to.
+    ///
+    /// Passed as is to the `segmentBaseTime` of the produced
+    /// [`nginx-vod-module`][1] mapping. Not specified by default.
+    ///
+    /// [1]: https://github.com/kaltura/nginx-vod-module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_base_time: Option<DateTime<Utc>>,
+
+    /// Type of the [`nginx-vod-module`][1] playlist produced for this
+    /// [`Playlist`].
+    ///
+    /// If not specified then [`PlaylistType::Live`] is used.
+    ///
+    /// [1]: https://github.com/kaltura/nginx-vod-module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playlist_type: Option<PlaylistType>,
+
+    /// Indicator whether [`Clip`]s of this [`Playlist`] have different media
+    /// parameters.
+    ///
+    /// Set to `false` only if all the [`Clip`]s were transcoded with exactly
+    /// the same parameters. If not specified then `true` is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discontinuity: Option<bool>,
+
+    /// Optional [`Clip`] to be looped through any [`Weekday`] having no
+    /// [`Clip`]s, so the stream never goes dark.
+    ///
+    /// Its duration has to be a fraction of 24 hours, the same way as the
+    /// total duration of [`Clip`]s in a single [`Weekday`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filler: Option<Clip>,
+
     /// [`Clip`]s which form this [`Playlist`], distributed by [`Weekday`]s.
     ///
     /// The total duration of all [`Clip`]s in the one [`Weekday`] hasn't to be
@@ -66,6 +125,25 @@ pub struct Playlist {
     pub clips: HashMap<Weekday, Vec<Clip>>,
 }
 
+/// Request to clone an existing [`Playlist`] under a new [`PlaylistSlug`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClonePlaylist {
+    /// [`PlaylistSlug`] to clone the [`Playlist`] under.
+    pub slug: PlaylistSlug,
+
+    /// Optional timezone to override the cloned [`Playlist`]'s one with.
+    #[serde(
+        default,
+        with = "timezone::opt",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tz: Option<TimeZone>,
+
+    /// Optional language to override the cloned [`Playlist`]'s one with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<Language>,
+}
+
 /// Clip in a [`Playlist`].
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Clip {
@@ -78,15 +156,129 @@ pub struct Clip {
     pub title: String,
 
     /// Starting timing position to play this [`Clip`] from.
-    #[serde(with = "timelike")]
-    pub from: Duration,
+    pub from: ClipPosition,
 
     /// Finish timing position to play this [`Clip`] until.
     ///
     /// Obviously, should be always greater than [`Clip::from`] for at least
     /// 1 second.
+    pub to: ClipPosition,
+
+    /// Indicator whether this [`Clip`]'s duration should be automatically
+    /// padded to the nearest segment boundary, rather than being rejected if
+    /// it's not divisible on [`SegmentDuration`].
+    ///
+    /// Padding extends [`Clip::to`] forward if the video is long enough, or
+    /// extends [`Clip::from`] backward otherwise.
+    #[serde(default)]
+    pub auto_pad: bool,
+
+    /// HTTP headers to be sent along with requests downloading this
+    /// [`Clip`]'s upstream source files (an authorization token of a private
+    /// CDN, for example).
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// Timing position in a video of a [`Clip`].
+///
+/// Is either an exact time in a `%H:%M:%S` time-like format (`01:05:00`, for
+/// example), or a percentage of the video's total duration (`50%`, for
+/// example).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClipPosition {
+    /// Exact timing position.
+    Time(Duration),
+
+    /// Percentage (from 0 to 100) of the video's total duration.
+    Percent(u8),
+}
+
+impl ClipPosition {
+    /// Resolves this [`ClipPosition`] into an exact timing position in a video
+    /// of the given `total` duration.
+    ///
+    /// Percentages are resolved with a precision of whole seconds (rounding
+    /// down).
+    #[must_use]
+    pub fn resolve(self, total: Duration) -> Duration {
+        match self {
+            Self::Time(time) => time,
+            Self::Percent(pct) => {
+                Duration::from_secs(total.as_secs() * u64::from(pct) / 100)
+            }
+        }
+    }
+}
+
+impl From<Duration> for ClipPosition {
+    #[inline]
+    fn from(time: Duration) -> Self {
+        Self::Time(time)
+    }
+}
+
+impl Serialize for ClipPosition {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Time(time) => timelike::serialize(time, serializer),
+            Self::Percent(pct) => {
+                serializer.serialize_str(&format!("{}%", pct))
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ClipPosition {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::{value::StrDeserializer, Error as _, IntoDeserializer};
+
+        let s = <Cow<'_, str>>::deserialize(deserializer)?;
+        if let Some(pct) = s.strip_suffix('%') {
+            return pct
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|pct| *pct <= 100)
+                .map(Self::Percent)
+                .ok_or_else(|| {
+                    D::Error::custom(format!("invalid percentage: {}", s))
+                });
+        }
+        let de: StrDeserializer<'_, D::Error> = s.as_ref().into_deserializer();
+        Ok(Self::Time(timelike::deserialize(de)?))
+    }
+}
+
+/// Summary of a successfully validated [`Request`], distributed by
+/// [`Playlist`]s.
+pub type Summary = HashMap<PlaylistSlug, PlaylistSummary>;
+
+/// Summary of a successfully validated [`Playlist`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PlaylistSummary {
+    /// Total number of [`Clip`]s in the [`Playlist`].
+    pub clips: usize,
+
+    /// Summaries of the [`Playlist`]'s [`Weekday`]s.
+    pub days: HashMap<Weekday, DaySummary>,
+}
+
+/// Summary of a single [`Weekday`] in a successfully validated [`Playlist`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DaySummary {
+    /// Number of [`Clip`]s in the [`Weekday`].
+    pub clips: usize,
+
+    /// Total duration of all [`Clip`]s in the [`Weekday`].
     #[serde(with = "timelike")]
-    pub to: Duration,
+    pub duration: Duration,
 }
 
 #[cfg(test)]
@@ -249,4 +441,46 @@ mod spec {
             }
         }
     }
+
+    mod clip_position {
+        use super::*;
+
+        #[test]
+        fn resolves_percentages() {
+            let total = Duration::from_secs(6686);
+
+            for (raw, expected) in &[
+                (r#""0%""#, 0),
+                (r#""50%""#, 3343),
+                (r#""33%""#, 2206),
+                (r#""100%""#, 6686),
+                (r#""00:01:30""#, 90),
+            ] {
+                let pos = serde_json::from_str::<ClipPosition>(raw)
+                    .expect("Failed to deserialize");
+                assert_eq!(
+                    pos.resolve(total),
+                    Duration::from_secs(*expected),
+                    "incorrectly resolves {}",
+                    raw,
+                );
+            }
+        }
+
+        #[test]
+        fn serializes_back() {
+            for raw in &[r#""50%""#, r#""01:51:26""#] {
+                let pos = serde_json::from_str::<ClipPosition>(raw).unwrap();
+                assert_eq!(serde_json::to_string(&pos).unwrap(), *raw);
+            }
+        }
+
+        #[test]
+        fn fails_deserialize_invalid() {
+            for raw in &[r#""101%""#, r#""-5%""#, r#""half%""#, r#""50""#] {
+                let res = serde_json::from_str::<ClipPosition>(raw);
+                assert!(res.is_err(), "should not deserialize: {}", raw);
+            }
+        }
+    }
 }
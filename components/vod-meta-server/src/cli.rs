@@ -1,10 +1,12 @@
 //! CLI (command line interface).
 
-use std::{fmt, net::IpAddr, path::PathBuf, str::FromStr as _};
+use std::{
+    fmt, net::IpAddr, num::NonZeroUsize, path::PathBuf, str::FromStr as _,
+};
 
 use anyhow::anyhow;
 use byte_unit::Byte;
-use ephyr_log::slog;
+use ephyr_log::{slog, ModuleFilters};
 use structopt::StructOpt;
 
 /// CLI (command line interface) of the server.
@@ -90,6 +92,25 @@ pub struct Opts {
     )]
     pub request_max_size: Byte,
 
+    /// Maximum number of concurrent requests to [`allatra::video::Api`]
+    /// performed while parsing [`vod::meta::State`] modifying requests.
+    ///
+    /// The limit is global: it's shared between all the clips of all the
+    /// playlists of all the requests being processed at the moment.
+    ///
+    /// [`allatra::video::Api`]: crate::api::allatra::video::Api
+    /// [`vod::meta::State`]: crate::vod::meta::State
+    #[structopt(
+        long,
+        env = "EPHYR_VOD_META_API_CONCURRENCY",
+        default_value = "10",
+        help = "Maximum concurrent requests to video info API",
+        long_help = "Maximum number of concurrent requests to video info API \
+                     performed while processing requests, which modify state \
+                     of the server"
+    )]
+    pub api_concurrency: NonZeroUsize,
+
     /// Verbosity level of the server logs.
     #[structopt(
         short,
@@ -99,6 +120,20 @@ pub struct Opts {
                 OFF | CRIT | ERRO | WARN | INFO | DEBG | TRCE"
     )]
     pub verbose: Option<slog::Level>,
+
+    /// Per-module overrides of the server logs verbosity level.
+    #[structopt(
+        long,
+        env = "EPHYR_VOD_META_LOG_FILTERS",
+        default_value = "",
+        help = "Per-module logs verbosity levels: \
+                <module>=<level>[,<module>=<level>...]",
+        long_help = "Per-module overrides of logs verbosity level, as \
+                     comma-separated list of `<module>=<level>` pairs \
+                     (like `hyper=info,actix_web=warn`). A filter applies to \
+                     the module and all its submodules."
+    )]
+    pub log_filters: ModuleFilters,
 }
 
 impl Opts {
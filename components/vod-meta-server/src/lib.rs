@@ -43,7 +43,7 @@ pub fn run() -> Result<(), cli::Failure> {
 
     // This guard should be held till the end of the program for the logger
     // to present in global context.
-    let _log_guard = ephyr_log::init(opts.verbose);
+    let _log_guard = ephyr_log::init(opts.verbose, opts.log_filters.clone());
 
     server::run(opts)
 }
@@ -3,24 +3,44 @@
 //! [VOD]: https://en.wikipedia.org/wiki/Video_on_demand
 
 use std::{
-    convert::TryInto as _, panic::AssertUnwindSafe, sync::Arc, time::Duration,
+    collections::hash_map::DefaultHasher,
+    convert::TryInto as _,
+    hash::{Hash as _, Hasher as _},
+    io::SeekFrom,
+    panic::AssertUnwindSafe,
+    path::{Component, Path},
+    sync::Arc,
+    time::Duration,
 };
 
 use actix_web::{
-    delete, dev::ServiceRequest, error, get, middleware, put, web, App,
-    FromRequest as _, HttpServer,
+    delete,
+    dev::{ServiceRequest, SizedStream},
+    error, get,
+    http::header::{
+        ACCEPT_RANGES, CACHE_CONTROL, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+        IF_NONE_MATCH, RANGE,
+    },
+    middleware, post, put, web, App, FromRequest as _, HttpRequest,
+    HttpResponse, HttpServer,
 };
 use actix_web_httpauth::{
     extractors::bearer::{self, BearerAuth},
     middleware::HttpAuthentication,
 };
+use chrono::{DateTime, Utc};
 use ephyr_log::log;
-use futures::{sink, FutureExt as _, StreamExt as _};
+use futures::{sink, stream, FutureExt as _, StreamExt as _};
 use serde::Deserialize;
-use tokio::time;
+use tokio::{
+    fs,
+    io::{AsyncReadExt as _, AsyncSeekExt as _},
+    sync::Semaphore,
+    time,
+};
 
 use crate::{
-    api::{nginx, vod},
+    api::vod,
     cli,
     util::display_panic,
     vod::{
@@ -70,15 +90,22 @@ pub async fn run(opts: cli::Opts) -> Result<(), cli::Failure> {
         Duration::from_secs(60),
     )));
 
+    #[cfg(unix)]
+    drop(tokio::spawn(reload_state_on_sighup(state.clone())));
+
     let auth_token_hash = AuthTokenHash(opts.auth_token_hash);
+    let api_limit = Arc::new(Semaphore::new(opts.api_concurrency.get()));
 
     let _ = HttpServer::new(move || {
         App::new()
             .data(state.clone())
             .data(cache.clone())
+            .data(api_limit.clone())
             .wrap(middleware::Logger::default())
+            .service(serve_cached_file)
             .service(produce_meta)
             .service(show_playlist)
+            .service(show_playlist_position)
             .service(show_state)
             .app_data(bearer::Config::default().realm("Restricted area"))
             .app_data(auth_token_hash.clone())
@@ -93,6 +120,8 @@ pub async fn run(opts: cli::Opts) -> Result<(), cli::Failure> {
             .service(renew_state)
             .service(renew_playlist)
             .service(delete_playlist)
+            .service(clone_playlist)
+            .service(validate_state)
     })
     .bind((opts.http_ip, opts.http_port))
     .map_err(|e| log::error!("Failed to bind web server: {}", e))?
@@ -102,28 +131,227 @@ pub async fn run(opts: cli::Opts) -> Result<(), cli::Failure> {
     Ok(())
 }
 
+/// Duration for which a response of [`produce_meta`] may be cached by
+/// clients.
+const META_MAX_AGE: Duration = Duration::from_secs(10);
+
 /// Responses with the [`nginx-vod-module` mapping][1] containing the playlist
 /// which should be played, starting from now and on.
 ///
+/// Supports [conditional requests][2] via `ETag` and `If-None-Match` headers,
+/// responding with `304 Not Modified` if the schedule hasn't changed.
+///
 /// [1]: https://github.com/kaltura/nginx-vod-module#mapping-response-format
+/// [2]: https://developer.mozilla.org/docs/Web/HTTP/Conditional_requests
 #[get("/{location}/{playlist}/{filename}")]
 async fn produce_meta(
+    req: HttpRequest,
     state: web::Data<state::Manager>,
     path: web::Path<(String, String, String)>,
-) -> Result<web::Json<nginx::vod_module::mapping::Set>, error::Error> {
+) -> Result<HttpResponse, error::Error> {
     let slug = state::PlaylistSlug::new(&path.1).ok_or_else(|| {
         error::ErrorBadRequest(format!("Invalid playlist slug '{}'", path.1))
     })?;
 
-    Ok(web::Json(
-        state
-            .playlist(&slug)
+    let now = Utc::now();
+    let set = state
+        .playlist(&slug)
+        .await
+        .ok_or_else(|| {
+            error::ErrorNotFound(format!("Unknown playlist '{}'", slug))
+        })?
+        .schedule_nginx_vod_module_set(Some(now), 5);
+    let body =
+        serde_json::to_vec(&set).map_err(error::ErrorInternalServerError)?;
+
+    let etag = meta_etag(&body, now);
+    let cache_control = format!("max-age={}", META_MAX_AGE.as_secs());
+
+    let is_not_modified = req
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |v| {
+            v.split(',').map(str::trim).any(|t| t == etag || t == "*")
+        });
+    if is_not_modified {
+        return Ok(HttpResponse::NotModified()
+            .header(ETAG, etag)
+            .header(CACHE_CONTROL, cache_control)
+            .finish());
+    }
+
+    Ok(HttpResponse::Ok()
+        .header(CONTENT_TYPE, "application/json")
+        .header(ETAG, etag)
+        .header(CACHE_CONTROL, cache_control)
+        .body(body))
+}
+
+/// Computes a quoted `ETag` value of the given serialized [`mapping::Set`]
+/// produced at the given moment.
+///
+/// As the schedule changes over time, the `ETag` is computed over the current
+/// minute bucket too, so it doesn't outlive the schedule it was computed for.
+///
+/// [`mapping::Set`]: crate::api::nginx::vod_module::mapping::Set
+fn meta_etag(body: &[u8], at: DateTime<Utc>) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    (at.timestamp() / 60).hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Size of chunks to stream cached files with by [`serve_cached_file`].
+const FILE_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Streams the requested file directly from the [`file::cache`] directory,
+/// bypassing [`nginx-vod-module`][1].
+///
+/// Supports a single [byte range][2] via `Range` header, responding with
+/// `206 Partial Content`.
+///
+/// Useful for debugging and for players, which cannot read `file:///` URLs
+/// produced by [`state::Playlist::fill_with_cache_files()`].
+///
+/// [1]: https://github.com/kaltura/nginx-vod-module
+/// [2]: https://developer.mozilla.org/docs/Web/HTTP/Range_requests
+#[get("/file/{cache_path:.+}")]
+async fn serve_cached_file(
+    req: HttpRequest,
+    cache: web::Data<Arc<file::cache::Manager>>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, error::Error> {
+    let rel_path = Path::new(path.as_str());
+    if rel_path
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(error::ErrorBadRequest(format!(
+            "Invalid cache path '{}'",
+            path,
+        )));
+    }
+
+    let full = cache
+        .resolve_cached_file(rel_path)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| {
+            error::ErrorNotFound(format!("No cached file '{}'", path))
+        })?;
+    let mut file = fs::File::open(&full)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let size = file
+        .metadata()
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .len();
+
+    let content_type = match full.extension().and_then(|e| e.to_str()) {
+        Some("mp4") => "video/mp4",
+        _ => "application/octet-stream",
+    };
+
+    let range = match req
+        .headers()
+        .get(RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| parse_byte_range(v, size))
+    {
+        Some(Err(())) => {
+            return Ok(HttpResponse::RangeNotSatisfiable()
+                .header(CONTENT_RANGE, format!("bytes */{}", size))
+                .finish());
+        }
+        Some(Ok(r)) => r,
+        None => None,
+    };
+
+    let mut resp = if let Some((start, end)) = range {
+        let _ = file
+            .seek(SeekFrom::Start(start))
             .await
-            .ok_or_else(|| {
-                error::ErrorNotFound(format!("Unknown playlist '{}'", slug))
-            })?
-            .schedule_nginx_vod_module_set(None, 5),
-    ))
+            .map_err(error::ErrorInternalServerError)?;
+        let mut resp = HttpResponse::PartialContent();
+        let _ = resp
+            .header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size));
+        resp
+    } else {
+        HttpResponse::Ok()
+    };
+    let len = range.map_or(size, |(start, end)| end - start + 1);
+
+    let body = stream::unfold((file, len), |(mut file, left)| async move {
+        if left == 0 {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation)] // bounded by chunk size
+        let mut buf = vec![0; left.min(FILE_CHUNK_SIZE) as usize];
+        match file.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok(web::Bytes::from(buf)), (file, left - n as u64)))
+            }
+            Err(e) => Some((Err(error::Error::from(e)), (file, 0))),
+        }
+    });
+
+    Ok(resp
+        .header(ACCEPT_RANGES, "bytes")
+        .header(CONTENT_TYPE, content_type)
+        .body(SizedStream::new(len, Box::pin(body))))
+}
+
+/// Parses the given `Range` HTTP header value into an inclusive range of bytes
+/// of a file with the given `size`.
+///
+/// Returns [`None`] if the header should be ignored (it's malformed or
+/// requests multiple ranges), so the whole file is served.
+///
+/// # Errors
+///
+/// If the requested range cannot be satisfied for the given `size`.
+fn parse_byte_range(header: &str, size: u64) -> Result<Option<(u64, u64)>, ()> {
+    let spec = match header.trim().strip_prefix("bytes=") {
+        Some(s) if !s.contains(',') => s.trim(),
+        _ => return Ok(None),
+    };
+    let (start, end) = match spec.find('-') {
+        Some(n) => (&spec[..n], &spec[n + 1..]),
+        None => return Ok(None),
+    };
+
+    let range = if start.is_empty() {
+        let suffix: u64 = match end.parse() {
+            Ok(n) => n,
+            Err(_) => return Ok(None),
+        };
+        if suffix == 0 {
+            return Err(());
+        }
+        (size.saturating_sub(suffix), size.checked_sub(1).ok_or(())?)
+    } else {
+        let start: u64 = match start.parse() {
+            Ok(n) => n,
+            Err(_) => return Ok(None),
+        };
+        let end = if end.is_empty() {
+            size.saturating_sub(1)
+        } else {
+            match end.parse::<u64>() {
+                Ok(n) => n.min(size.saturating_sub(1)),
+                Err(_) => return Ok(None),
+            }
+        };
+        if start >= size || start > end {
+            return Err(());
+        }
+        (start, end)
+    };
+    Ok(Some(range))
 }
 
 /// Displays the current whole `vod-meta` server [`State`].
@@ -143,6 +371,26 @@ async fn show_playlist(
     )?))
 }
 
+/// Displays the current [`state::Playlist::initial`] position of the requested
+/// `vod-meta` server [`state::Playlist`], which its schedule is built from.
+///
+/// Responds with `null` if the position hasn't been initialized yet.
+#[get("/{playlist}/position")]
+async fn show_playlist_position(
+    state: web::Data<state::Manager>,
+    slug: web::Path<state::PlaylistSlug>,
+) -> Result<web::Json<Option<state::PlaylistInitialPosition>>, error::Error> {
+    Ok(web::Json(
+        state
+            .playlist(&slug.0)
+            .await
+            .ok_or_else(|| {
+                error::ErrorNotFound(format!("Unknown playlist '{}'", slug))
+            })?
+            .initial,
+    ))
+}
+
 /// Renews the `vod-meta` server [`State`] with the new one provided in
 /// [`vod::meta::Request`].
 ///
@@ -157,12 +405,14 @@ async fn show_playlist(
 async fn renew_state(
     state: web::Data<state::Manager>,
     cache: web::Data<Arc<file::cache::Manager>>,
+    api_limit: web::Data<Arc<Semaphore>>,
     req: web::Json<vod::meta::Request>,
     mode: web::Query<Mode>,
 ) -> Result<&'static str, error::Error> {
-    let mut new = State::parse_request(req.0)
+    let prev = state.state().await;
+    let mut new = State::parse_request(req.0, &prev, &api_limit)
         .await
-        .map_err(error::ErrorBadRequest)?;
+        .map_err(parse_error)?;
 
     for playlist in new.values_mut() {
         playlist
@@ -179,6 +429,19 @@ async fn renew_state(
     Ok("Ok")
 }
 
+/// Converts the given error of parsing a `vod-meta` server API request into an
+/// HTTP error.
+///
+/// [`state::NoMutualResolutions`] is reported as `422 Unprocessable Entity`,
+/// while any other error is considered as `400 Bad Request`.
+fn parse_error(err: anyhow::Error) -> error::Error {
+    if err.is::<state::NoMutualResolutions>() {
+        error::ErrorUnprocessableEntity(err)
+    } else {
+        error::ErrorBadRequest(err)
+    }
+}
+
 /// Renews the single [`state::Playlist`] in `vod-meta` server [`State`] with
 /// the new one provided in [`vod::meta::Playlist`] request.
 ///
@@ -198,13 +461,20 @@ async fn renew_state(
 async fn renew_playlist(
     state: web::Data<state::Manager>,
     cache: web::Data<Arc<file::cache::Manager>>,
+    api_limit: web::Data<Arc<Semaphore>>,
     slug: web::Path<state::PlaylistSlug>,
     req: web::Json<vod::meta::Playlist>,
     mode: web::Query<Mode>,
 ) -> Result<&'static str, error::Error> {
-    let mut playlist = state::Playlist::parse_request(slug.0, req.0)
-        .await
-        .map_err(error::ErrorBadRequest)?;
+    let prev = state.playlist(&slug).await;
+    let mut playlist = state::Playlist::parse_request(
+        slug.0,
+        req.0,
+        prev.as_ref(),
+        &api_limit,
+    )
+    .await
+    .map_err(parse_error)?;
 
     playlist
         .fill_with_cache_files(&cache)
@@ -244,6 +514,104 @@ async fn delete_playlist(
     Ok("Ok")
 }
 
+/// Clones the single [`state::Playlist`] identified by its
+/// [`state::Playlist::slug`] under the new slug, optionally overriding its
+/// timezone and language.
+///
+/// The cloned [`state::Playlist`] preserves all the [`state::Clip`]s of the
+/// original one, but starts from a fresh [`state::Playlist::initial`]
+/// position.
+///
+/// Responds with `404 Not Found` if there is no such [`state::Playlist`] to
+/// clone, or with `409 Conflict` if the new slug is occupied already.
+///
+/// # Authorization
+///
+/// __Mandatory.__ The request must be authorized with [Bearer HTTP token][1],
+/// which value is verified against [`cli::VodMetaOpts::auth_token_hash`].
+///
+/// [1]: https://tools.ietf.org/html/rfc6750#section-2.1
+#[post(
+    "/{playlist}/clone",
+    wrap = "HttpAuthentication::bearer(verify_auth_token)"
+)]
+async fn clone_playlist(
+    state: web::Data<state::Manager>,
+    slug: web::Path<state::PlaylistSlug>,
+    req: web::Json<vod::meta::ClonePlaylist>,
+) -> Result<&'static str, error::Error> {
+    let (slug, req) = (slug.0, req.0);
+    let new_slug = req.slug.clone();
+    let is_cloned = state
+        .clone_playlist(&slug, req.slug, req.tz, req.lang)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| {
+            error::ErrorNotFound(format!("Unknown playlist '{}'", slug))
+        })?;
+    if !is_cloned {
+        return Err(error::ErrorConflict(format!(
+            "Playlist '{}' exists already",
+            new_slug,
+        )));
+    }
+    Ok("Ok")
+}
+
+/// Validates the provided [`vod::meta::Request`] (performing all the checks of
+/// [`renew_state`]) without applying it to the `vod-meta` server [`State`].
+///
+/// Responds with a [`vod::meta::Summary`] of the validated request, or with
+/// `422 Unprocessable Entity` and the first validation error.
+///
+/// # Authorization
+///
+/// __Mandatory.__ The [`vod::meta::Request`] must be authorized with
+/// [Bearer HTTP token][1], which value is verified against
+/// [`cli::VodMetaOpts::auth_token_hash`].
+///
+/// [1]: https://tools.ietf.org/html/rfc6750#section-2.1
+#[post("/validate", wrap = "HttpAuthentication::bearer(verify_auth_token)")]
+async fn validate_state(
+    api_limit: web::Data<Arc<Semaphore>>,
+    req: web::Json<vod::meta::Request>,
+) -> Result<web::Json<vod::meta::Summary>, error::Error> {
+    let validated = State::parse_request(req.0, &State::default(), &api_limit)
+        .await
+        .map_err(error::ErrorUnprocessableEntity)?;
+    Ok(web::Json(validated.summary()))
+}
+
+/// Runs job, which reloads the given `state` from its file on every received
+/// [SIGHUP] signal, so the file may be edited out-of-band without restarting
+/// the server.
+///
+/// In-flight requests are not affected, as they operate on the [`State`]
+/// copies.
+///
+/// [SIGHUP]: https://en.wikipedia.org/wiki/SIGHUP
+#[cfg(unix)]
+async fn reload_state_on_sighup(state: state::Manager) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("Failed to listen SIGHUP signal: {}", e);
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        match state.reload().await {
+            Ok(()) => log::info!("vod::meta::State reloaded on SIGHUP"),
+            Err(e) => log::error!(
+                "Failed to reload vod::meta::State on SIGHUP: {}",
+                e,
+            ),
+        }
+    }
+}
+
 /// Runs job, which periodically (with the given `period`) refills the given
 /// `state` with information about files available in the given `cache`.
 async fn refill_state_with_cache_files(
@@ -364,3 +732,269 @@ struct Mode {
     #[serde(default)]
     dry_run: bool,
 }
+
+#[cfg(test)]
+mod spec {
+    use std::fs;
+
+    use actix_web::{
+        http::{
+            header::{AUTHORIZATION, IF_NONE_MATCH, RANGE},
+            StatusCode,
+        },
+        test,
+    };
+    use chrono::Timelike as _;
+    use tempfile::{NamedTempFile, TempDir};
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn responds_not_modified_for_unchanged_schedule() {
+        let file = NamedTempFile::new().unwrap();
+        fs::copy("example.vod.meta.json", file.path())
+            .expect("No example file found");
+        let state = state::Manager::try_new(file.path()).await.unwrap();
+
+        // Avoid crossing a minute bucket between the requests.
+        while Utc::now().second() >= 55 {
+            time::delay_for(Duration::from_secs(1)).await;
+        }
+
+        let mut app =
+            test::init_service(App::new().data(state).service(produce_meta))
+                .await;
+
+        let req = test::TestRequest::get()
+            .uri("/vod/divan-tv/seg.mp4")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().contains_key(CACHE_CONTROL));
+        let etag = resp.headers().get(ETAG).expect("No ETag").clone();
+
+        let req = test::TestRequest::get()
+            .uri("/vod/divan-tv/seg.mp4")
+            .header(IF_NONE_MATCH, etag.clone())
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers().get(ETAG), Some(&etag));
+
+        let req = test::TestRequest::get()
+            .uri("/vod/divan-tv/seg.mp4")
+            .header(IF_NONE_MATCH, "\"outdated\"")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn validates_request_without_applying() {
+        let file = NamedTempFile::new().unwrap();
+        let state = state::Manager::try_new(file.path()).await.unwrap();
+        let hash = argon2::hash_encoded(
+            b"qwerty",
+            b"somesaltvalue",
+            &argon2::Config::default(),
+        )
+        .unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(state.clone())
+                .data(Arc::new(Semaphore::new(10)))
+                .app_data(AuthTokenHash(hash))
+                .service(validate_state),
+        )
+        .await;
+
+        let day = r#"[{
+          "from": "00:00:00",
+          "to": "00:01:00",
+          "title": "Life circle",
+          "url": "https://www.youtube.com/watch?v=0wAtNWA93hM"
+        }]"#;
+        let playlist = |title: &str| {
+            serde_json::from_str::<serde_json::Value>(&format!(
+                r#"{{"life": {{
+                  "title": "{}",
+                  "lang": "eng",
+                  "tz": "+02:00",
+                  "clips": {{
+                    "mon": {day}, "tue": {day}, "wed": {day}, "thu": {day},
+                    "fri": {day}, "sat": {day}, "sun": {day}
+                  }}
+                }}}}"#,
+                title,
+                day = day,
+            ))
+            .unwrap()
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/validate")
+            .header(AUTHORIZATION, "Bearer qwerty")
+            .set_json(&playlist("Life"))
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let summary: vod::meta::Summary = test::read_body_json(resp).await;
+        let life = &summary[&state::PlaylistSlug::new("life").unwrap()];
+        assert_eq!(life.clips, 7);
+        assert_eq!(life.days.len(), 7);
+        assert_eq!(
+            life.days[&chrono::Weekday::Mon].duration,
+            Duration::from_secs(60),
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/validate")
+            .header(AUTHORIZATION, "Bearer qwerty")
+            .set_json(&playlist(""))
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        assert!(state.state().await.is_empty(), "state is mutated");
+    }
+
+    #[actix_rt::test]
+    async fn shows_refreshed_playlist_position() {
+        let file = NamedTempFile::new().unwrap();
+        fs::copy("example.vod.meta.json", file.path())
+            .expect("No example file found");
+        let state = state::Manager::try_new(file.path()).await.unwrap();
+        let slug = state::PlaylistSlug::new("divan-tv").unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(state.clone())
+                .service(show_playlist_position),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/divan-tv/position")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let pos: Option<state::PlaylistInitialPosition> =
+            test::read_body_json(resp).await;
+        assert_eq!(pos, None);
+
+        state.refresh_playlists_positions().await.unwrap();
+        let expected = state.playlist(&slug).await.unwrap().initial;
+        assert!(expected.is_some(), "initial position is not set");
+
+        let req = test::TestRequest::get()
+            .uri("/divan-tv/position")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let pos: Option<state::PlaylistInitialPosition> =
+            test::read_body_json(resp).await;
+        assert_eq!(pos, expected);
+
+        let req = test::TestRequest::get()
+            .uri("/unknown/position")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn serves_range_of_cached_file() {
+        let dir = TempDir::new().unwrap();
+        let content: Vec<u8> = (0..=255).collect();
+        fs::create_dir(dir.path().join("videos")).unwrap();
+        fs::write(dir.path().join("videos/clip.mp4"), &content).unwrap();
+        let cache =
+            Arc::new(file::cache::Manager::try_new(dir.path()).unwrap());
+
+        let mut app = test::init_service(
+            App::new().data(cache).service(serve_cached_file),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/file/videos/clip.mp4")
+            .header(RANGE, "bytes=10-19")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            resp.headers().get(CONTENT_RANGE).unwrap(),
+            "bytes 10-19/256",
+        );
+        assert_eq!(test::read_body(resp).await, &content[10..20]);
+
+        let req = test::TestRequest::get()
+            .uri("/file/videos/clip.mp4")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(test::read_body(resp).await, &content[..]);
+
+        let req = test::TestRequest::get()
+            .uri("/file/videos/clip.mp4")
+            .header(RANGE, "bytes=300-")
+            .to_request();
+        let resp = test::call_service(&mut app, req).await;
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    }
+
+    #[actix_rt::test]
+    async fn rejects_cached_file_path_traversal() {
+        let root = TempDir::new().unwrap();
+        fs::create_dir(root.path().join("cache")).unwrap();
+        fs::write(root.path().join("secret"), b"secret").unwrap();
+        let cache = Arc::new(
+            file::cache::Manager::try_new(root.path().join("cache")).unwrap(),
+        );
+
+        let mut app = test::init_service(
+            App::new().data(cache.clone()).service(serve_cached_file),
+        )
+        .await;
+
+        for uri in &[
+            "/file/../secret",
+            "/file/%2e%2e/secret",
+            "/file//etc/passwd",
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&mut app, req).await;
+            assert_eq!(
+                resp.status(),
+                StatusCode::BAD_REQUEST,
+                "'{}' is not rejected",
+                uri,
+            );
+        }
+
+        assert_eq!(cache.resolve_cached_file("../secret").await.unwrap(), None);
+    }
+
+    #[test]
+    fn parses_byte_range() {
+        for (header, expected) in &[
+            ("bytes=0-9", Ok(Some((0, 9)))),
+            ("bytes=90-", Ok(Some((90, 99)))),
+            ("bytes=-10", Ok(Some((90, 99)))),
+            ("bytes=50-500", Ok(Some((50, 99)))),
+            ("bytes=0-1,5-6", Ok(None)),
+            ("items=0-9", Ok(None)),
+            ("bytes=100-", Err(())),
+            ("bytes=9-0", Err(())),
+            ("bytes=-0", Err(())),
+        ] {
+            assert_eq!(
+                parse_byte_range(header, 100),
+                *expected,
+                "wrong range for '{}'",
+                header,
+            );
+        }
+    }
+}
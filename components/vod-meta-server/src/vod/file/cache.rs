@@ -3,6 +3,7 @@
 //! [VOD]: https://en.wikipedia.org/wiki/Video_on_demand
 
 use std::{
+    collections::HashMap,
     panic::AssertUnwindSafe,
     path::{self, Path, PathBuf},
 };
@@ -10,6 +11,7 @@ use std::{
 use anyhow::anyhow;
 use ephyr_log::log;
 use futures::{sink, FutureExt as _, StreamExt as _, TryStreamExt as _};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use tempfile::TempDir;
 use tokio::{fs, io, sync::mpsc};
 use tokio_util::compat::FuturesAsyncReadCompatExt as _;
@@ -29,8 +31,9 @@ pub struct Manager {
     /// persisted in.
     cache_dir: PathBuf,
 
-    /// Queue of tasks to perform downloading.
-    downloads: mpsc::UnboundedSender<Url>,
+    /// Queue of tasks to perform downloading, along with HTTP headers to be
+    /// sent in the downloading requests.
+    downloads: mpsc::UnboundedSender<(Url, HashMap<String, String>)>,
 
     /// Directory where temporary downloading files are created.
     ///
@@ -61,7 +64,7 @@ impl Manager {
             .prefix("ephyr-vod-cache.")
             .tempdir()?;
 
-        let (tx, rx) = mpsc::unbounded_channel::<Url>();
+        let (tx, rx) = mpsc::unbounded_channel();
         drop(tokio::spawn(Self::run_downloads(
             rx,
             cache_dir.clone(),
@@ -79,7 +82,7 @@ impl Manager {
     /// cache any.
     ///
     /// If there is no cached file for the given [`Url`], then schedules it for
-    /// downloading.
+    /// downloading with the given HTTP `headers`.
     ///
     /// # Errors
     ///
@@ -88,6 +91,7 @@ impl Manager {
     pub async fn get_cached_path(
         &self,
         url: &Url,
+        headers: &HashMap<String, String>,
     ) -> Result<Option<PathBuf>, anyhow::Error> {
         let full =
             self.cache_dir
@@ -104,13 +108,15 @@ impl Manager {
                 e,
             )),
             _ => {
-                self.downloads.send(url.clone()).map_err(|e| {
-                    anyhow!(
-                        "Failed to schedule '{}' URL for downloading: {}",
-                        url,
-                        e,
-                    )
-                })?;
+                self.downloads
+                    .send((url.clone(), headers.clone()))
+                    .map_err(|e| {
+                        anyhow!(
+                            "Failed to schedule '{}' URL for downloading: {}",
+                            url,
+                            e,
+                        )
+                    })?;
                 Ok(None)
             }
         }
@@ -122,16 +128,16 @@ impl Manager {
     ///
     /// The job finishes once [`Manager`] is dropped.
     async fn run_downloads(
-        downloads: mpsc::UnboundedReceiver<Url>,
+        downloads: mpsc::UnboundedReceiver<(Url, HashMap<String, String>)>,
         dst: PathBuf,
         tmp: PathBuf,
     ) {
         let _ = downloads
-            .map(move |url| {
+            .map(move |(url, headers)| {
                 let dst = dst.clone();
                 let tmp = tmp.clone();
                 async move {
-                    AssertUnwindSafe(Self::download(&url, &dst, &tmp))
+                    AssertUnwindSafe(Self::download(&url, &headers, &dst, &tmp))
                         .catch_unwind()
                         .await
                         .map_err(|p| {
@@ -157,8 +163,9 @@ impl Manager {
             .await;
     }
 
-    /// Downloads the given [`Url`] into `dst_dir` using `tmp_dir` for keeping
-    /// temporary file while downloading happens.
+    /// Downloads the given [`Url`] (sending the given HTTP `headers`) into
+    /// `dst_dir` using `tmp_dir` for keeping temporary file while downloading
+    /// happens.
     ///
     /// The temporary file is required to avoid any problems with partially
     /// downloaded files. That's why, first, the file is downloaded into
@@ -168,12 +175,14 @@ impl Manager {
     /// # Errors
     ///
     /// - If file in `tmp_dir` or `dst_dir` cannot be created.
+    /// - If the given HTTP `headers` are invalid.
     /// - If the given [`Url`] couldn't be reached or responses with non-success
     ///   HTTP code.
     /// - If downloading of file from the given [`Url`] fails or is interrupted.
     #[allow(clippy::too_many_lines)]
     async fn download(
         url: &Url,
+        headers: &HashMap<String, String>,
         dst_dir: &Path,
         tmp_dir: &Path,
     ) -> Result<(), anyhow::Error> {
@@ -216,16 +225,14 @@ impl Manager {
         }
 
         log::info!(
-            "Start downloading '{}' URL to '{}' VOD cache file",
+            "Start downloading '{}' URL to '{}' VOD cache file{}",
             url,
             dst_path.display(),
+            display_headers(headers),
         );
 
-        let mut resp = reqwest::get(url.clone())
-            .await
-            .map_err(|e| anyhow!("Failed to perform GET '{}': {}", url, e))?
-            .error_for_status()
-            .map_err(|e| anyhow!("Bad response for GET '{}': {}", url, e))?
+        let mut resp = Self::request(url, headers)
+            .await?
             .bytes_stream()
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
             .into_async_read()
@@ -319,6 +326,60 @@ impl Manager {
         Ok(())
     }
 
+    /// Performs GET HTTP request to the given [`Url`] with the given HTTP
+    /// `headers`, returning the response if it's successful.
+    ///
+    /// # Errors
+    ///
+    /// - If the given HTTP `headers` are invalid.
+    /// - If the given [`Url`] couldn't be reached or responses with non-success
+    ///   HTTP code.
+    async fn request(
+        url: &Url,
+        headers: &HashMap<String, String>,
+    ) -> Result<reqwest::Response, anyhow::Error> {
+        reqwest::Client::new()
+            .get(url.clone())
+            .headers(parse_headers(headers)?)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to perform GET '{}': {}", url, e))?
+            .error_for_status()
+            .map_err(|e| anyhow!("Bad response for GET '{}': {}", url, e))
+    }
+
+    /// Resolves the given `path` (relative to the cache directory) into an
+    /// absolute path of the cached file, if there is any.
+    ///
+    /// [`None`] is returned if the given `path` points outside the cache
+    /// directory (even via symlinks), or there is no such file in cache.
+    ///
+    /// # Errors
+    ///
+    /// If the file existence cannot be checked.
+    pub async fn resolve_cached_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> io::Result<Option<PathBuf>> {
+        let path = path.as_ref();
+        if !path
+            .components()
+            .all(|c| matches!(c, path::Component::Normal(_)))
+        {
+            return Ok(None);
+        }
+
+        let full = match fs::canonicalize(self.cache_dir.join(path)).await {
+            Ok(p) => p,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if !full.starts_with(&self.cache_dir) {
+            return Ok(None);
+        }
+        Ok(fs::metadata(&full).await?.is_file().then(|| full))
+    }
+
     /// Extracts path of the file in cache from the given [`Url`].
     ///
     /// If [`None`] is returned, then such [`Url`] is not supported for
@@ -341,3 +402,126 @@ impl Manager {
         Some(path.to_owned())
     }
 }
+
+/// Parses the given HTTP `headers` into a [`HeaderMap`].
+///
+/// # Errors
+///
+/// If any of the given HTTP `headers` has invalid name or value.
+pub fn parse_headers(
+    headers: &HashMap<String, String>,
+) -> Result<HeaderMap, anyhow::Error> {
+    headers
+        .iter()
+        .map(|(name, val)| {
+            let name =
+                HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                    anyhow!("Invalid header name '{}': {}", name, e)
+                })?;
+            // Value is not reported, as it may contain sensitive data.
+            let val = HeaderValue::from_str(val).map_err(|e| {
+                anyhow!("Invalid value of '{}' header: {}", name, e)
+            })?;
+            Ok::<_, anyhow::Error>((name, val))
+        })
+        .collect()
+}
+
+/// Displays the given HTTP `headers` for logging, with their values being
+/// redacted, as they may contain sensitive data (authorization tokens, for
+/// example).
+fn display_headers(headers: &HashMap<String, String>) -> String {
+    if headers.is_empty() {
+        return String::new();
+    }
+    let mut names: Vec<_> = headers.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    format!(
+        " with headers: {}: <redacted>",
+        names.join(": <redacted>, "),
+    )
+}
+
+#[cfg(test)]
+mod spec {
+    use std::{
+        io::{Read as _, Write as _},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn attaches_configured_headers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Imitate upstream server, which returns the received request.
+        let server = thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut req = Vec::new();
+            let mut buf = [0; 1024];
+            while !req.ends_with(b"\r\n\r\n") {
+                let n = conn.read(&mut buf).unwrap();
+                assert_ne!(n, 0, "connection closed unexpectedly");
+                req.extend_from_slice(&buf[..n]);
+            }
+            conn.write_all(
+                b"HTTP/1.1 200 OK\r\n\
+                  Content-Length: 2\r\n\
+                  Connection: close\r\n\r\nok",
+            )
+            .unwrap();
+            String::from_utf8(req).unwrap().to_lowercase()
+        });
+
+        let url = Url::parse(&format!("http://{}/video.mp4", addr)).unwrap();
+        let headers: HashMap<_, _> = vec![
+            ("Authorization".to_owned(), "Bearer secret".to_owned()),
+            ("X-Cdn-Token".to_owned(), "qwerty".to_owned()),
+        ]
+        .into_iter()
+        .collect();
+        let resp = Manager::request(&url, &headers).await.unwrap();
+        assert_eq!(resp.text().await.unwrap(), "ok");
+
+        let req = server.join().unwrap();
+        assert!(req.contains("authorization: bearer secret\r\n"), "{}", req);
+        assert!(req.contains("x-cdn-token: qwerty\r\n"), "{}", req);
+    }
+
+    #[test]
+    fn redacts_headers_values() {
+        let headers: HashMap<_, _> = vec![
+            ("Authorization".to_owned(), "Bearer secret".to_owned()),
+            ("X-Cdn-Token".to_owned(), "qwerty".to_owned()),
+        ]
+        .into_iter()
+        .collect();
+
+        let displayed = display_headers(&headers);
+
+        assert_eq!(
+            displayed,
+            " with headers: Authorization: <redacted>, \
+             X-Cdn-Token: <redacted>",
+        );
+        assert_eq!(display_headers(&HashMap::new()), "");
+    }
+
+    #[test]
+    fn disallows_invalid_headers() {
+        for (name, val) in &[("Bad Name", "value"), ("Name", "bad\nvalue")] {
+            let headers: HashMap<_, _> =
+                vec![((*name).to_owned(), (*val).to_owned())]
+                    .into_iter()
+                    .collect();
+            assert!(
+                parse_headers(&headers).is_err(),
+                "allows '{}: {}'",
+                name,
+                val,
+            );
+        }
+    }
+}
@@ -3,7 +3,8 @@
 use std::{path::Path, sync::Arc};
 
 use anyhow::anyhow;
-use chrono::Utc;
+use chrono::{FixedOffset as TimeZone, Utc};
+use isolang::Language;
 use tokio::{fs, io::AsyncReadExt as _, sync::RwLock};
 
 use super::{Playlist, PlaylistSlug, State};
@@ -56,18 +57,7 @@ impl Manager {
                 anyhow!("Failed to read '{}' file: {}", file.display(), e)
             })?;
 
-        let state = if contents.is_empty() {
-            State::default()
-        } else {
-            serde_json::from_slice(&contents).map_err(|e| {
-                anyhow!(
-                    "Failed to deserialize vod::meta::State read from \
-                     '{}' file: {}",
-                    file.display(),
-                    e,
-                )
-            })?
-        };
+        let state = Self::parse_state(&contents, file)?;
 
         Ok(Self {
             file: file.into(),
@@ -75,6 +65,53 @@ impl Manager {
         })
     }
 
+    /// Re-reads the [`State`] from this [`Manager::file`] and replaces the
+    /// current [`State`] with it.
+    ///
+    /// Intended for applying the [`State`] edited in the file out-of-band,
+    /// without restarting the server.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be read or contains broken [`State`]. The current
+    /// [`State`] remains untouched in such case.
+    pub async fn reload(&self) -> Result<(), anyhow::Error> {
+        let contents = fs::read(&*self.file).await.map_err(|e| {
+            anyhow!("Failed to read '{}' file: {}", self.file.display(), e)
+        })?;
+        let new = Self::parse_state(&contents, &self.file)?;
+
+        let mut state = self.state.write().await;
+        state.0 = new;
+        state.1 = state.1.checked_add(1).unwrap_or_default();
+
+        Ok(())
+    }
+
+    /// Parses [`State`] from the given `contents` read from the given `file`.
+    ///
+    /// Empty `contents` are considered as the default [`State`].
+    ///
+    /// # Errors
+    ///
+    /// If the `contents` contain broken [`State`].
+    fn parse_state(
+        contents: &[u8],
+        file: &Path,
+    ) -> Result<State, anyhow::Error> {
+        if contents.is_empty() {
+            return Ok(State::default());
+        }
+        serde_json::from_slice(contents).map_err(|e| {
+            anyhow!(
+                "Failed to deserialize vod::meta::State read from '{}' file: \
+                 {}",
+                file.display(),
+                e,
+            )
+        })
+    }
+
     /// Returns the copy of the current actual [`State`].
     #[inline]
     pub async fn state(&self) -> State {
@@ -212,6 +249,43 @@ impl Manager {
         Ok(())
     }
 
+    /// Clones the [`Playlist`] identified by the given `from` slug under the
+    /// given `slug`, optionally overriding its timezone and language.
+    ///
+    /// Returns `false` if [`Playlist`] with the given `slug` exists already,
+    /// or [`None`] if there is no [`Playlist`] with the `from` slug in the
+    /// current [`State`].
+    ///
+    /// # Errors
+    ///
+    /// If updated [`State`] fails to be persisted.
+    pub async fn clone_playlist(
+        &self,
+        from: &PlaylistSlug,
+        slug: PlaylistSlug,
+        tz: Option<TimeZone>,
+        lang: Option<Language>,
+    ) -> Result<Option<bool>, anyhow::Error> {
+        let mut state = self.state.write().await;
+
+        let playlist = match state.0.get(from) {
+            Some(pl) => pl.clone_as(slug, tz, lang),
+            None => return Ok(None),
+        };
+        if state.0.contains_key(&playlist.slug) {
+            return Ok(Some(false));
+        }
+
+        let mut new = state.0.clone();
+        let _ = new.insert(playlist.slug.clone(), playlist);
+        self.persist_state(&new).await?;
+
+        state.0 = new;
+        state.1 = state.1.checked_add(1).unwrap_or_default();
+
+        Ok(Some(true))
+    }
+
     /// Refreshes all [`Playlist::initial`] positions in the [`State`] managed
     /// by this [`Manager`].
     ///
@@ -289,3 +363,55 @@ impl Manager {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod spec {
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reloads_changed_file() {
+        let file = NamedTempFile::new().unwrap();
+        fs::copy("example.vod.meta.json", file.path())
+            .await
+            .expect("No example file found");
+        let manager = Manager::try_new(file.path()).await.unwrap();
+        let (orig, ver) = manager.state_and_version().await;
+        assert_eq!(orig.len(), 1, "unexpected example");
+
+        let mut changed = orig.clone();
+        let mut playlist = changed.values().next().unwrap().clone();
+        playlist.title = "Reloaded".into();
+        let _ = changed.insert(playlist.slug.clone(), playlist);
+        fs::write(file.path(), serde_json::to_vec(&changed).unwrap())
+            .await
+            .unwrap();
+
+        manager.reload().await.unwrap();
+
+        let (reloaded, new_ver) = manager.state_and_version().await;
+        assert_ne!(new_ver, ver, "version is not bumped");
+        assert_eq!(
+            reloaded.values().next().unwrap().title.as_str(),
+            "Reloaded",
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_state_on_broken_file() {
+        let file = NamedTempFile::new().unwrap();
+        fs::copy("example.vod.meta.json", file.path())
+            .await
+            .expect("No example file found");
+        let manager = Manager::try_new(file.path()).await.unwrap();
+        let (_, ver) = manager.state_and_version().await;
+
+        fs::write(file.path(), b"{broken").await.unwrap();
+
+        assert!(manager.reload().await.is_err(), "broken file is reloaded");
+        let (state, new_ver) = manager.state_and_version().await;
+        assert_eq!(new_ver, ver, "version is bumped");
+        assert_eq!(state.len(), 1, "state is changed");
+    }
+}
@@ -17,6 +17,8 @@ pub mod manager;
 use std::{
     borrow::Cow,
     collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    future::Future,
     time::Duration,
 };
 
@@ -27,13 +29,14 @@ use chrono::{
 };
 use derive_more::{Deref, DerefMut, Display, Into};
 use ephyr_serde::{timelike, timezone};
-use futures::{stream, StreamExt as _, TryFutureExt as _, TryStreamExt as _};
+use futures::{future, TryFutureExt as _};
 use isolang::Language;
 use mime::Mime;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Deserializer, Serialize};
 use smart_default::SmartDefault;
+use tokio::sync::Semaphore;
 use url::Url;
 
 use crate::{
@@ -53,22 +56,41 @@ pub struct State(HashMap<PlaylistSlug, Playlist>);
 impl State {
     /// Parses new [`State`] from the given API request.
     ///
+    /// All the [`Playlist`]s are parsed concurrently, while the total number of
+    /// concurrent requests to [`allatra::video::Api`] is bounded by the given
+    /// `limit`.
+    ///
+    /// Already resolved [`SrcUrl::local`]s of the `prev` [`State`] are
+    /// preserved (see [`Playlist::parse_request()`] for details).
+    ///
     /// # Errors
     ///
     /// If some [`Playlist`] fails to parse.
     pub async fn parse_request(
         req: api::vod::meta::Request,
+        prev: &State,
+        limit: &Semaphore,
     ) -> Result<Self, anyhow::Error> {
-        // We don't process each playlist concurrently to avoid performing too
-        // many concurrent requests to `allatra::video::Api`.
         Ok(Self(
-            stream::iter(req.into_iter())
-                .then(|(pl_slug, pl)| Playlist::parse_request(pl_slug, pl))
-                .map_ok(|pl| (pl.slug.clone(), pl))
-                .try_collect()
-                .await?,
+            future::try_join_all(req.into_iter().map(|(pl_slug, pl)| {
+                let prev = prev.get(&pl_slug);
+                Playlist::parse_request(pl_slug, pl, prev, limit)
+            }))
+            .await?
+            .into_iter()
+            .map(|pl| (pl.slug.clone(), pl))
+            .collect(),
         ))
     }
+
+    /// Summarizes this [`State`] for reporting a successfully validated
+    /// `vod-meta` server API request.
+    #[must_use]
+    pub fn summary(&self) -> api::vod::meta::Summary {
+        self.iter()
+            .map(|(slug, pl)| (slug.clone(), pl.summary()))
+            .collect()
+    }
 }
 
 /// Playlist of [`Clip`]s to be played for some audience.
@@ -107,6 +129,41 @@ pub struct Playlist {
     #[serde(default)]
     pub resolutions: HashSet<Resolution>,
 
+    /// Whitelist of [`Clip`]'s [`Resolution`]s allowed to be provided by this
+    /// [`Playlist`], if [`Playlist::resolutions`] are not specified.
+    ///
+    /// If empty then all available [`Clip`]'s [`Resolution`]s are allowed.
+    #[serde(default, skip_serializing_if = "HashSet::is_empty")]
+    pub allowed_resolutions: HashSet<Resolution>,
+
+    /// Policy of what to do with [`Clip`]s missing some of the
+    /// [`Playlist::allowed_resolutions`].
+    #[serde(default)]
+    pub resolution_fallback: ResolutionFallback,
+
+    /// Absolute time to align segments of this [`Playlist`] to, provided as
+    /// [`nginx::vod_module::mapping::Set::segment_base_time`].
+    ///
+    /// If [`None`] then no segment alignment is requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_base_time: Option<DateTime<Utc>>,
+
+    /// Type of the [`nginx::vod_module::mapping::Set`] produced for this
+    /// [`Playlist`].
+    ///
+    /// If [`None`] then [`nginx::vod_module::mapping::PlaylistType::Live`] is
+    /// used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playlist_type: Option<nginx::vod_module::mapping::PlaylistType>,
+
+    /// Indicator whether [`Clip`]s of this [`Playlist`] have different media
+    /// parameters, provided as
+    /// [`nginx::vod_module::mapping::Set::discontinuity`].
+    ///
+    /// If [`None`] then `true` is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discontinuity: Option<bool>,
+
     /// Initial position of this [`Playlist`] to start building
     /// [`nginx::vod_module::mapping`] schedule from.
     ///
@@ -119,6 +176,13 @@ pub struct Playlist {
     #[serde(default)]
     pub initial: Option<PlaylistInitialPosition>,
 
+    /// [`Clip`] to be looped through any [`Weekday`] having no [`Clip`]s to
+    /// be scheduled.
+    ///
+    /// If [`None`] then such [`Weekday`]s are not scheduled at all.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filler: Option<Clip>,
+
     /// [`Clip`]s which form this [`Playlist`], distributed by [`Weekday`]s.
     ///
     /// The total duration of all [`Clip`]s in the one [`Weekday`] hasn't to be
@@ -140,34 +204,75 @@ impl Playlist {
     #[must_use]
     pub fn mutual_resolutions(&self) -> HashSet<Resolution> {
         let mut mutual: Option<HashSet<Resolution>> = None;
-        for clips in self.clips.values() {
-            for clip in clips {
-                #[allow(clippy::option_if_let_else)]
-                if let Some(m) = &mut mutual {
-                    m.retain(|r| clip.sources.contains_key(r))
-                } else {
-                    mutual = Some(clip.sources.keys().copied().collect())
-                }
+        for clip in self.clips.values().flatten().chain(&self.filler) {
+            #[allow(clippy::option_if_let_else)]
+            if let Some(m) = &mut mutual {
+                m.retain(|r| clip.sources.contains_key(r))
+            } else {
+                mutual = Some(clip.sources.keys().copied().collect())
             }
         }
         mutual.unwrap_or_default()
     }
 
+    /// Returns [`Resolution`]s to schedule this [`Playlist`]'s [`Clip`]s with.
+    ///
+    /// If [`Playlist::resolutions`] are specified, then they are used "as is".
+    /// Otherwise, [`Playlist::allowed_resolutions`] are applied according to
+    /// the [`Playlist::resolution_fallback`] policy:
+    /// - [`ResolutionFallback::ReduceQuality`] uses only the
+    ///   [`Playlist::mutual_resolutions`] being allowed;
+    /// - [`ResolutionFallback::DropClip`] uses all the allowed
+    ///   [`Resolution`]s, so [`Clip`]s missing any of them should be skipped
+    ///   (see [`Clip::has_resolutions`]).
+    #[must_use]
+    pub fn scheduled_resolutions(&self) -> HashSet<Resolution> {
+        if !self.resolutions.is_empty() {
+            return self.resolutions.clone();
+        }
+        if self.allowed_resolutions.is_empty() {
+            // Because all `mapping::Set::sequences` must have the same length,
+            // we should define the minimal mutual intersection of all
+            // resolutions and use only them to form a `mapping::Set`.
+            return self.mutual_resolutions();
+        }
+        match self.resolution_fallback {
+            ResolutionFallback::ReduceQuality => {
+                let mut mutual = self.mutual_resolutions();
+                mutual.retain(|r| self.allowed_resolutions.contains(r));
+                mutual
+            }
+            ResolutionFallback::DropClip => self.allowed_resolutions.clone(),
+        }
+    }
+
     /// Parses new [`Playlist`] from the given `vod-meta` server API request.
     ///
+    /// All the [`Clip`]s are parsed concurrently, while the total number of
+    /// concurrent requests to [`allatra::video::Api`] is bounded by the given
+    /// `limit`.
+    ///
+    /// If the `prev` version of this [`Playlist`] is given, then its already
+    /// resolved [`SrcUrl::local`]s are carried over to the [`Src`]s with the
+    /// same [`SrcUrl::upstream`], so they don't require resolving from
+    /// [`file::cache`] once again.
+    ///
     /// # Errors
     ///
     /// - If [`Playlist`] has empty title.
     /// - If all [`Clip`]s in [`Playlist`] don't fit well into 24 hours.
-    /// - If any weekday doesn't have at least one clip.
+    /// - If any weekday doesn't have at least one clip, while no filler
+    ///   [`Clip`] is specified.
+    /// - If filler [`Clip`]'s duration is not a fraction of 24 hours.
     /// - If some [`Clip`] fails to parse.
+    /// - If [`Clip`]s have no [`Resolution`]s to be scheduled with (see
+    ///   [`NoMutualResolutions`]).
     pub async fn parse_request(
         slug: PlaylistSlug,
         req: api::vod::meta::Playlist,
+        prev: Option<&Playlist>,
+        limit: &Semaphore,
     ) -> Result<Self, anyhow::Error> {
-        // We limit concurrent requests to `allatra::video::Api` to avoid
-        // possible rate-limiting.
-        const CONCURRENT_REQUESTS: usize = 10;
         const SECS_IN_DAY: u64 = 86400;
 
         if req.title.is_empty() {
@@ -179,31 +284,98 @@ impl Playlist {
 
         let segment_duration = req.segment_duration.unwrap_or_default();
         let resolutions = &req.resolutions;
-        let clips =
-            stream::iter(req.clips.into_iter().flat_map(|(day, clips)| {
-                clips.into_iter().map(move |c| (day, c))
-            }))
-            .map(|(day, req)| {
-                Clip::parse_request(req, segment_duration, resolutions)
+        let mut clips = future::try_join_all(
+            req.clips
+                .into_iter()
+                .flat_map(|(day, clips)| {
+                    clips.into_iter().map(move |c| (day, c))
+                })
+                .map(|(day, req)| {
+                    Clip::parse_request(
+                        req,
+                        segment_duration,
+                        resolutions,
+                        limit,
+                    )
                     .map_ok(move |c| (day, c))
-            })
-            .buffered(CONCURRENT_REQUESTS)
-            .try_fold(
-                <HashMap<_, Vec<_>>>::new(),
-                |mut all, (day, clip)| async move {
-                    all.entry(day).or_default().push(clip);
-                    Ok(all)
-                },
-            )
-            .await?;
+                }),
+        )
+        .await?
+        .into_iter()
+        .fold(<HashMap<_, Vec<_>>>::new(), |mut all, (day, clip)| {
+            all.entry(day).or_default().push(clip);
+            all
+        });
+        let mut filler = match req.filler {
+            Some(req) => Some(
+                Clip::parse_request(req, segment_duration, resolutions, limit)
+                    .await?,
+            ),
+            None => None,
+        };
 
-        if clips.len() != 7 {
+        if clips.len() != 7 && filler.is_none() {
             return Err(anyhow!(
                 "Playlist '{}' should have all weekdays filled",
                 slug,
             ));
         }
+        if let Some(prev) = prev {
+            let locals: HashMap<_, _> = prev
+                .clips
+                .values()
+                .flatten()
+                .chain(&prev.filler)
+                .flat_map(|c| c.sources.values())
+                .filter_map(|s| Some((&s.url.upstream, s.url.local.as_ref()?)))
+                .collect();
+            for src in clips
+                .values_mut()
+                .flatten()
+                .chain(&mut filler)
+                .flat_map(|c| c.sources.values_mut())
+            {
+                src.url.local = locals.get(&src.url.upstream).copied().cloned();
+            }
+        }
+        let drop_clip_resolutions = (req.resolutions.is_empty()
+            && req.resolution_fallback == ResolutionFallback::DropClip)
+            .then(|| &req.allowed_resolutions)
+            .filter(|r| !r.is_empty());
+        if let Some(filler) = &filler {
+            if !drop_clip_resolutions
+                .map_or(true, |r| filler.has_resolutions(r))
+            {
+                return Err(anyhow!(
+                    "Filler clip '{}' of playlist '{}' misses some of the \
+                     allowed resolutions",
+                    filler.title,
+                    req.title,
+                ));
+            }
+            let duration = (filler.view.to - filler.view.from).as_secs();
+            if SECS_IN_DAY % duration != 0 {
+                return Err(anyhow!(
+                    "Duration of filler clip '{}' of playlist '{}' is not \
+                     fraction of 24 hours",
+                    filler.title,
+                    req.title,
+                ));
+            }
+        }
         for (weekday, clips) in &clips {
+            // Clips missing any allowed resolution won't be scheduled at all
+            // under the `ResolutionFallback::DropClip` policy, so shouldn't be
+            // considered in any checks.
+            let clips: Vec<_> = clips
+                .iter()
+                .filter(|c| {
+                    drop_clip_resolutions.map_or(true, |r| c.has_resolutions(r))
+                })
+                .collect();
+            if clips.is_empty() && filler.is_some() {
+                continue;
+            }
             if clips.is_empty() {
                 return Err(anyhow!(
                     "Day {} of playlist '{}' has no clips, but should have at \
@@ -232,18 +404,114 @@ impl Playlist {
             }
         }
 
-        Ok(Playlist {
+        let playlist = Playlist {
             slug,
             title: req.title,
             lang: req.lang,
             tz: req.tz,
             segment_duration,
             resolutions: req.resolutions,
+            allowed_resolutions: req.allowed_resolutions,
+            resolution_fallback: req.resolution_fallback,
+            segment_base_time: req.segment_base_time,
+            playlist_type: req.playlist_type,
+            discontinuity: req.discontinuity,
             initial: None,
+            filler,
+            clips,
+        };
+        playlist.check_scheduled_resolutions()?;
+        Ok(playlist)
+    }
+
+    /// Checks whether this [`Playlist`] has at least one [`Resolution`] to
+    /// schedule its [`Clip`]s with (see [`Playlist::scheduled_resolutions`]).
+    ///
+    /// Otherwise, [`Playlist::schedule_nginx_vod_module_set`] would produce a
+    /// [`nginx::vod_module::mapping::Set`] without any sequences.
+    ///
+    /// # Errors
+    ///
+    /// With [`NoMutualResolutions`] listing the available [`Resolution`]s of
+    /// each [`Clip`], if there are no [`Resolution`]s to schedule with.
+    pub fn check_scheduled_resolutions(
+        &self,
+    ) -> Result<(), NoMutualResolutions> {
+        if self.clips.is_empty() || !self.scheduled_resolutions().is_empty() {
+            return Ok(());
+        }
+        let mut clips: Vec<_> = self
+            .clips
+            .values()
+            .flatten()
+            .chain(&self.filler)
+            .map(|c| {
+                let mut sizes: Vec<_> = c.sources.keys().copied().collect();
+                sizes.sort_unstable();
+                (c.title.clone(), sizes)
+            })
+            .collect();
+        clips.sort();
+        clips.dedup();
+        Err(NoMutualResolutions {
+            playlist: self.title.clone(),
             clips,
         })
     }
 
+    /// Summarizes this [`Playlist`]'s [`Clip`]s count and durations by
+    /// [`Weekday`]s.
+    #[must_use]
+    pub fn summary(&self) -> api::vod::meta::PlaylistSummary {
+        api::vod::meta::PlaylistSummary {
+            clips: self.clips.values().map(Vec::len).sum(),
+            days: self
+                .clips
+                .iter()
+                .map(|(day, clips)| {
+                    let summary = api::vod::meta::DaySummary {
+                        clips: clips.len(),
+                        duration: clips
+                            .iter()
+                            .map(|c| c.view.to - c.view.from)
+                            .sum(),
+                    };
+                    (*day, summary)
+                })
+                .collect(),
+        }
+    }
+
+    /// Deep-copies this [`Playlist`] under the given new `slug`, optionally
+    /// overriding its timezone and language.
+    ///
+    /// All the [`Playlist::clips`] (along with their [`Src`]s) are preserved
+    /// "as is", while the [`Playlist::initial`] position is reset.
+    #[must_use]
+    pub fn clone_as(
+        &self,
+        slug: PlaylistSlug,
+        tz: Option<TimeZone>,
+        lang: Option<Language>,
+    ) -> Self {
+        Self {
+            slug,
+            title: self.title.clone(),
+            lang: lang.unwrap_or(self.lang),
+            tz: tz.unwrap_or(self.tz),
+            segment_duration: self.segment_duration,
+            resolutions: self.resolutions.clone(),
+            allowed_resolutions: self.allowed_resolutions.clone(),
+            resolution_fallback: self.resolution_fallback,
+            segment_base_time: self.segment_base_time,
+            playlist_type: self.playlist_type,
+            discontinuity: self.discontinuity,
+            initial: None,
+            filler: self.filler.clone(),
+            clips: self.clips.clone(),
+        }
+    }
+
     /// Inspects all [`Src`]s of this [`Playlist`] and fills them with
     /// information about [VOD] files available in the given `cache`.
     ///
@@ -256,29 +524,27 @@ impl Playlist {
         &mut self,
         cache: &file::cache::Manager,
     ) -> Result<(), anyhow::Error> {
-        for clips in self.clips.values_mut() {
-            for cl in clips.iter_mut() {
-                for src in cl.sources.values_mut() {
-                    if src.url.local.is_some() {
-                        continue;
-                    }
-                    if let Some(path) = cache
-                        .get_cached_path(&src.url.upstream)
-                        .await
-                        .map_err(|e| {
-                            anyhow!(
-                                "Failed to get cached file path for '{}' \
-                                 URL: {}",
-                                src.url.upstream,
-                                e,
-                            )
-                        })?
-                    {
-                        src.url.local = Some(Url::parse(&format!(
-                            "file:///{}",
-                            path.display(),
-                        ))?);
-                    }
+        for cl in self.clips.values_mut().flatten().chain(&mut self.filler) {
+            for src in cl.sources.values_mut() {
+                if src.url.local.is_some() {
+                    continue;
+                }
+                if let Some(path) = cache
+                    .get_cached_path(&src.url.upstream, &src.url.headers)
+                    .await
+                    .map_err(|e| {
+                        anyhow!(
+                            "Failed to get cached file path for '{}' \
+                             URL: {}",
+                            src.url.upstream,
+                            e,
+                        )
+                    })?
+                {
+                    src.url.local = Some(Url::parse(&format!(
+                        "file:///{}",
+                        path.display(),
+                    ))?);
                 }
             }
         }
@@ -311,7 +577,8 @@ impl Playlist {
     /// `count` limitation allows.
     ///
     /// Each day is fully filled with clips without any gaps (looping the
-    /// weekday's [`Clip`]s), if it has at least one [`Clip`].
+    /// weekday's [`Clip`]s), if it has at least one [`Clip`]. Otherwise, the
+    /// [`Playlist::filler`] is looped through the day, if any.
     ///
     /// All [`Clip`]s are scheduled in the [`Playlist`]'s timezone.
     ///
@@ -320,6 +587,10 @@ impl Playlist {
     /// without any gaps, basing on the provided [`PlaylistInitialPosition`] and
     /// updating it whenever it's feasible.
     ///
+    /// If [`Clip`]s of the currently playing day have been changed, so the
+    /// provided [`PlaylistInitialPosition`] doesn't match any [`Clip`]
+    /// boundary anymore, then it's recomputed against the new [`Clip`]s.
+    ///
     /// [1]: https://github.com/kaltura/nginx-vod-module
     /// [2]: crate::api::nginx::vod_module::mapping::Set::MAX_DURATIONS_LEN
     #[allow(clippy::too_many_lines)]
@@ -333,9 +604,12 @@ impl Playlist {
 
         let mut set = mapping::Set {
             id: Some(self.slug.clone().into()),
-            playlist_type: mapping::PlaylistType::Live,
-            discontinuity: true,
+            playlist_type: self
+                .playlist_type
+                .unwrap_or(mapping::PlaylistType::Live),
+            discontinuity: self.discontinuity.unwrap_or(true),
             segment_duration: Some(self.segment_duration.as_duration().into()),
+            segment_base_time: self.segment_base_time.map(Into::into),
             ..mapping::Set::default()
         };
 
@@ -343,14 +617,7 @@ impl Playlist {
             count = mapping::Set::MAX_DURATIONS_LEN;
         }
 
-        let resolutions = if self.resolutions.is_empty() {
-            // Because all `mapping::Set::sequences` must have the same length,
-            // we should define the minimal mutual intersection of all
-            // resolutions and use only them to form a `mapping::Set`.
-            self.mutual_resolutions()
-        } else {
-            self.resolutions.clone()
-        };
+        let resolutions = self.scheduled_resolutions();
         if resolutions.is_empty() {
             return set;
         }
@@ -387,7 +654,25 @@ impl Playlist {
             let day = start_time.date().and_hms(0, 0, 0);
             let next_day = day + DateDuration::days(1);
 
-            if let Some(day_clips) = self.clips.get(&day.weekday()) {
+            // Clips missing any of the scheduled resolutions are skipped, as
+            // all `mapping::Set::sequences` must have the same length.
+            let day_clips = self
+                .clips
+                .get(&day.weekday())
+                .map(|clips| {
+                    clips
+                        .iter()
+                        .filter(|c| c.has_resolutions(&resolutions))
+                        .collect::<Vec<_>>()
+                })
+                .filter(|c| !c.is_empty())
+                .or_else(|| {
+                    self.filler
+                        .as_ref()
+                        .filter(|c| c.has_resolutions(&resolutions))
+                        .map(|c| vec![c])
+                });
+            if let Some(day_clips) = day_clips {
                 let mut time = day;
 
                 // Unfortunately, nginx-vod-module loops the whole playlist
@@ -396,11 +681,26 @@ impl Playlist {
                 // without affecting next day's playlist, we need to repeat the
                 // playlist manually, until the next day comes.
                 'day_loop: while time < next_day {
-                    for clip in day_clips {
+                    for clip in &day_clips {
                         let clip_duration = clip.view.to - clip.view.from;
                         let next_time = time
                             + DateDuration::from_std(clip_duration).unwrap();
 
+                        // If the day's clips have been changed while it's
+                        // playing, then `self.initial` position may point into
+                        // the middle of some clip, as was computed against the
+                        // old clips. In such case we re-align the position to
+                        // the beginning of this clip, so neither indices nor
+                        // `clip_times` overlap with the ones of the next clips.
+                        if time < start_time && start_time < next_time {
+                            let elapsed =
+                                (start_time - time).to_std().unwrap().as_secs();
+                            segment_index = segment_index.saturating_sub(
+                                elapsed / segment_duration_secs,
+                            );
+                            start_time = time;
+                        }
+
                         // There is no sense to return clips, which have been
                         // already finished. Instead, we start from the first
                         // non-finished today's clip. This way we reserve more
@@ -478,6 +778,10 @@ impl Playlist {
         }
 
         set.sequences = sequences.into_iter().map(|(_, seq)| seq).collect();
+        // `mapping::Set::clip_times` are allowed for discontinuous `Set`s only.
+        if !set.discontinuity {
+            set.clip_times.clear();
+        }
         set
     }
 }
@@ -574,18 +878,24 @@ impl Clip {
     /// Parses new [`Clip`] from the given `vod-meta` server API request, with
     /// accordance to the given [`SegmentDuration`].
     ///
+    /// Request to [`allatra::video::Api`] is performed only once a permit of
+    /// the given `limit` is acquired.
+    ///
     /// # Errors
     ///
     /// - If [`Clip`] has empty title.
     /// - If incorrect [`Clip`]'s [YouTube] video URL is provided.
     /// - If [`Clip`] info cannot be retrieved from [`allatra::video::Api`].
-    /// - If [`Clip`]'s duration is incorrect.
+    /// - If [`Clip`]'s duration is incorrect (and cannot be auto-padded, if
+    ///   requested).
+    /// - If [`Clip`]'s HTTP headers are invalid.
     ///
     /// [YouTube]: https://youtube.com
     pub async fn parse_request(
         req: api::vod::meta::Clip,
         segment_duration: SegmentDuration,
         resolutions: &HashSet<Resolution>,
+        limit: &Semaphore,
     ) -> Result<Self, anyhow::Error> {
         if req.title.is_empty() {
             return Err(anyhow!(
@@ -594,6 +904,10 @@ impl Clip {
             ));
         }
 
+        let _ = file::cache::parse_headers(&req.headers).map_err(|e| {
+            anyhow!("Invalid headers provided for clip '{}': {}", req.title, e)
+        })?;
+
         let youtube_id = Self::parse_youtube_id(&req.url).map_err(|e| {
             anyhow!(
                 "Incorrect video link '{}' provided for clip '{}': {}",
@@ -603,49 +917,71 @@ impl Clip {
             )
         })?;
 
-        let resp = allatra::video::Api::get_videos_yt(&youtube_id)
-            .await
-            .map_err(|e| {
-                anyhow!(
+        let resp =
+            with_permit(limit, allatra::video::Api::get_videos_yt(&youtube_id))
+                .await
+                .map_err(|e| {
+                    anyhow!(
                     "Failed to retrieve info about clip '{}' by the provided \
                      URL '{}': {}",
                     req.title,
                     req.url,
                     e,
                 )
-            })?;
+                })?;
+
+        let mut from = req.from.resolve(resp.duration);
+        let mut to = req.to.resolve(resp.duration);
 
-        if req.from >= resp.duration {
+        if from >= resp.duration {
             return Err(anyhow!(
                 "Clip '{}' cannot start from {}, because video's total \
                  duration is {}",
                 req.title,
-                timelike::format(&req.from),
+                timelike::format(&from),
                 timelike::format(&resp.duration),
             ));
         }
-        if req.to > resp.duration {
+        if to > resp.duration {
             return Err(anyhow!(
                 "Clip '{}' cannot finish at {}, because video's total duration \
                  is {}",
                 req.title,
-                timelike::format(&req.to),
+                timelike::format(&to),
                 timelike::format(&resp.duration),
             ));
         }
-        if req.to.checked_sub(req.from).unwrap_or_default()
-            < Duration::from_secs(1)
-        {
+        if to.checked_sub(from).unwrap_or_default() < Duration::from_secs(1) {
             return Err(anyhow!(
                 "Clip '{}' should start before it ends at {}, but it starts \
                  from {}",
                 req.title,
-                timelike::format(&req.to),
-                timelike::format(&req.from),
+                timelike::format(&to),
+                timelike::format(&from),
             ));
         }
 
-        let clip_secs = (req.to - req.from).as_secs();
+        if req.auto_pad {
+            let (padded_from, padded_to) = Self::pad_to_segments(
+                from,
+                to,
+                resp.duration,
+                segment_duration,
+            )
+            .ok_or_else(|| {
+                anyhow!(
+                    "Clip '{}' cannot be padded to {} segment duration \
+                     within video's total duration {}",
+                    req.title,
+                    timelike::format(&segment_duration.as_duration()),
+                    timelike::format(&resp.duration),
+                )
+            })?;
+            from = padded_from;
+            to = padded_to;
+        }
+
+        let clip_secs = (to - from).as_secs();
         let segment_secs = segment_duration.as_duration().as_secs();
         if clip_secs % segment_secs != 0 {
             return Err(anyhow!(
@@ -665,6 +1001,7 @@ impl Clip {
                     url: SrcUrl {
                         upstream: source.src,
                         local: None,
+                        headers: req.headers.clone(),
                     },
                     mime_type: source.r#type,
                     size: source.size,
@@ -686,14 +1023,19 @@ impl Clip {
         Ok(Self {
             youtube_id,
             title: req.title,
-            view: ClipView {
-                from: req.from,
-                to: req.to,
-            },
+            view: ClipView { from, to },
             sources,
         })
     }
 
+    /// Indicates whether this [`Clip`] has sources of all the given
+    /// [`Resolution`]s.
+    #[inline]
+    #[must_use]
+    pub fn has_resolutions(&self, resolutions: &HashSet<Resolution>) -> bool {
+        resolutions.iter().all(|r| self.sources.contains_key(r))
+    }
+
     /// Validates whether the given [`Url`] is a correct [YouTube] video link
     /// and parses ID of the video from it.
     ///
@@ -724,6 +1066,36 @@ impl Clip {
             )
             .ok_or_else(|| anyhow!("YouTube URL should contain video ID"))
     }
+
+    /// Pads the given `from`-`to` time window to the nearest boundary of the
+    /// given [`SegmentDuration`], so its duration becomes divisible on it.
+    ///
+    /// Extends `to` forward if it fits into the given `total` duration of the
+    /// video, or extends `from` backward otherwise.
+    ///
+    /// Returns [`None`] if neither of the paddings fits into the video.
+    #[must_use]
+    pub fn pad_to_segments(
+        from: Duration,
+        to: Duration,
+        total: Duration,
+        segment_duration: SegmentDuration,
+    ) -> Option<(Duration, Duration)> {
+        let clip_secs = to.checked_sub(from)?.as_secs();
+        let segment_secs = segment_duration.as_duration().as_secs();
+
+        let rem = clip_secs % segment_secs;
+        if rem == 0 {
+            return Some((from, to));
+        }
+        let pad = Duration::from_secs(segment_secs - rem);
+
+        if to + pad <= total {
+            Some((from, to + pad))
+        } else {
+            from.checked_sub(pad).map(|from| (from, to))
+        }
+    }
 }
 
 /// Time window in a source file to play in a [`Clip`]. Also, defines duration
@@ -776,6 +1148,59 @@ pub struct SrcUrl {
     /// [VOD]: https://en.wikipedia.org/wiki/Video_on_demand
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub local: Option<Url>,
+
+    /// HTTP headers to be sent along with requests downloading the
+    /// [`SrcUrl::upstream`] file.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+}
+
+/// Error of a [`Playlist`] whose [`Clip`]s have no [`Resolution`]s to be
+/// scheduled with.
+#[derive(Clone, Debug)]
+pub struct NoMutualResolutions {
+    /// Title of the [`Playlist`].
+    pub playlist: String,
+
+    /// Titles of the [`Playlist`]'s [`Clip`]s along with their available
+    /// [`Resolution`]s.
+    pub clips: Vec<(String, Vec<Resolution>)>,
+}
+
+impl fmt::Display for NoMutualResolutions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Clips of playlist '{}' have no mutual resolution to be \
+             scheduled with:",
+            self.playlist,
+        )?;
+        for (title, sizes) in &self.clips {
+            let sizes: Vec<_> =
+                sizes.iter().map(|r| format!("{}p", *r as u16)).collect();
+            write!(f, " '{}' has [{}];", title, sizes.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NoMutualResolutions {}
+
+/// Policy of what to do with [`Clip`]s missing some of the
+/// [`Playlist::allowed_resolutions`].
+#[derive(
+    Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SmartDefault,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResolutionFallback {
+    /// Reduce quality of the whole [`Playlist`] to the [`Resolution`]s
+    /// provided by all its [`Clip`]s.
+    #[default]
+    ReduceQuality,
+
+    /// Skip [`Clip`]s missing any of the [`Playlist::allowed_resolutions`]
+    /// from the schedule, preserving quality of the whole [`Playlist`].
+    DropClip,
 }
 
 /// Duration of a [`Clip`]'s segment.
@@ -831,12 +1256,50 @@ impl<'de> Deserialize<'de> for SegmentDuration {
     }
 }
 
+/// Awaits the given `fut` only while holding a permit of the given `limit`.
+async fn with_permit<F: Future>(limit: &Semaphore, fut: F) -> F::Output {
+    let _permit = limit.acquire().await;
+    fut.await
+}
+
 #[cfg(test)]
 mod spec {
     use std::fs;
 
     use super::*;
 
+    mod with_permit {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use super::*;
+
+        #[tokio::test]
+        async fn never_exceeds_limit() {
+            let limit = Semaphore::new(4);
+            let (current, max) = (AtomicUsize::new(0), AtomicUsize::new(0));
+
+            let request = || async {
+                let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                let _ = max.fetch_max(now, Ordering::SeqCst);
+                for _ in 0..5 {
+                    tokio::task::yield_now().await;
+                }
+                let _ = current.fetch_sub(1, Ordering::SeqCst);
+            };
+            // Imitate several playlists with many clips each, being parsed
+            // concurrently.
+            let playlist = || {
+                future::join_all(
+                    (0..20).map(|_| super::with_permit(&limit, request())),
+                )
+            };
+            let _ = future::join3(playlist(), playlist(), playlist()).await;
+
+            assert_eq!(current.load(Ordering::SeqCst), 0);
+            assert_eq!(max.load(Ordering::SeqCst), 4);
+        }
+    }
+
     #[test]
     fn deserializes_example() {
         let serialized =
@@ -913,6 +1376,7 @@ mod spec {
                 req,
                 SegmentDuration::default(),
                 &HashSet::default(),
+                &Semaphore::new(10),
             )
             .await;
             assert!(res.is_ok(), "failed to parse: {}", res.unwrap_err());
@@ -960,6 +1424,7 @@ mod spec {
                     req,
                     SegmentDuration::default(),
                     &HashSet::default(),
+                    &Semaphore::new(10),
                 )
                 .await;
                 assert!(res.is_err(), "allows non-YouTube URL in: {}", json);
@@ -1007,11 +1472,64 @@ mod spec {
                     req,
                     SegmentDuration::default(),
                     &HashSet::default(),
+                    &Semaphore::new(10),
                 )
                 .await;
                 assert!(res.is_err(), "allows invalid duration in: {}", json);
             }
         }
+
+        #[test]
+        fn pads_to_segment_boundary() {
+            let secs = Duration::from_secs;
+            let seg = SegmentDuration::new(secs(10)).unwrap();
+
+            for (from, to, total, expected) in &[
+                (0, 60, 120, Some((0, 60))),
+                (0, 55, 120, Some((0, 60))),
+                (10, 115, 118, Some((5, 115))),
+                (0, 55, 55, None),
+                (10, 119, 120, Some((10, 120))),
+            ] {
+                let res = Clip::pad_to_segments(
+                    secs(*from),
+                    secs(*to),
+                    secs(*total),
+                    seg,
+                );
+                assert_eq!(
+                    res,
+                    expected.map(|(f, t)| (secs(f), secs(t))),
+                    "incorrect padding of {}-{} within {}",
+                    from,
+                    to,
+                    total,
+                );
+                if let Some((f, t)) = res {
+                    assert_eq!(
+                        (t - f).as_secs() % 10,
+                        0,
+                        "not segment-aligned"
+                    );
+                    assert!(t <= secs(*total), "exceeds video duration");
+                }
+            }
+        }
+
+        #[test]
+        fn disables_auto_pad_by_default() {
+            let req = serde_json::from_str::<api::vod::meta::Clip>(
+                r#"{
+                  "url": "https://www.youtube.com/watch?v=0wAtNWA93hM",
+                  "title": "Круг Жизни",
+                  "from": "00:00:00",
+                  "to": "00:00:55"
+                }"#,
+            )
+            .expect("Failed to deserialize request");
+
+            assert!(!req.auto_pad, "auto-pad is enabled by default");
+        }
     }
 
     mod playlist {
@@ -1109,7 +1627,13 @@ mod spec {
             )
             .expect("Failed to deserialize request");
 
-            let res = Playlist::parse_request(slug.clone(), req).await;
+            let res = Playlist::parse_request(
+                slug.clone(),
+                req,
+                None,
+                &Semaphore::new(10),
+            )
+            .await;
             assert!(res.is_ok(), "failed to parse: {}", res.unwrap_err());
 
             let pl = res.unwrap();
@@ -1122,6 +1646,124 @@ mod spec {
             assert_eq!(pl.clips.get(&Weekday::Mon).unwrap().len(), 2);
         }
 
+        #[test]
+        fn clones_under_new_slug() {
+            let serialized = fs::read("example.vod.meta.json")
+                .expect("No example file found");
+            let state = serde_json::from_slice::<State>(&serialized)
+                .expect("Failed to deserialize example");
+            let mut orig = state.values().next().unwrap().clone();
+            let _ = orig.schedule_nginx_vod_module_set(None, 1);
+            assert!(orig.initial.is_some(), "initial position is not set");
+
+            let slug = PlaylistSlug::new("cloned").unwrap();
+            let lang = Language::from_639_1("en").unwrap();
+            let cloned = orig.clone_as(
+                slug.clone(),
+                Some(TimeZone::west(3600)),
+                Some(lang),
+            );
+
+            assert_eq!(cloned.slug, slug);
+            assert_ne!(cloned.slug, orig.slug);
+            assert!(cloned.initial.is_none(), "initial position is not reset");
+            assert_eq!(cloned.tz, TimeZone::west(3600));
+            assert_eq!(cloned.lang, lang);
+            assert_eq!(&cloned.title, &orig.title);
+            assert_eq!(
+                serde_json::to_value(&cloned.clips).unwrap(),
+                serde_json::to_value(&orig.clips).unwrap(),
+            );
+        }
+
+        #[test]
+        fn disallows_disjoint_resolutions() {
+            let serialized = fs::read("example.vod.meta.json")
+                .expect("No example file found");
+            let state = serde_json::from_slice::<State>(&serialized)
+                .expect("Failed to deserialize example");
+            let mut pl = state.values().next().unwrap().clone();
+            assert!(
+                pl.check_scheduled_resolutions().is_ok(),
+                "disallows example",
+            );
+
+            let monday = pl.clips.get_mut(&Weekday::Mon).unwrap();
+            assert_eq!(monday.len(), 2, "unexpected example");
+            monday[0].sources.retain(|r, _| *r == Resolution::P1080);
+            monday[1].sources.retain(|r, _| *r == Resolution::P720);
+
+            let err = pl.check_scheduled_resolutions().unwrap_err();
+            let msg = err.to_string();
+            assert!(msg.contains("'Круг Жизни' has [1080p]"), "{}", msg);
+            assert!(msg.contains("'ПРАВДА ЖИЗНИ' has [720p]"), "{}", msg);
+        }
+
+        #[tokio::test]
+        async fn preserves_local_urls_of_prev() {
+            let slug = PlaylistSlug::new("life").unwrap();
+            let day = r#"[{
+              "url": "https://www.youtube.com/watch?v=0wAtNWA93hM",
+              "title": "Круг Жизни",
+              "from": "00:00:00",
+              "to": "0:30:00"
+            }]"#;
+            let json = format!(
+                r#"{{
+                  "title": "Передачи с Игорем Михайловичем",
+                  "lang": "rus",
+                  "tz": "+03:00",
+                  "clips": {{
+                    "mon": {day}, "tue": {day}, "wed": {day}, "thu": {day},
+                    "fri": {day}, "sat": {day}, "sun": {day}
+                  }}
+                }}"#,
+                day = day,
+            );
+            let req = || {
+                serde_json::from_str::<api::vod::meta::Playlist>(&json)
+                    .expect("Failed to deserialize request")
+            };
+            let limit = Semaphore::new(10);
+
+            let mut prev =
+                Playlist::parse_request(slug.clone(), req(), None, &limit)
+                    .await
+                    .expect("Failed to parse playlist");
+            for src in prev
+                .clips
+                .values_mut()
+                .flatten()
+                .flat_map(|c| c.sources.values_mut())
+            {
+                src.url.local = Some(
+                    Url::parse(&format!("file:///{}.mp4", src.size as u16))
+                        .unwrap(),
+                );
+            }
+
+            let res = Playlist::parse_request(
+                slug.clone(),
+                req(),
+                Some(&prev),
+                &limit,
+            )
+            .await;
+            assert!(res.is_ok(), "failed to parse: {}", res.unwrap_err());
+
+            let pl = res.unwrap();
+            for src in
+                pl.clips.values().flatten().flat_map(|c| c.sources.values())
+            {
+                assert_eq!(
+                    src.url.local.as_ref().map(Url::as_str),
+                    Some(format!("file:///{}.mp4", src.size as u16).as_str()),
+                    "local URL is not preserved for {}",
+                    src.url.upstream,
+                );
+            }
+        }
+
         #[tokio::test]
         async fn disallows_invalid_clip() {
             let slug = PlaylistSlug::new("life").unwrap();
@@ -1231,7 +1873,13 @@ mod spec {
                     serde_json::from_str::<api::vod::meta::Playlist>(&json)
                         .expect("Failed to deserialize request");
 
-                let res = Playlist::parse_request(slug.clone(), req).await;
+                let res = Playlist::parse_request(
+                    slug.clone(),
+                    req,
+                    None,
+                    &Semaphore::new(10),
+                )
+                .await;
                 assert!(res.is_err(), "allows invalid clip in value: {}", json);
             }
         }
@@ -1350,7 +1998,13 @@ mod spec {
                     serde_json::from_str::<api::vod::meta::Playlist>(&json)
                         .expect("Failed to deserialize request");
 
-                let res = Playlist::parse_request(slug.clone(), req).await;
+                let res = Playlist::parse_request(
+                    slug.clone(),
+                    req,
+                    None,
+                    &Semaphore::new(10),
+                )
+                .await;
                 assert!(
                     res.is_err(),
                     "allows non-24-hours fractioned total duration in: {}",
@@ -1558,7 +2212,13 @@ mod spec {
                     serde_json::from_str::<api::vod::meta::Playlist>(&json)
                         .expect("Failed to deserialize request");
 
-                let res = Playlist::parse_request(slug.clone(), req).await;
+                let res = Playlist::parse_request(
+                    slug.clone(),
+                    req,
+                    None,
+                    &Semaphore::new(10),
+                )
+                .await;
                 assert!(
                     res.is_err(),
                     "allows more than 24 hours total duration in: {}",
@@ -1665,7 +2325,13 @@ mod spec {
                     serde_json::from_str::<api::vod::meta::Playlist>(&json)
                         .expect("Failed to deserialize request");
 
-                let res = Playlist::parse_request(slug.clone(), req).await;
+                let res = Playlist::parse_request(
+                    slug.clone(),
+                    req,
+                    None,
+                    &Semaphore::new(10),
+                )
+                .await;
                 assert!(res.is_err(), "allows missing weekday in: {}", json);
             }
         }
@@ -1673,6 +2339,8 @@ mod spec {
         mod schedule {
             use chrono::TimeZone as _;
 
+            use crate::api::nginx::vod_module::mapping::PlaylistType;
+
             use super::*;
 
             #[tokio::test]
@@ -1736,9 +2404,14 @@ mod spec {
                 )
                 .expect("Failed to deserialize request");
 
-                let mut pl = Playlist::parse_request(slug.clone(), req)
-                    .await
-                    .expect("Failed to parse playlist");
+                let mut pl = Playlist::parse_request(
+                    slug.clone(),
+                    req,
+                    None,
+                    &Semaphore::new(10),
+                )
+                .await
+                .expect("Failed to parse playlist");
 
                 // Prefill initial position.
                 let at = Utc.ymd(2020, 9, 12).and_hms(22, 0, 0);
@@ -1779,6 +2452,234 @@ mod spec {
                     Utc.ymd(2020, 9, 13).and_hms(22, 2, 0).into(),
                 );
             }
+
+            #[test]
+            fn realigns_initial_position_on_mid_day_edit() {
+                let serialized = fs::read("example.vod.meta.json")
+                    .expect("No example file found");
+                let state = serde_json::from_slice::<State>(&serialized)
+                    .expect("Failed to deserialize example");
+                let mut pl = state.values().next().unwrap().clone();
+
+                // Monday's clips of 60 and 120 seconds are playing, so 13:00
+                // in the playlist's timezone is a boundary of the first clip.
+                let at = Utc.ymd(2020, 9, 14).and_hms(10, 1, 30);
+                let _ = pl.schedule_nginx_vod_module_set(Some(at), 3);
+                let old = pl.initial.expect("initial position is not set");
+                assert_eq!(old.at, Utc.ymd(2020, 9, 14).and_hms(10, 0, 0));
+
+                // Prolong Monday's first clip up to 90 seconds, so 13:00 falls
+                // into the middle of the second clip (12:59:30 - 13:01:30).
+                let monday = pl.clips.get_mut(&Weekday::Mon).unwrap();
+                assert_eq!(monday.len(), 2, "unexpected example");
+                monday[0].view.to =
+                    monday[0].view.from + Duration::from_secs(90);
+
+                let at = Utc.ymd(2020, 9, 14).and_hms(10, 1, 20);
+                let schedule = pl.schedule_nginx_vod_module_set(Some(at), 4);
+
+                assert_eq!(schedule.initial_clip_index, Some(old.clip_index));
+                assert_eq!(
+                    schedule.initial_segment_index,
+                    Some(old.segment_index - 3),
+                );
+                assert_eq!(
+                    pl.initial,
+                    Some(PlaylistInitialPosition {
+                        clip_index: old.clip_index,
+                        segment_index: old.segment_index - 3,
+                        at: Utc.ymd(2020, 9, 14).and_hms(9, 59, 30),
+                    }),
+                );
+
+                assert_eq!(schedule.clip_times.len(), 4);
+                assert_eq!(
+                    schedule.clip_times[0],
+                    Utc.ymd(2020, 9, 14).and_hms(9, 59, 30).into(),
+                );
+                for (i, pair) in schedule.clip_times.windows(2).enumerate() {
+                    let (start, next): (DateTime<Utc>, DateTime<Utc>) =
+                        (pair[0].clone().into(), pair[1].clone().into());
+                    let duration = Duration::from(schedule.durations[i]);
+                    assert_eq!(
+                        start + DateDuration::from_std(duration).unwrap(),
+                        next,
+                        "clip_times overlap or have gaps",
+                    );
+                }
+            }
+
+            #[test]
+            fn loops_filler_through_empty_weekday() {
+                let serialized = fs::read("example.vod.meta.json")
+                    .expect("No example file found");
+                let state = serde_json::from_slice::<State>(&serialized)
+                    .expect("Failed to deserialize example");
+                let mut pl = state.values().next().unwrap().clone();
+
+                let filler = pl.clips[&Weekday::Mon][0].clone();
+                assert_eq!(
+                    filler.view.to - filler.view.from,
+                    Duration::from_secs(60),
+                    "unexpected example",
+                );
+                let _ = pl.clips.remove(&Weekday::Tue);
+                pl.filler = Some(filler.clone());
+
+                // Tuesday in the playlist's timezone.
+                let at = Utc.ymd(2020, 9, 15).and_hms(10, 0, 0);
+                let schedule = pl.schedule_nginx_vod_module_set(Some(at), 4);
+
+                assert_eq!(
+                    schedule
+                        .durations
+                        .iter()
+                        .map(|d| Duration::from(*d).as_secs())
+                        .collect::<Vec<_>>(),
+                    vec![60, 60, 60, 60],
+                );
+                assert_eq!(
+                    schedule.clip_times[0],
+                    Utc.ymd(2020, 9, 15).and_hms(9, 59, 0).into(),
+                );
+                assert!(!schedule.sequences.is_empty());
+                for seq in &schedule.sequences {
+                    assert_eq!(seq.clips.len(), 4);
+                    assert!(
+                        seq.clips.windows(2).all(|c| c[0] == c[1]),
+                        "filler is not looped",
+                    );
+                }
+            }
+
+            /// Returns the example [`Playlist`], which Monday's second clip
+            /// misses 1080p resolution, with the given
+            /// [`ResolutionFallback`] policy allowing 1080p and 720p only.
+            fn mixed_resolutions_playlist(
+                fallback: ResolutionFallback,
+            ) -> Playlist {
+                let serialized = fs::read("example.vod.meta.json")
+                    .expect("No example file found");
+                let state = serde_json::from_slice::<State>(&serialized)
+                    .expect("Failed to deserialize example");
+                let mut pl = state.values().next().unwrap().clone();
+
+                let monday = pl.clips.get_mut(&Weekday::Mon).unwrap();
+                assert_eq!(monday.len(), 2, "unexpected example");
+                let _ = monday[1].sources.remove(&Resolution::P1080);
+
+                pl.allowed_resolutions =
+                    vec![Resolution::P1080, Resolution::P720]
+                        .into_iter()
+                        .collect();
+                pl.resolution_fallback = fallback;
+                pl
+            }
+
+            #[test]
+            fn reduces_quality_for_mixed_resolutions() {
+                let mut pl = mixed_resolutions_playlist(
+                    ResolutionFallback::ReduceQuality,
+                );
+
+                // Monday in the playlist's timezone.
+                let at = Utc.ymd(2020, 9, 14).and_hms(10, 0, 0);
+                let schedule = pl.schedule_nginx_vod_module_set(Some(at), 4);
+
+                assert_eq!(schedule.sequences.len(), 1);
+                assert_eq!(
+                    schedule.sequences[0].label.as_deref(),
+                    Some("720p")
+                );
+                assert_eq!(schedule.sequences[0].clips.len(), 4);
+                assert_eq!(
+                    schedule
+                        .durations
+                        .iter()
+                        .map(|d| Duration::from(*d).as_secs())
+                        .collect::<Vec<_>>(),
+                    vec![60, 120, 60, 120],
+                );
+            }
+
+            #[test]
+            fn drops_clips_missing_allowed_resolutions() {
+                let mut pl =
+                    mixed_resolutions_playlist(ResolutionFallback::DropClip);
+
+                // Monday in the playlist's timezone.
+                let at = Utc.ymd(2020, 9, 14).and_hms(10, 0, 0);
+                let schedule = pl.schedule_nginx_vod_module_set(Some(at), 4);
+
+                assert_eq!(schedule.sequences.len(), 2);
+                for seq in &schedule.sequences {
+                    assert_eq!(seq.clips.len(), 4);
+                }
+                assert_eq!(
+                    schedule
+                        .durations
+                        .iter()
+                        .map(|d| Duration::from(*d).as_secs())
+                        .collect::<Vec<_>>(),
+                    vec![120, 120, 120, 120],
+                );
+            }
+
+            #[test]
+            fn provides_segment_base_time() {
+                let serialized = fs::read("example.vod.meta.json")
+                    .expect("No example file found");
+                let state = serde_json::from_slice::<State>(&serialized)
+                    .expect("Failed to deserialize example");
+                let mut pl = state.values().next().unwrap().clone();
+                let at = Utc.ymd(2020, 9, 14).and_hms(10, 0, 0);
+
+                let set = serde_json::to_value(
+                    pl.clone().schedule_nginx_vod_module_set(Some(at), 2),
+                )
+                .unwrap();
+                assert!(set.get("segmentBaseTime").is_none());
+
+                pl.segment_base_time =
+                    Some(Utc.ymd(2020, 9, 1).and_hms(0, 0, 0));
+                let set = serde_json::to_value(
+                    pl.schedule_nginx_vod_module_set(Some(at), 2),
+                )
+                .unwrap();
+                assert_eq!(set["segmentBaseTime"], 1_598_918_400_000_i64);
+            }
+
+            #[test]
+            fn provides_vod_playlist_type() {
+                let serialized = fs::read("example.vod.meta.json")
+                    .expect("No example file found");
+                let state = serde_json::from_slice::<State>(&serialized)
+                    .expect("Failed to deserialize example");
+                let mut pl = state.values().next().unwrap().clone();
+                let at = Utc.ymd(2020, 9, 14).and_hms(10, 0, 0);
+
+                let set = pl.clone().schedule_nginx_vod_module_set(Some(at), 2);
+                assert_eq!(set.playlist_type, PlaylistType::Live);
+                assert!(set.discontinuity);
+                assert_eq!(set.clip_times.len(), 2);
+
+                pl.playlist_type = Some(PlaylistType::Vod);
+                pl.discontinuity = Some(false);
+                let json = serde_json::to_string(&pl)
+                    .expect("Failed to serialize playlist");
+                let mut pl = serde_json::from_str::<Playlist>(&json)
+                    .expect("Failed to deserialize playlist");
+                assert_eq!(pl.playlist_type, Some(PlaylistType::Vod));
+                assert_eq!(pl.discontinuity, Some(false));
+
+                let set = serde_json::to_value(
+                    pl.schedule_nginx_vod_module_set(Some(at), 2),
+                )
+                .unwrap();
+                assert_eq!(set["playlistType"], "vod");
+                assert_eq!(set["discontinuity"], false);
+                assert!(set["clipTimes"].as_array().unwrap().is_empty());
+            }
         }
     }
 }